@@ -0,0 +1,78 @@
+use crate::image::{ImageEncoder, Raster, bands};
+use std::io;
+use std::io::{BufWriter, Write};
+
+/// Size of the file header and of the `BITMAPINFOHEADER` preceding the pixels.
+const HEADERS_SIZE: u32 = 14 + 40;
+
+/// Uncompressed 24 bits BMP image of a canvas. The transparency of its pixels is lost.
+pub struct BmpImage<'c> {
+    raster: &'c dyn Raster,
+}
+
+impl ImageEncoder for BmpImage<'_> {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(writer);
+        let (width, height) = (self.raster.width() as u32, self.raster.height() as u32);
+        // rows are padded to a multiple of 4 bytes
+        let row_size = (width * 3).div_ceil(4) * 4;
+        let padding = vec![0; (row_size - width * 3) as usize];
+
+        writer.write_all(b"BM")?;
+        writer.write_all(&(HEADERS_SIZE + row_size * height).to_le_bytes())?;
+        writer.write_all(&[0; 4])?;
+        writer.write_all(&HEADERS_SIZE.to_le_bytes())?;
+
+        writer.write_all(&40u32.to_le_bytes())?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // planes
+        writer.write_all(&24u16.to_le_bytes())?; // bits per pixel
+        writer.write_all(&0u32.to_le_bytes())?; // no compression
+        writer.write_all(&(row_size * height).to_le_bytes())?;
+        writer.write_all(&2835u32.to_le_bytes())?; // 72 DPI, horizontally
+        writer.write_all(&2835u32.to_le_bytes())?; // and vertically
+        writer.write_all(&0u32.to_le_bytes())?; // colors in the palette
+        writer.write_all(&0u32.to_le_bytes())?; // important colors
+
+        // rows are stored from the bottom one up, with pixels in blue, green, red order
+        for rows in bands(self.raster).rev() {
+            let band = self.raster.band(rows.clone());
+            for y in rows.rev() {
+                for x in 0..band.width {
+                    let (r, g, b, _) = band.get(x, y).as_rgba();
+                    writer.write_all(&[b, g, r])?;
+                }
+                writer.write_all(&padding)?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+impl<'c, R: Raster> From<&'c R> for BmpImage<'c> {
+    fn from(value: &'c R) -> Self {
+        Self { raster: value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::domain::Color;
+
+    #[test]
+    fn test_write_to() {
+        let mut canvas = Canvas::new(1, 2);
+        canvas.set(0, 0, Color::Red);
+
+        let mut bmp = Vec::new();
+        BmpImage::from(&canvas).write_to(&mut bmp).unwrap();
+
+        assert_eq!(bmp.len(), 62);
+        assert_eq!(&bmp[..6], b"BM\x3e\x00\x00\x00");
+        assert_eq!(&bmp[18..26], &[1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(&bmp[54..], &[255, 255, 255, 0, 0, 0, 255, 0]);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::domain::{Blueprint, Color, Edge, LineStyle, Point, Shape, SourceSpan};
+use std::mem;
+
+/// Builds a blueprint by moving a pen, without going through its source:
+///
+/// ```
+/// use blueprint_core::builder::BlueprintBuilder;
+/// use blueprint_core::domain::Color;
+///
+/// let blueprint = BlueprintBuilder::new()
+///     .move_to(0., 0.)
+///     .tag("p0")
+///     .color(Color::Red)
+///     .line_to(10., 0.)
+///     .line_by(0., 10.)
+///     .build();
+/// assert_eq!(blueprint.shapes_iter().count(), 1);
+/// ```
+///
+/// As with blueprint sources, attributes apply to the edges drawn after they are set, and the
+/// built blueprint is translated to have its top-left corner at the origin.
+#[derive(Debug, Clone)]
+pub struct BlueprintBuilder {
+    blueprint: Blueprint,
+    /// Edges drawn since the pen was last lifted.
+    edges: Vec<Edge>,
+    position: Point,
+    color: Color,
+    width: f32,
+    style: LineStyle,
+    layer: Option<usize>,
+    span: SourceSpan,
+}
+
+impl BlueprintBuilder {
+    pub fn new() -> Self {
+        Self {
+            blueprint: Blueprint::default(),
+            edges: Vec::new(),
+            position: Point::default(),
+            color: Color::default(),
+            width: 1.,
+            style: LineStyle::default(),
+            layer: None,
+            span: SourceSpan::default(),
+        }
+    }
+
+    /// Lifts the pen to `x,y`, starting a new shape.
+    pub fn move_to(mut self, x: f32, y: f32) -> Self {
+        self.end_shape();
+        self.position = Point::new(x, y);
+        self
+    }
+
+    /// Lifts the pen by `dx,dy`, starting a new shape.
+    pub fn move_by(self, dx: f32, dy: f32) -> Self {
+        let to = self.position.add(dx, dy);
+        self.move_to(to.x, to.y)
+    }
+
+    /// Draws an edge to `x,y`.
+    pub fn line_to(mut self, x: f32, y: f32) -> Self {
+        let to = Point::new(x, y);
+        let edge = Edge::new_from_points(self.position, to, self.color, self.span)
+            .with_width(self.width)
+            .with_style(self.style)
+            .with_layer(self.layer);
+        self.edges.push(edge);
+        self.position = to;
+        self
+    }
+
+    /// Draws an edge by `dx,dy`.
+    pub fn line_by(self, dx: f32, dy: f32) -> Self {
+        let to = self.position.add(dx, dy);
+        self.line_to(to.x, to.y)
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Stroke width, in blueprint units.
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn style(mut self, style: LineStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Puts the edges in the layer named `name`, or in none when `None`.
+    pub fn layer(mut self, name: Option<&str>) -> Self {
+        self.layer = name.map(|name| self.blueprint.layer(name));
+        self
+    }
+
+    /// Location of the source code the edges are created from, for the ones built from a source.
+    pub fn span(mut self, span: SourceSpan) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Tags the position of the pen.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.blueprint.tag(tag.to_string(), self.position);
+        self
+    }
+
+    /// Current position of the pen.
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn build(mut self) -> Blueprint {
+        self.end_shape();
+        self.blueprint.translate_to_origin();
+        self.blueprint
+    }
+
+    fn end_shape(&mut self) {
+        if !self.edges.is_empty() {
+            self.blueprint.push(Shape::from(mem::take(&mut self.edges)));
+        }
+    }
+}
+
+impl Default for BlueprintBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let blueprint = BlueprintBuilder::new()
+            .move_to(-10., 0.)
+            .tag("start")
+            .layer(Some("walls"))
+            .style(LineStyle::Dashed)
+            .line_by(10., 0.)
+            .line_by(0., 5.)
+            .move_by(5., 0.)
+            .layer(None)
+            .color(Color::Blue)
+            .width(2.)
+            .line_to(5., 10.)
+            .build();
+
+        let shapes = blueprint.shapes_iter().collect::<Vec<_>>();
+        assert_eq!(shapes.len(), 2);
+        let edges = shapes[0].edges_iter().collect::<Vec<_>>();
+        assert_eq!(edges[0].from, Point::new(0., 0.));
+        assert_eq!(edges[1].to, Point::new(10., 5.));
+        assert_eq!(edges[1].dash_offset, 10.);
+        assert_eq!(edges[1].layer, Some(0));
+
+        let edge = shapes[1].edges_iter().next().unwrap();
+        assert_eq!(edge.from, Point::new(15., 5.));
+        assert_eq!(edge.to, Point::new(15., 10.));
+        assert_eq!(
+            (edge.color, edge.width, edge.layer),
+            (Color::Blue, 2., None)
+        );
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("start", &Point::new(0., 0.))]
+        );
+    }
+}
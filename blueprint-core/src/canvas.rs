@@ -0,0 +1,348 @@
+use crate::domain::{Blueprint, Bound, Color, Draw, Point};
+use crate::font;
+use std::ops::Range;
+
+/// Band of rows of an image, holding the pixels of these rows only. Drawing outside of the band
+/// is ignored.
+#[derive(Clone)]
+pub struct Canvas {
+    pub(crate) width: usize,
+    /// Height of the whole image.
+    pub(crate) height: usize,
+    /// First row of the band.
+    pub(crate) top: usize,
+    pub(crate) pixels: Vec<Color>,
+    /// Color of the pixels nothing is drawn on.
+    pub(crate) background: Color,
+}
+
+impl From<Blueprint> for Canvas {
+    fn from(blueprint: Blueprint) -> Self {
+        // an empty blueprint is drawn on a single pixel
+        let (_, bottom_right) = blueprint.boundaries().unwrap_or_default();
+        let (width, height) = (bottom_right.x, bottom_right.y);
+        let mut canvas = Canvas::new((width + 1.).ceil() as usize, (height + 1.).ceil() as usize);
+        blueprint.draw(&mut canvas);
+
+        canvas
+    }
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::new_band(width, height, 0..height)
+    }
+
+    pub fn new_band(width: usize, height: usize, rows: Range<usize>) -> Self {
+        debug_assert!(rows.end <= height, "band: {} > {}", rows.end, height);
+        Self {
+            width,
+            height,
+            top: rows.start,
+            pixels: vec![Color::White; width * rows.len()],
+            background: Color::White,
+        }
+    }
+
+    /// Rows held by the canvas.
+    pub fn rows(&self) -> Range<usize> {
+        self.top
+            ..self.top
+                + self
+                    .pixels
+                    .len()
+                    .checked_div(self.width)
+                    .unwrap_or_default()
+    }
+
+    /// Clears the canvas with the `background` color.
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.pixels.fill(background);
+        self.background = background;
+        self
+    }
+
+    /// Maps every pixel to black or to the background, for devices printing in black only. With
+    /// `dither`, the lighter a pixel, the fewer of its neighbours are black, following a 4×4
+    /// ordered dithering matrix; without it, all the pixels not of the background are black.
+    pub(crate) fn monochrome(mut self, dither: bool) -> Self {
+        const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+        for (i, pixel) in self.pixels.iter_mut().enumerate() {
+            if *pixel == self.background {
+                continue;
+            }
+
+            let (r, g, b, a) = pixel.as_rgba();
+            let black = if dither {
+                // luminance of the pixel over a white background
+                let alpha = a as f32 / 255.;
+                let luminance = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) * alpha
+                    + 255. * (1. - alpha);
+                let (x, y) = (i % self.width, self.top + i / self.width);
+                luminance < (BAYER[y % 4][x % 4] as f32 + 0.5) * 16.
+            } else {
+                a > 0
+            };
+            *pixel = if black { Color::Black } else { self.background };
+        }
+
+        self
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        debug_assert!(x < self.width, "set width: {} >= {}", x, self.width);
+        debug_assert!(y < self.height, "set height: {} >= {}", y, self.height);
+
+        if self.rows().contains(&y) {
+            self.pixels[x + (y - self.top) * self.width] = color;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        debug_assert!(x < self.width, "get width: {} >= {}", x, self.width);
+        debug_assert!(
+            self.rows().contains(&y),
+            "get rows: {} not in {:?}",
+            y,
+            self.rows()
+        );
+        self.pixels[x + (y - self.top) * self.width]
+    }
+
+    /// Writes `text` with its top left corner at `x`,`y`, each pixel of the glyphs being a square
+    /// of `scale` pixels. Characters without a glyph are left blank.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, scale: usize, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            let Some(rows) = font::glyph(c) else {
+                continue;
+            };
+            let left = x + i * (font::GLYPH_WIDTH + 1) * scale;
+
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    for py in y + row * scale..y + (row + 1) * scale {
+                        for px in left + column * scale..left + (column + 1) * scale {
+                            if px < self.width && py < self.height {
+                                self.set(px, py, color);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the `rows` of the canvas in a new band.
+    pub(crate) fn crop_rows(&self, rows: Range<usize>) -> Self {
+        let start = (rows.start - self.top) * self.width;
+        let end = (rows.end - self.top) * self.width;
+        Self {
+            top: rows.start,
+            pixels: self.pixels[start..end].to_vec(),
+            ..*self
+        }
+    }
+
+    /// Fills the polygon whose vertices are `points` using the even-odd rule, so that
+    /// self-intersecting polygons and holes are handled. A pixel is filled when its coordinates
+    /// are inside the polygon.
+    pub fn fill_polygon(&mut self, points: &[Point], color: Color) {
+        if points.len() < 3 || self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let (min_y, max_y) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+            (min.min(p.y), max.max(p.y))
+        });
+        if max_y < 0. {
+            return;
+        }
+        let min_y = min_y.ceil().max(0.) as usize;
+        let max_y = (max_y.floor() as usize).min(self.height - 1);
+
+        let mut crossings = Vec::with_capacity(points.len());
+        for y in min_y..=max_y {
+            let scanline = y as f32;
+
+            crossings.clear();
+            for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                // an edge covers the scanlines in [min(a.y, b.y), max(a.y, b.y)[, so that
+                // vertices shared by two edges are only counted once
+                if (a.y <= scanline) != (b.y <= scanline) {
+                    crossings.push(a.x + (scanline - a.y) * (b.x - a.x) / (b.y - a.y));
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                if span[1] < 0. {
+                    continue;
+                }
+                let from = span[0].ceil().max(0.) as usize;
+                let to = (span[1].floor() as usize).min(self.width - 1);
+                for x in from..=to {
+                    self.set(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Shrinks the canvas `factor` times in both directions, each pixel being the average of the
+    /// pixels it replaces. The first row of the band must be a multiple of `factor`.
+    pub(crate) fn downsample(self, factor: usize) -> Self {
+        if factor <= 1 {
+            return self;
+        }
+
+        let rows = self.rows();
+        debug_assert!(
+            rows.start.is_multiple_of(factor),
+            "band top: {}",
+            rows.start
+        );
+        let mut canvas = Canvas::new_band(
+            self.width.div_ceil(factor),
+            self.height.div_ceil(factor),
+            rows.start / factor..rows.end.div_ceil(factor),
+        )
+        .with_background(self.background);
+        for y in canvas.rows() {
+            for x in 0..canvas.width {
+                let block = (y * factor..((y + 1) * factor).min(rows.end)).flat_map(|y| {
+                    (x * factor..((x + 1) * factor).min(self.width)).map(move |x| (x, y))
+                });
+
+                let first = self.get(x * factor, y * factor);
+                let (mut sum, mut count, mut uniform) = ([0u64; 4], 0, true);
+                for (x, y) in block {
+                    let color = self.get(x, y);
+                    // channels are weighted by the alpha, so that transparent pixels do not darken
+                    // the result
+                    let (r, g, b, a) = color.as_rgba();
+                    let a = a as u64;
+                    for (sum, channel) in
+                        sum.iter_mut()
+                            .zip([r as u64 * a, g as u64 * a, b as u64 * a, a])
+                    {
+                        *sum += channel;
+                    }
+                    count += 1;
+                    uniform &= color == first;
+                }
+
+                let color = if uniform {
+                    first
+                } else {
+                    let [r, g, b, a] = sum;
+                    let channel = |sum: u64| sum.checked_div(a).unwrap_or_default() as u8;
+                    Color::Custom((channel(r), channel(g), channel(b), (a / count) as u8))
+                };
+                canvas.set(x, y, color);
+            }
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        (0..canvas.height)
+            .map(|y| {
+                (0..canvas.width)
+                    .map(|x| match canvas.get(x, y) {
+                        Color::White => '.',
+                        _ => '#',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        let mut canvas = Canvas::new(6, 5);
+        canvas.fill_polygon(
+            &[
+                Point::new(1., 0.),
+                Point::new(4., 0.),
+                Point::new(4., 3.),
+                Point::new(1., 3.),
+            ],
+            Color::Black,
+        );
+        assert_eq!(
+            rows(&canvas),
+            vec![".####.", ".####.", ".####.", "......", "......"]
+        );
+
+        // a pentagram: the even-odd rule leaves its center empty
+        let mut canvas = Canvas::new(11, 11);
+        canvas.fill_polygon(
+            &[
+                Point::new(5., 0.),
+                Point::new(8., 10.),
+                Point::new(0., 4.),
+                Point::new(10., 4.),
+                Point::new(2., 10.),
+            ],
+            Color::Black,
+        );
+        assert_eq!(canvas.get(5, 6), Color::White);
+        assert_eq!(canvas.get(5, 2), Color::Black);
+
+        // out of bounds parts are ignored
+        let mut canvas = Canvas::new(3, 3);
+        canvas.fill_polygon(
+            &[
+                Point::new(-5., -5.),
+                Point::new(1., -5.),
+                Point::new(1., 10.),
+            ],
+            Color::Black,
+        );
+        assert_eq!(rows(&canvas), vec!["##.", "##.", "##."]);
+    }
+
+    #[test]
+    fn test_monochrome() {
+        let gray = Color::Custom((128, 128, 128, 255));
+        let mut canvas = Canvas::new(4, 4);
+        for y in 0..4 {
+            canvas.set(0, y, Color::Yellow);
+            canvas.set(1, y, gray);
+            canvas.set(2, y, Color::Black);
+        }
+
+        assert_eq!(
+            rows(&canvas.clone().monochrome(false)),
+            vec!["###.", "###.", "###.", "###."]
+        );
+        // half of the gray pixels are black, and few of the yellow ones
+        assert_eq!(
+            rows(&canvas.monochrome(true)),
+            vec![".##.", "..#.", ".##.", "#.#."]
+        );
+    }
+
+    #[test]
+    fn test_downsample() {
+        let mut canvas = Canvas::new(5, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0)] {
+            canvas.set(x, y, Color::Black);
+        }
+
+        let canvas = canvas.downsample(2);
+        assert_eq!((canvas.width, canvas.height), (3, 1));
+        assert_eq!(canvas.get(0, 0), Color::Black);
+        assert_eq!(canvas.get(1, 0), Color::Custom((191, 191, 191, 255)));
+        assert_eq!(canvas.get(2, 0), Color::White);
+    }
+}
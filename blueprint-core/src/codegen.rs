@@ -0,0 +1,165 @@
+use crate::domain::{Blueprint, Color, LineStyle, Point};
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Rust function building a blueprint with [`crate::builder::BlueprintBuilder`], to embed fixed
+/// drawings in programs and tests without parsing their source.
+///
+/// The edges of a shape that are not connected to the previous one start a new shape, and the
+/// locations of the edges in their source are not kept.
+pub struct RustCode<'b> {
+    blueprint: &'b Blueprint,
+    function: String,
+}
+
+impl RustCode<'_> {
+    /// Name of the generated function, `blueprint` by default.
+    pub fn function(mut self, function: &str) -> Self {
+        self.function = function.to_string();
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<'b> From<&'b Blueprint> for RustCode<'b> {
+    fn from(value: &'b Blueprint) -> Self {
+        Self {
+            blueprint: value,
+            function: "blueprint".to_string(),
+        }
+    }
+}
+
+/// Attributes of the builder, to only set the ones changing from an edge to the next.
+#[derive(PartialEq)]
+struct Pen {
+    color: Color,
+    width: f32,
+    style: LineStyle,
+    layer: Option<usize>,
+}
+
+impl Display for RustCode<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "use blueprint_core::builder::BlueprintBuilder;")?;
+        writeln!(
+            f,
+            "use blueprint_core::domain::{{Blueprint, Color, LineStyle}};"
+        )?;
+        writeln!(f)?;
+        writeln!(f, "pub fn {}() -> Blueprint {{", self.function)?;
+        writeln!(f, "    BlueprintBuilder::new()")?;
+
+        let layers = self
+            .blueprint
+            .layers_iter()
+            .map(|layer| layer.name.as_str())
+            .collect::<Vec<_>>();
+        let mut tags = self.blueprint.tags_iter().collect::<Vec<_>>();
+        // tags are set when the pen first reaches their point
+        let mut tag = |f: &mut Formatter<'_>, point: Point| {
+            for (tag, _) in tags.extract_if(.., |(_, tagged)| **tagged == point) {
+                writeln!(f, "        .tag({tag:?})")?;
+            }
+            Ok(())
+        };
+
+        let mut pen = Pen {
+            color: Color::default(),
+            width: 1.,
+            style: LineStyle::default(),
+            layer: None,
+        };
+        for shape in self.blueprint.shapes_iter() {
+            let mut position = None;
+            for edge in shape.edges_iter() {
+                if position != Some(edge.from) {
+                    writeln!(f, "        .move_to({:?}, {:?})", edge.from.x, edge.from.y)?;
+                    tag(f, edge.from)?;
+                }
+
+                let edge_pen = Pen {
+                    color: edge.color,
+                    width: edge.width,
+                    style: edge.style,
+                    layer: edge.layer,
+                };
+                if edge_pen.color != pen.color {
+                    writeln!(f, "        .color(Color::{:?})", edge_pen.color)?;
+                }
+                if edge_pen.width != pen.width {
+                    writeln!(f, "        .width({:?})", edge_pen.width)?;
+                }
+                if edge_pen.style != pen.style {
+                    writeln!(f, "        .style(LineStyle::{:?})", edge_pen.style)?;
+                }
+                if edge_pen.layer != pen.layer {
+                    match edge_pen.layer {
+                        Some(layer) => writeln!(f, "        .layer(Some({:?}))", layers[layer])?,
+                        None => writeln!(f, "        .layer(None)")?,
+                    }
+                }
+                pen = edge_pen;
+
+                writeln!(f, "        .line_to({:?}, {:?})", edge.to.x, edge.to.y)?;
+                tag(f, edge.to)?;
+                position = Some(edge.to);
+            }
+        }
+
+        // tags of points without edges
+        for (tag, point) in tags {
+            writeln!(f, "        .move_to({:?}, {:?})", point.x, point.y)?;
+            writeln!(f, "        .tag({tag:?})")?;
+        }
+
+        writeln!(f, "        .build()")?;
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_rust_code() {
+        let (blueprint, _) = load_blueprint_str(
+            "{ [color:red] 10,0 #corner [width:2, layer:walls] 0,5 } { move @20,0 [style:dashed] 0,5 }",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            RustCode::from(&blueprint.unwrap())
+                .function("plan")
+                .to_string(),
+            r#"use blueprint_core::builder::BlueprintBuilder;
+use blueprint_core::domain::{Blueprint, Color, LineStyle};
+
+pub fn plan() -> Blueprint {
+    BlueprintBuilder::new()
+        .move_to(0.0, 0.0)
+        .color(Color::Red)
+        .line_to(10.0, 0.0)
+        .tag("corner")
+        .color(Color::Black)
+        .width(2.0)
+        .layer(Some("walls"))
+        .line_to(10.0, 5.0)
+        .move_to(20.0, 0.0)
+        .width(1.0)
+        .style(LineStyle::Dashed)
+        .layer(None)
+        .line_to(20.0, 5.0)
+        .build()
+}
+"#
+        );
+    }
+}
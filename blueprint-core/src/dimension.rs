@@ -0,0 +1,165 @@
+use crate::domain::{Blueprint, Color, Coordinate, Curve, Edge, Point, Shape, SourceSpan};
+use crate::font;
+use crate::info::number;
+
+/// Blueprint with the dimension of each visible straight edge drawn on the left of the edge, as
+/// drawn: extension lines from its ends, a dimension line between them ended by arrowheads, and
+/// its length, real-world when the blueprint has units. `size` is the height of the text, in
+/// blueprint units, the rest of the dimensions being sized after it.
+pub fn dimensioned<T: Coordinate>(blueprint: &Blueprint<T>, size: f32) -> Blueprint<T> {
+    let mut dimensioned = blueprint.clone();
+    for edge in blueprint.edges_iter().filter(|edge| {
+        edge.curve == Curve::Line && !edge.color.is_transparent() && edge.length() > 0.
+    }) {
+        // drawn relative to the edge, to stay precise however far from the origin it lies
+        let origin = edge.origin();
+        let relative = edge.relative_to(origin);
+        let edges = dimension(
+            relative.from,
+            relative.to,
+            &length(blueprint, edge.length()),
+            size,
+        );
+        dimensioned.push(Shape::from(
+            edges.iter().map(|edge| edge.at(origin)).collect::<Vec<_>>(),
+        ));
+    }
+    dimensioned
+}
+
+/// `length` as written by dimensions, in the unit of the blueprint if any.
+fn length<T: Coordinate>(blueprint: &Blueprint<T>, length: f32) -> String {
+    match blueprint.units() {
+        Some(units) => format!("{}{}", number(units.length(length)), units.unit),
+        None => number(length),
+    }
+}
+
+/// Edges of the dimension of the segment from `from` to `to`, written `text`.
+fn dimension(from: Point, to: Point, text: &str, size: f32) -> Vec<Edge> {
+    let length = from.distance_to_point(&to);
+    let (ux, uy) = ((to.x - from.x) / length, (to.y - from.y) / length);
+    // left of the edge, the y axis pointing down
+    let (nx, ny) = (uy, -ux);
+    let (offset, gap, arrow) = (1.5 * size, 0.3 * size, 0.6 * size);
+    let line = |from: Point, to: Point| {
+        Edge::new_from_points(from, to, Color::Black, SourceSpan::default()).with_width(size / 10.)
+    };
+    let at = |point: Point, along: f32, across: f32| {
+        Point::new(
+            point.x + ux * along + nx * across,
+            point.y + uy * along + ny * across,
+        )
+    };
+
+    let (start, end) = (at(from, 0., offset), at(to, 0., offset));
+    let mut edges = vec![
+        line(at(from, 0., gap), at(from, 0., offset + gap)),
+        line(at(to, 0., gap), at(to, 0., offset + gap)),
+        line(start, end),
+    ];
+    for (tip, direction) in [(start, 1.), (end, -1.)] {
+        for side in [-1., 1.] {
+            edges.push(line(tip, at(tip, direction * arrow, side * arrow / 3.)));
+        }
+    }
+
+    // the text reads from left to right, or upwards, and lies beyond the dimension line
+    let (rx, ry) = if ux > 0. || (ux == 0. && uy < 0.) {
+        (ux, uy)
+    } else {
+        (-ux, -uy)
+    };
+    let (dx, dy) = (-ry, rx);
+    let pixel = size / font::GLYPH_HEIGHT as f32;
+    let width = (text.chars().count() * (font::GLYPH_WIDTH + 1) - 1) as f32 * pixel;
+    let above = if nx * dx + ny * dy < 0. { size } else { 0. };
+    let middle = at(start, length / 2., gap + above);
+    let top_left = Point::new(middle.x - rx * width / 2., middle.y - ry * width / 2.);
+    let glyph_point =
+        |x: f32, y: f32| Point::new(top_left.x + rx * x + dx * y, top_left.y + ry * x + dy * y);
+
+    for (i, c) in text.chars().enumerate() {
+        let Some(rows) = font::glyph(c) else {
+            continue;
+        };
+        let left = (i * (font::GLYPH_WIDTH + 1)) as f32;
+        for (row, bits) in rows.iter().enumerate() {
+            // each run of pixels of a row is drawn as one stroke as wide as a pixel
+            let mut column = 0;
+            while column < font::GLYPH_WIDTH {
+                let lit = |column: usize| bits & (1 << (font::GLYPH_WIDTH - 1 - column)) != 0;
+                if !lit(column) {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < font::GLYPH_WIDTH && lit(column) {
+                    column += 1;
+                }
+                let y = (row as f32 + 0.5) * pixel;
+                edges.push(
+                    Edge::new_from_points(
+                        glyph_point((left + start as f32) * pixel, y),
+                        glyph_point((left + column as f32) * pixel, y),
+                        Color::Black,
+                        SourceSpan::default(),
+                    )
+                    .with_width(pixel),
+                );
+            }
+        }
+    }
+
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Unit, Units};
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_dimensioned() {
+        let (blueprint, _) = load_blueprint_str(
+            "{ 40,0 } { [color:transparent] 0,10 }",
+            Path::new("test.bp"),
+            false,
+        );
+        let mut blueprint = blueprint.unwrap();
+        let dimensioned = dimensioned(&blueprint, 5.);
+        // the transparent edge has no dimension
+        assert_eq!(
+            dimensioned.shapes_iter().count(),
+            blueprint.shapes_iter().count() + 1
+        );
+
+        let dimension = dimensioned.shapes_iter().last().unwrap();
+        let edges = dimension.edges_iter().collect::<Vec<_>>();
+        // extension lines and dimension line, above the edge drawn to the right
+        assert_eq!(
+            (edges[0].from, edges[0].to),
+            (Point::new(0., -1.5), Point::new(0., -9.))
+        );
+        assert_eq!(
+            (edges[2].from, edges[2].to),
+            (Point::new(0., -7.5), Point::new(40., -7.5))
+        );
+        // arrowheads pointing at the extension lines
+        assert_eq!(edges[3].to, Point::new(3., -6.5));
+        assert_eq!(edges[5].to, Point::new(37., -6.5));
+        // "40", 7 pixels of 1 wide centered above the dimension line, in 7 and 8 strokes
+        assert_eq!(edges.len(), 7 + 7 + 8);
+        assert_eq!(
+            (edges[7].from, edges[7].to),
+            (Point::new(16.5, -13.5), Point::new(17.5, -13.5))
+        );
+        assert_eq!(edges[7].width, 1.);
+
+        assert_eq!(length(&blueprint, 12.5), "12.5");
+        blueprint.set_units(Some(Units::new(5., Unit::Centimeter)));
+        assert_eq!(length(&blueprint, 40.), "200cm");
+    }
+}
@@ -0,0 +1,3306 @@
+use crate::canvas::Canvas;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::f32::consts::TAU;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, Div, Mul, Sub};
+use std::slice::{Iter, IterMut};
+
+/// Distance, in blueprint units, within which curves are followed by the straight edges they are
+/// measured, cropped and exported as when the format has no curves.
+pub const FLATNESS: f32 = 0.01;
+
+/// Most straight edges a curve is flattened into, whatever the tolerance.
+const MAX_SEGMENTS: usize = 1000;
+
+/// Number type of the coordinates of blueprints: `f32` unless they need more precision, e.g. `f64`
+/// for site plans in millimeters whose coordinates run into the millions, or integers for
+/// generated geometry. Whatever the type, lengths, angles and curves are computed in `f32`,
+/// relative to a point close to them, see [`Edge::origin`].
+pub trait Coordinate:
+    Copy
+    + Default
+    + Debug
+    + Display
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Send
+    + Sync
+    + 'static
+{
+    /// Closest value to `value`, integers rounding it.
+    fn from_f32(value: f32) -> Self;
+
+    /// Closest value to `value`, integers rounding it.
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f32(self) -> f32;
+
+    fn to_f64(self) -> f64;
+
+    /// Closest value to the decimal number `value` prints as, e.g. `0.1` rather than
+    /// `0.10000000149011612` in `f64`: the number it was written as in a source.
+    fn from_decimal(value: f32) -> Self {
+        Self::from_f32(value)
+    }
+
+    /// The value as a `U`, unchanged when it already is one.
+    fn cast<U: Coordinate>(self) -> U {
+        match (&self as &dyn Any).downcast_ref::<U>() {
+            Some(value) => *value,
+            None => U::from_f64(self.to_f64()),
+        }
+    }
+
+    /// Coordinate of the origin of the frame the geometry around the value is computed in, in
+    /// `f32`: the value itself, or 0 for `f32` values whose geometry is computed where it is.
+    fn frame(self) -> Self {
+        self
+    }
+}
+
+impl Coordinate for f32 {
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn frame(self) -> Self {
+        0.
+    }
+}
+
+impl Coordinate for f64 {
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn from_decimal(value: f32) -> Self {
+        // the shortest decimal number printed for a value is the one it was written as
+        value.to_string().parse().unwrap_or(value as f64)
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Coordinate for i32 {
+    fn from_f32(value: f32) -> Self {
+        value.round() as i32
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value.round() as i32
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Coordinate for i64 {
+    fn from_f32(value: f32) -> Self {
+        value.round() as i64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value.round() as i64
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+pub trait Bound<T: Coordinate = f32> {
+    /// Top left and bottom right corners of the smallest box holding everything, `None` when there
+    /// is nothing to hold, as for empty shapes and blueprints.
+    fn boundaries(self) -> Option<(Point<T>, Point<T>)>;
+}
+
+pub trait Translate {
+    fn translate(&mut self, dx: f32, dy: f32);
+}
+
+pub trait Rotate {
+    /// Rotates by `degrees` about `pivot`, counterclockwise as drawn, the y axis pointing down.
+    fn rotate(&mut self, pivot: Point, degrees: f32);
+}
+
+pub trait Mirror {
+    /// Mirrors about the line through `from` and `to`, left unchanged when they are the same.
+    fn mirror(&mut self, from: Point, to: Point);
+
+    /// Mirrors about the vertical line at `x`, swapping left and right.
+    fn mirror_x(&mut self, x: f32) {
+        self.mirror(Point::new(x, 0.), Point::new(x, 1.));
+    }
+
+    /// Mirrors about the horizontal line at `y`, swapping top and bottom.
+    fn mirror_y(&mut self, y: f32) {
+        self.mirror(Point::new(0., y), Point::new(1., y));
+    }
+}
+
+pub trait Draw {
+    fn draw(&self, canvas: &mut Canvas);
+}
+
+/// Walks the parts of a blueprint given to [`Blueprint::visit`], the ones it is not interested in
+/// being ignored.
+pub trait Visitor<T: Coordinate = f32> {
+    /// Called for each shape, before its edges.
+    fn visit_shape(&mut self, _shape: &Shape<T>) {}
+
+    fn visit_edge(&mut self, _shape: &Shape<T>, _edge: &Edge<T>) {}
+
+    fn visit_tag(&mut self, _tag: &str, _point: &Point<T>) {}
+}
+
+/// Geometry moved by affine transforms, computed in `f32`: only `f32` blueprints and their parts
+/// are transformable, others being cast to `f32` first, e.g. when drawn.
+pub trait Transformable {
+    /// Moves every point by `transform`.
+    fn transform(&mut self, transform: &Transform);
+}
+
+impl<T: Transformable> Translate for T {
+    fn translate(&mut self, dx: f32, dy: f32) {
+        self.transform(&Transform::translation(dx, dy));
+    }
+}
+
+impl<T: Transformable> Rotate for T {
+    fn rotate(&mut self, pivot: Point, degrees: f32) {
+        self.transform(&Transform::rotation(pivot, degrees));
+    }
+}
+
+impl<T: Transformable> Mirror for T {
+    fn mirror(&mut self, from: Point, to: Point) {
+        self.transform(&Transform::mirror(from, to));
+    }
+}
+
+/// Affine transform of the plane, the matrix
+/// ```text
+/// | a c e |
+/// | b d f |
+/// | 0 0 1 |
+/// ```
+/// moving `x,y` to `a*x + c*y + e, b*x + d*y + f`, as the `matrix` transforms of SVG.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.,
+        b: 0.,
+        c: 0.,
+        d: 1.,
+        e: 0.,
+        f: 0.,
+    };
+
+    pub fn translation(dx: f32, dy: f32) -> Self {
+        Self {
+            e: dx,
+            f: dy,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scaling by `sx` horizontally and `sy` vertically about the origin.
+    pub fn scaling(sx: f32, sy: f32) -> Self {
+        Self {
+            a: sx,
+            d: sy,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Rotation by `degrees` about `pivot`, counterclockwise as drawn, the y axis pointing down.
+    pub fn rotation(pivot: Point, degrees: f32) -> Self {
+        // exact for quarter turns, which most rotations of plans are
+        let (sin, cos) = match degrees.rem_euclid(360.) {
+            0. => (0., 1.),
+            90. => (1., 0.),
+            180. => (0., -1.),
+            270. => (-1., 0.),
+            degrees => degrees.to_radians().sin_cos(),
+        };
+        Self {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            e: pivot.x - pivot.x * cos - pivot.y * sin,
+            f: pivot.y + pivot.x * sin - pivot.y * cos,
+        }
+    }
+
+    /// Mirror about the line through `from` and `to`, the identity when they are the same.
+    pub fn mirror(from: Point, to: Point) -> Self {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let length = dx * dx + dy * dy;
+        if length == 0. {
+            return Self::IDENTITY;
+        }
+        let (cos, sin) = ((dx * dx - dy * dy) / length, 2. * dx * dy / length);
+        Self {
+            a: cos,
+            b: sin,
+            c: sin,
+            d: -cos,
+            e: from.x - from.x * cos - from.y * sin,
+            f: from.y - from.x * sin + from.y * cos,
+        }
+    }
+
+    /// Transform moving points by `self`, then by `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        Point {
+            x: self.a * point.x + self.c * point.y + self.e,
+            y: self.b * point.x + self.d * point.y + self.f,
+            span: point.span,
+        }
+    }
+
+    /// Factor areas are scaled by, negative when the transform mirrors.
+    pub fn determinant(&self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// Whether circles stay circles: the transform only translates, rotates, mirrors and scales
+    /// uniformly.
+    pub fn is_similarity(&self) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-6 * (a.abs() + b.abs()).max(1.);
+        (close(self.a, self.d) && close(self.b, -self.c))
+            || (close(self.a, -self.d) && close(self.b, self.c))
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl<I, E, T> Bound<T> for I
+where
+    I: Iterator<Item = E>,
+    E: Bound<T>,
+    T: Coordinate,
+{
+    fn boundaries(self) -> Option<(Point<T>, Point<T>)> {
+        self.filter_map(Bound::boundaries).reduce(
+            |(top_left, bottom_right), (inner_top_left, inner_bottom_right)| {
+                (
+                    top_left.top_left(&inner_top_left),
+                    bottom_right.bottom_right(&inner_bottom_right),
+                )
+            },
+        )
+    }
+}
+
+/// Drawing made of shapes, with coordinates of type `T`, see [`Coordinate`].
+#[derive(Default, Debug, Clone)]
+pub struct Blueprint<T: Coordinate = f32> {
+    shapes: Vec<Shape<T>>,
+    /// Tagged points, by tag.
+    tags: BTreeMap<String, Point<T>>,
+    /// Layers, edges referring to them by index.
+    layers: Vec<Layer>,
+    /// Real-world length of a blueprint unit, when known.
+    units: Option<Units>,
+    /// Custom attributes of the edges, edges referring to them by index, each set being kept once.
+    attributes: Vec<Attributes>,
+}
+
+/// Attributes of an edge having no meaning for blueprints, e.g. `[material:brick]`, kept for the
+/// tools using them: their values, by key, as written.
+pub type Attributes = BTreeMap<String, String>;
+
+/// Named group of edges, e.g. the walls or the electric plan of a floor, with the settings of its
+/// drawing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    /// Color the edges of the layer are drawn with instead of their own when set, as the layer
+    /// colors of CAD tools.
+    pub color: Option<Color>,
+    pub visible: bool,
+    /// Layers are drawn from the lowest z-order to the highest, the edges without a layer being
+    /// at 0.
+    pub z_order: i32,
+}
+
+impl Layer {
+    /// Visible layer at z-order 0, drawing its edges with their own color.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            color: None,
+            visible: true,
+            z_order: 0,
+        }
+    }
+}
+
+/// Unit of real-world lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Millimeter,
+    Centimeter,
+    Meter,
+    Inch,
+    Foot,
+}
+
+impl Unit {
+    pub const ALL: [Unit; 5] = [
+        Unit::Millimeter,
+        Unit::Centimeter,
+        Unit::Meter,
+        Unit::Inch,
+        Unit::Foot,
+    ];
+
+    /// Millimeters in one of the unit.
+    pub fn millimeters(&self) -> f32 {
+        match self {
+            Unit::Millimeter => 1.,
+            Unit::Centimeter => 10.,
+            Unit::Meter => 1000.,
+            Unit::Inch => 25.4,
+            Unit::Foot => 304.8,
+        }
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Millimeter => write!(f, "mm"),
+            Unit::Centimeter => write!(f, "cm"),
+            Unit::Meter => write!(f, "m"),
+            Unit::Inch => write!(f, "in"),
+            Unit::Foot => write!(f, "ft"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Unit {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Unit::ALL
+            .into_iter()
+            .find(|unit| unit.to_string() == value.to_lowercase())
+            .ok_or(())
+    }
+}
+
+/// Real-world length of a blueprint unit, e.g. `5cm` for plans whose `10,0` edges are 50 cm long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Units {
+    /// Lengths in `unit` of a blueprint unit.
+    pub length: f32,
+    pub unit: Unit,
+}
+
+impl Units {
+    pub fn new(length: f32, unit: Unit) -> Self {
+        Self { length, unit }
+    }
+
+    /// Units of a blueprint scaled by `factor`, each of its units being `factor` times longer.
+    pub fn scale(&self, factor: f32) -> Self {
+        Self::new(self.length * factor, self.unit)
+    }
+
+    /// Real-world length, in the unit of the units, of `length` blueprint units.
+    pub fn length(&self, length: f32) -> f32 {
+        length * self.length
+    }
+
+    /// Real-world length, in `unit`, of `length` blueprint units.
+    pub fn length_in(&self, length: f32, unit: Unit) -> f32 {
+        self.length(length) * self.unit.millimeters() / unit.millimeters()
+    }
+
+    /// Real-world area, in the square unit of the units, of `area` square blueprint units.
+    pub fn area(&self, area: f32) -> f32 {
+        area * self.length * self.length
+    }
+}
+
+impl Display for Units {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.length, self.unit)
+    }
+}
+
+impl TryFrom<&str> for Units {
+    type Error = ();
+
+    /// Reads units written as a positive length followed by its unit, e.g. `5cm` or `0.5 m`, the
+    /// length being 1 when missing, e.g. `mm`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        let split = value.find(|c: char| c.is_ascii_alphabetic()).ok_or(())?;
+        let (length, unit) = value.split_at(split);
+        let length = match length.trim() {
+            "" => 1.,
+            length => length.parse::<f32>().map_err(|_| ())?,
+        };
+        if !(length > 0. && length.is_finite()) {
+            return Err(());
+        }
+        Ok(Self::new(length, Unit::try_from(unit)?))
+    }
+}
+
+impl<T: Coordinate> Blueprint<T> {
+    pub fn push(&mut self, shape: Shape<T>) {
+        self.shapes.push(shape);
+    }
+
+    pub fn shapes_iter(&self) -> Iter<'_, Shape<T>> {
+        self.shapes.iter()
+    }
+
+    /// Edges of all the shapes, in the order they are drawn.
+    pub fn edges_iter(&self) -> impl Iterator<Item = &Edge<T>> {
+        self.shapes.iter().flat_map(|shape| shape.edges.iter())
+    }
+
+    pub fn tag(&mut self, tag: String, point: Point<T>) {
+        self.tags.insert(tag, point);
+    }
+
+    pub fn tags_iter(&self) -> impl Iterator<Item = (&str, &Point<T>)> {
+        self.tags.iter().map(|(tag, point)| (tag.as_str(), point))
+    }
+
+    /// Index of `attributes` in the custom attributes of the blueprint, added unless they are
+    /// already known, for an edge to refer to them.
+    pub fn intern_attributes(&mut self, attributes: Attributes) -> usize {
+        match self
+            .attributes
+            .iter()
+            .position(|known| *known == attributes)
+        {
+            Some(index) => index,
+            None => {
+                self.attributes.push(attributes);
+                self.attributes.len() - 1
+            }
+        }
+    }
+
+    /// Custom attributes of `edge`, an edge of the blueprint.
+    pub fn attributes(&self, edge: &Edge<T>) -> Option<&Attributes> {
+        edge.attributes
+            .map(|attributes| &self.attributes[attributes])
+    }
+
+    /// Real-world length of a blueprint unit, `None` when the blueprint is not drawn to scale.
+    pub fn units(&self) -> Option<Units> {
+        self.units
+    }
+
+    pub fn set_units(&mut self, units: Option<Units>) {
+        self.units = units;
+    }
+
+    /// Draws all the visible edges in black.
+    pub fn monochrome(&self) -> Blueprint<T> {
+        let mut blueprint = self.clone();
+        for edge in blueprint
+            .shapes
+            .iter_mut()
+            .flat_map(|shape| shape.edges.iter_mut())
+        {
+            if !edge.color.is_transparent() {
+                edge.color = Color::Black;
+            }
+        }
+        blueprint
+    }
+
+    /// Index of the layer named `name`, added if it is not known yet.
+    pub fn layer(&mut self, name: &str) -> usize {
+        match self.layer_index(name) {
+            Some(index) => index,
+            None => {
+                self.layers.push(Layer::new(name));
+                self.layers.len() - 1
+            }
+        }
+    }
+
+    /// Index of the layer named `name`, if any.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Layers, in the order of their indices.
+    pub fn layers_iter(&self) -> Iter<'_, Layer> {
+        self.layers.iter()
+    }
+
+    /// Layers, in the order of their indices, to change their settings.
+    pub fn layers_iter_mut(&mut self) -> IterMut<'_, Layer> {
+        self.layers.iter_mut()
+    }
+
+    /// Blueprint as drawn according to its layers: the edges of hidden layers are removed, the
+    /// edges of layers having a color take it, and the shapes are split between the z-orders of
+    /// their edges, the lowest drawn first. Blueprints whose layers are all at the same z-order
+    /// keep their shapes in order.
+    pub fn composed(&self) -> Blueprint<T> {
+        let layer = |edge: &Edge<T>| edge.layer.map(|layer| &self.layers[layer]);
+        let z_order = |edge: &Edge<T>| layer(edge).map_or(0, |layer| layer.z_order);
+        let mut z_orders = self
+            .layers
+            .iter()
+            .map(|layer| layer.z_order)
+            .chain([0])
+            .collect::<Vec<_>>();
+        z_orders.sort();
+        z_orders.dedup();
+
+        let mut shapes = Vec::new();
+        for z in z_orders {
+            for shape in &self.shapes {
+                let edges = shape
+                    .edges
+                    .iter()
+                    .filter(|edge| z_order(edge) == z && layer(edge).is_none_or(|l| l.visible))
+                    .map(|edge| match layer(edge).and_then(|layer| layer.color) {
+                        Some(color) => Edge { color, ..*edge },
+                        None => *edge,
+                    })
+                    .collect::<Vec<_>>();
+                if !edges.is_empty() {
+                    shapes.push(shape.with_edges(edges));
+                }
+            }
+        }
+
+        Self {
+            shapes,
+            tags: self.tags.clone(),
+            layers: self.layers.clone(),
+            units: self.units,
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Keeps the edges of `layer` only, or the ones without a layer when it is `None`.
+    pub fn layer_only(&self, layer: Option<usize>) -> Blueprint<T> {
+        self.filter_edges(|edge| edge.layer == layer)
+    }
+
+    /// Keeps the edges of the layers named `names` only, unknown names being ignored.
+    pub fn layers_only(&self, names: &[String]) -> Blueprint<T> {
+        self.filter_edges(|edge| {
+            edge.layer
+                .is_some_and(|layer| names.contains(&self.layers[layer].name))
+        })
+    }
+
+    fn filter_edges(&self, keep: impl Fn(&Edge<T>) -> bool) -> Blueprint<T> {
+        Self {
+            shapes: self
+                .shapes
+                .iter()
+                .map(|shape| {
+                    shape.with_edges(
+                        shape
+                            .edges
+                            .iter()
+                            .filter(|edge| keep(edge))
+                            .copied()
+                            .collect(),
+                    )
+                })
+                .filter(|shape| !shape.edges.is_empty())
+                .collect(),
+            tags: self.tags.clone(),
+            layers: self.layers.clone(),
+            units: self.units,
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Moves the blueprint so that the top left corner of its boundaries is the origin.
+    pub fn translate_to_origin(&mut self) {
+        let Some((top_left, _)) = self.boundaries() else {
+            return;
+        };
+        // adding zero turns -0 into 0
+        let translate = |point: Point<T>| Point {
+            x: point.x - top_left.x + T::default(),
+            y: point.y - top_left.y + T::default(),
+            ..point
+        };
+        for edge in self
+            .shapes
+            .iter_mut()
+            .flat_map(|shape| shape.edges.iter_mut())
+        {
+            *edge = edge.map_points(translate);
+        }
+        for point in self.tags.values_mut() {
+            *point = translate(*point);
+        }
+    }
+
+    /// Keeps the part of the blueprint within the rectangle having `corner` and `opposite` as
+    /// corners, moved so that the top left corner of the rectangle is the origin.
+    pub fn crop(&self, corner: Point<T>, opposite: Point<T>) -> Blueprint<T> {
+        let (min, max) = (corner.top_left(&opposite), corner.bottom_right(&opposite));
+        // clipped relative to the rectangle, to stay precise however far from the origin it lies
+        let size = max.relative_to(min);
+        let contains = |point: &Point<T>| {
+            min.x <= point.x && point.x <= max.x && min.y <= point.y && point.y <= max.y
+        };
+
+        Self {
+            shapes: self
+                .shapes
+                .iter()
+                .map(|shape| {
+                    shape.with_edges(
+                        shape
+                            .edges
+                            .iter()
+                            .flat_map(|edge| edge.relative_to(min).flatten(FLATNESS))
+                            .filter_map(|edge| edge.clip(Point::default(), size))
+                            .map(|edge| edge.cast())
+                            .collect(),
+                    )
+                })
+                .filter(|shape| !shape.edges.is_empty())
+                .collect(),
+            tags: self
+                .tags
+                .iter()
+                .filter(|(_, point)| contains(point))
+                .map(|(tag, point)| (tag.clone(), point.relative_to(min).cast()))
+                .collect(),
+            layers: self.layers.clone(),
+            units: self.units,
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Walks the blueprint with `visitor`: each shape, followed by its edges, then the tags in
+    /// alphabetical order.
+    pub fn visit(&self, visitor: &mut impl Visitor<T>) {
+        for shape in &self.shapes {
+            visitor.visit_shape(shape);
+            for edge in &shape.edges {
+                visitor.visit_edge(shape, edge);
+            }
+        }
+        for (tag, point) in &self.tags {
+            visitor.visit_tag(tag, point);
+        }
+    }
+
+    /// Adds the shapes and the tags of `other`, moved by `offset`, e.g. to compose the blueprints
+    /// of several files into a drawing. The edges are kept in the layers of the same name, added
+    /// if they are not known yet, and keep their custom attributes. The shapes whose identifier is
+    /// already taken, e.g. when merging a file twice, get another one derived from it.
+    ///
+    /// Like a tag defined again in a source, the tags of `other` replace the ones of the same
+    /// name, whose names are returned in alphabetical order.
+    pub fn merge(&mut self, other: &Blueprint<T>, offset: Point<T>) -> Vec<String> {
+        let layers = other
+            .layers
+            .iter()
+            .map(|layer| match self.layer_index(&layer.name) {
+                Some(index) => index,
+                None => {
+                    self.layers.push(layer.clone());
+                    self.layers.len() - 1
+                }
+            })
+            .collect::<Vec<_>>();
+        let attributes = other
+            .attributes
+            .iter()
+            .map(|attributes| self.intern_attributes(attributes.clone()))
+            .collect::<Vec<_>>();
+        let translate = |point: Point<T>| Point {
+            x: point.x + offset.x,
+            y: point.y + offset.y,
+            span: point.span,
+        };
+        let mut ids = self.shapes.iter().map(Shape::id).collect::<HashSet<_>>();
+        for shape in &other.shapes {
+            let mut shape = shape.map_points(translate);
+            while !ids.insert(shape.id) {
+                shape.id = shape.id.salted();
+            }
+            for edge in &mut shape.edges {
+                edge.layer = edge.layer.map(|layer| layers[layer]);
+                edge.attributes = edge.attributes.map(|index| attributes[index]);
+            }
+            self.shapes.push(shape);
+        }
+
+        let mut collisions = Vec::new();
+        for (tag, point) in &other.tags {
+            if self.tags.insert(tag.clone(), translate(*point)).is_some() {
+                collisions.push(tag.clone());
+            }
+        }
+        collisions
+    }
+
+    pub fn scale(&self, factor: f32) -> Blueprint<T> {
+        Self {
+            shapes: self
+                .shapes
+                .iter()
+                .map(|shape| shape.scale(factor))
+                .collect(),
+            tags: self
+                .tags
+                .iter()
+                .map(|(tag, point)| (tag.clone(), point.scale(factor)))
+                .collect(),
+            layers: self.layers.clone(),
+            units: self.units.map(|units| units.scale(1. / factor)),
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Scales the width of the edges by `factor`, leaving their coordinates as they are.
+    pub fn scale_widths(&self, factor: f32) -> Blueprint<T> {
+        Self {
+            shapes: self
+                .shapes
+                .iter()
+                .map(|shape| shape.scale_widths(factor))
+                .collect(),
+            tags: self.tags.clone(),
+            layers: self.layers.clone(),
+            units: self.units,
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Visible edge closest to `p`, with its point closest to `p` and the distance between them.
+    pub fn find_closest_edge(&self, p: Point<T>) -> Option<(&Edge<T>, Point<T>, f32)> {
+        let mut closest = None;
+
+        for edge in self.edges_iter() {
+            if edge.color == Color::Transparent {
+                continue;
+            }
+            // measured relative to the edge, to stay precise however far from the origin it lies
+            let origin = edge.origin();
+            if let Some((d, point)) = p
+                .relative_to(origin)
+                .distance_to_edge(&edge.relative_to(origin))
+                && d < closest.map(|(_, d, _)| d).unwrap_or(f32::INFINITY)
+            {
+                closest = Some((edge, d, point.at(origin)))
+            }
+        }
+
+        closest.map(|(e, d, p)| (e, p, d))
+    }
+
+    /// The blueprint with its coordinates converted to `U`, e.g. to draw in `f32` a blueprint
+    /// loaded in `f64`.
+    pub fn cast<U: Coordinate>(&self) -> Blueprint<U> {
+        self.map_points(Point::cast)
+    }
+
+    /// The blueprint with its points moved by `f`.
+    fn map_points<U: Coordinate>(&self, f: impl Fn(Point<T>) -> Point<U>) -> Blueprint<U> {
+        Blueprint {
+            shapes: self
+                .shapes
+                .iter()
+                .map(|shape| shape.map_points(&f))
+                .collect(),
+            tags: self
+                .tags
+                .iter()
+                .map(|(tag, point)| (tag.clone(), f(*point)))
+                .collect(),
+            layers: self.layers.clone(),
+            units: self.units,
+            attributes: self.attributes.clone(),
+        }
+    }
+}
+
+impl Blueprint {
+    /// The blueprint with its coordinates converted to `U` as [`Point::widen`] does, e.g. to load
+    /// in `f64` a blueprint read in `f32`.
+    pub fn widen<U: Coordinate>(&self) -> Blueprint<U> {
+        self.map_points(Point::widen)
+    }
+
+    /// Moves the ends of the edges, the points of their curves and the tags to the grid of `grid`
+    /// units as `rounding` leads to, e.g. to clean up imported or computed geometry before
+    /// exporting it. Nothing is moved when `grid` is not positive.
+    pub fn snap_all(&mut self, grid: f32, rounding: Rounding) {
+        let snap = |point: Point| point.snap_with(grid, rounding);
+        for shape in &mut self.shapes {
+            let edges = shape
+                .edges
+                .iter()
+                .map(|edge| Edge {
+                    from: snap(edge.from),
+                    to: snap(edge.to),
+                    curve: edge.curve.map(snap),
+                    ..*edge
+                })
+                .collect();
+            // the dash patterns continue over the new lengths
+            *shape = shape.with_edges(edges);
+        }
+        for point in self.tags.values_mut() {
+            *point = snap(*point);
+        }
+    }
+
+    /// Visible edges lying entirely within the rectangle having `corner` and `opposite` as corners.
+    pub fn edges_within(&self, corner: Point, opposite: Point) -> impl Iterator<Item = &Edge> {
+        let (min, max) = (corner.top_left(&opposite), corner.bottom_right(&opposite));
+        let contains = move |point: &Point| {
+            min.x <= point.x && point.x <= max.x && min.y <= point.y && point.y <= max.y
+        };
+        self.edges_iter().filter(move |edge| {
+            let (top_left, bottom_right) = edge.extent();
+            !edge.color.is_transparent() && contains(&top_left) && contains(&bottom_right)
+        })
+    }
+
+    /// Edges added since `previous`, and edges of `previous` removed. Edges are the same when they
+    /// are drawn the same way, whatever their direction, layer and location in the source.
+    pub fn diff(&self, previous: &Blueprint) -> (Vec<Edge>, Vec<Edge>) {
+        // edges of `blueprint` missing from `other`, as many times as they are missing
+        let missing = |blueprint: &Blueprint, other: &Blueprint| {
+            let mut counts = HashMap::new();
+            for edge in other.edges_iter() {
+                *counts.entry(edge.drawing()).or_insert(0) += 1;
+            }
+            blueprint
+                .edges_iter()
+                .filter(|edge| match counts.get_mut(&edge.drawing()) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                })
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        (missing(self, previous), missing(previous, self))
+    }
+
+    /// Smallest closed shape enclosing `p`, with its index.
+    pub fn find_enclosing_shape(&self, p: Point) -> Option<(usize, &Shape)> {
+        self.shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| shape.contains(p))
+            .filter_map(|(index, shape)| Some((index, shape, shape.area()?)))
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .map(|(index, shape, _)| (index, shape))
+    }
+
+    /// Smallest convex shape enclosing the visible edges, e.g. the footprint of the blueprint,
+    /// going clockwise as drawn from its leftmost corner; empty when they cover no area.
+    pub fn convex_hull(&self) -> Shape {
+        let mut points = self
+            .edges_iter()
+            .filter(|edge| !edge.color.is_transparent())
+            .flat_map(|edge| edge.flatten(FLATNESS))
+            .flat_map(|edge| [edge.from, edge.to])
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        points.dedup();
+
+        // Andrew's monotone chain: the top of the hull from left to right, then its bottom back,
+        // dropping the corners turning the wrong way or not turning at all
+        let turns = |a: &Point, b: &Point, c: &Point| {
+            (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) > 0.
+        };
+        let mut hull: Vec<Point> = Vec::with_capacity(points.len() + 1);
+        for pass in [
+            points.iter().collect::<Vec<_>>(),
+            points.iter().rev().collect(),
+        ] {
+            let start = hull.len();
+            for point in pass {
+                while hull.len() >= start + 2
+                    && !turns(&hull[hull.len() - 2], &hull[hull.len() - 1], point)
+                {
+                    hull.pop();
+                }
+                hull.push(*point);
+            }
+            // the last corner of a pass is the first one of the next
+            hull.pop();
+        }
+        if hull.len() < 3 {
+            return Shape::default();
+        }
+        hull.push(hull[0]);
+
+        Shape::from(
+            hull.windows(2)
+                .map(|pair| {
+                    Edge::new_from_points(pair[0], pair[1], Color::default(), SourceSpan::default())
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Adds shapes whose edges are in the layers of the blueprint, e.g. generated ones.
+impl<T: Coordinate> Extend<Shape<T>> for Blueprint<T> {
+    fn extend<I: IntoIterator<Item = Shape<T>>>(&mut self, shapes: I) {
+        self.shapes.extend(shapes);
+    }
+}
+
+impl<T: Coordinate> Bound<T> for &Blueprint<T> {
+    fn boundaries(self) -> Option<(Point<T>, Point<T>)> {
+        self.shapes.iter().boundaries()
+    }
+}
+
+impl Transformable for Blueprint {
+    fn transform(&mut self, transform: &Transform) {
+        self.shapes
+            .iter_mut()
+            .for_each(|shape| shape.transform(transform));
+        self.tags
+            .values_mut()
+            .for_each(|point| point.transform(transform));
+    }
+}
+
+impl Draw for Blueprint {
+    fn draw(&self, canvas: &mut Canvas) {
+        self.shapes.iter().for_each(|shape| shape.draw(canvas));
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Shape<T: Coordinate = f32> {
+    edges: Vec<Edge<T>>,
+    name: Option<String>,
+    id: ShapeId,
+}
+
+/// Identifier of a shape, the same from a load of its source to the next. The one of a named
+/// shape only depends on its name and on the blocks enclosing it, wherever it is in them; the one
+/// of an unnamed shape on its rank among the unnamed blocks of the enclosing block.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShapeId(u64);
+
+impl ShapeId {
+    /// Identifier of the block named `name` within the block identified by `self`.
+    pub fn named(self, name: &str) -> ShapeId {
+        self.child(1, name.as_bytes())
+    }
+
+    /// Identifier of the `index`-th unnamed block within the block identified by `self`.
+    pub fn nth(self, index: usize) -> ShapeId {
+        self.child(0, &(index as u64).to_le_bytes())
+    }
+
+    /// Another identifier derived from this one, to tell apart shapes having the same.
+    fn salted(self) -> ShapeId {
+        self.child(2, &[])
+    }
+
+    /// FNV-1a hash of the identifier of the parent, of the kind of key and of the key, the
+    /// hashers of the standard library not being the same from a build to the next.
+    fn child(self, kind: u8, key: &[u8]) -> ShapeId {
+        let bytes = self.0.to_le_bytes();
+        let hash = bytes
+            .iter()
+            .chain([&kind])
+            .chain(key)
+            .fold(0xcbf29ce484222325, |hash: u64, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+            });
+        ShapeId(hash)
+    }
+}
+
+impl<T: Coordinate> Shape<T> {
+    pub fn edges_iter(&self) -> Iter<'_, Edge<T>> {
+        self.edges.iter()
+    }
+
+    /// Name given by `shape <name> { ... }`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn with_name(mut self, name: Option<&str>) -> Self {
+        self.name = name.map(str::to_string);
+        self
+    }
+
+    /// Identifier assigned by the loader, to refer to the shape across reloads.
+    pub fn id(&self) -> ShapeId {
+        self.id
+    }
+
+    pub fn with_id(mut self, id: ShapeId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Shape made of `edges` instead, with the name and the identifier of this one.
+    fn with_edges(&self, edges: Vec<Edge<T>>) -> Shape<T> {
+        Shape::from(edges).with_name(self.name()).with_id(self.id)
+    }
+
+    pub fn scale(&self, factor: f32) -> Shape<T> {
+        Self {
+            edges: self.edges.iter().map(|edge| edge.scale(factor)).collect(),
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
+
+    pub fn scale_widths(&self, factor: f32) -> Shape<T> {
+        Self {
+            edges: self
+                .edges
+                .iter()
+                .map(|edge| edge.with_width(edge.width * factor))
+                .collect(),
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
+
+    /// Total length of the edges.
+    pub fn perimeter(&self) -> f32 {
+        self.edges.iter().map(Edge::length).sum()
+    }
+
+    /// The shape with its coordinates converted to `U`.
+    pub fn cast<U: Coordinate>(&self) -> Shape<U> {
+        self.map_points(Point::cast)
+    }
+
+    /// The shape with its points moved by `f`.
+    fn map_points<U: Coordinate>(&self, f: impl Fn(Point<T>) -> Point<U>) -> Shape<U> {
+        Shape {
+            edges: self.edges.iter().map(|edge| edge.map_points(&f)).collect(),
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
+}
+
+impl Shape {
+    /// Whether the edges are connected one to the next, the last one ending at the start of the
+    /// first one, ends at most `epsilon` apart being connected.
+    pub fn is_closed(&self, epsilon: f32) -> bool {
+        let (Some(first), Some(last)) = (self.edges.first(), self.edges.last()) else {
+            return false;
+        };
+        let connected = |end: &Point, start: &Point| end.distance_to_point(start) <= epsilon;
+        connected(&last.to, &first.from)
+            && self
+                .edges
+                .windows(2)
+                .all(|pair| connected(&pair[0].to, &pair[1].from))
+    }
+
+    /// The edges, their curves followed by straight edges within [`FLATNESS`].
+    fn flattened_edges(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.edges.iter().flat_map(|edge| edge.flatten(FLATNESS))
+    }
+
+    /// Corners of the shape, the starts of its edges.
+    pub fn points(&self) -> Vec<Point> {
+        self.edges.iter().map(|edge| edge.from).collect()
+    }
+
+    /// Area enclosed by a closed shape, its curves flattened, `None` when the shape is open.
+    pub fn area(&self) -> Option<f32> {
+        self.is_closed(0.).then(|| {
+            polygon_area(
+                &self
+                    .flattened_edges()
+                    .map(|edge| edge.from)
+                    .collect::<Vec<_>>(),
+            )
+        })
+    }
+
+    /// Whether `point` lies in the region enclosed by a closed shape, following the even-odd rule,
+    /// its curves flattened.
+    pub fn contains(&self, point: Point) -> bool {
+        self.is_closed(0.)
+            && self
+                .flattened_edges()
+                .filter(|edge| {
+                    (edge.from.y > point.y) != (edge.to.y > point.y)
+                        && point.x
+                            < edge.from.x
+                                + (point.y - edge.from.y) * (edge.to.x - edge.from.x)
+                                    / (edge.to.y - edge.from.y)
+                })
+                .count()
+                % 2
+                == 1
+    }
+
+    /// Contour of a closed shape at `distance` outside of it, or inside of it when negative,
+    /// e.g. the outer side of a wall drawn by its centerline; `None` when the shape is open or
+    /// encloses no area. Curves are flattened, and the corners where the offset edges part are
+    /// joined as `join` tells. Contours inset further than the shape is wide overlap themselves.
+    pub fn offset(&self, distance: f32, join: Join) -> Option<Shape> {
+        // miters longer than this many times the distance are beveled, as SVG does by default
+        const MITER_LIMIT: f32 = 4.;
+
+        let edges = self
+            .flattened_edges()
+            .filter(|edge| edge.length() > 0.)
+            .collect::<Vec<_>>();
+        if !self.is_closed(0.) || edges.is_empty() {
+            return None;
+        }
+        let doubled_area = edges
+            .iter()
+            .map(|edge| edge.from.x * edge.to.y - edge.to.x * edge.from.y)
+            .sum::<f32>();
+        if doubled_area == 0. {
+            return None;
+        }
+        if distance == 0. {
+            return Some(Shape::from(edges));
+        }
+
+        // the normals of the edges point outside of shapes going clockwise as drawn
+        let outside = doubled_area.signum();
+        let normals = edges
+            .iter()
+            .map(|edge| {
+                let (nx, ny) = edge.normal().expect("the edge has a length");
+                (nx * outside, ny * outside)
+            })
+            .collect::<Vec<_>>();
+
+        // ends of the offset edges at the start of each edge, joined by an edge when they part
+        let joins = (0..edges.len())
+            .map(|i| {
+                let previous = (i + edges.len() - 1) % edges.len();
+                let corner = edges[i].from;
+                let ((ax, ay), (bx, by)) = (normals[previous], normals[i]);
+                let (end, start) = (
+                    corner.add(ax * distance, ay * distance),
+                    corner.add(bx * distance, by * distance),
+                );
+                let cos = ax * bx + ay * by;
+                let (da, db) = (
+                    edges[previous].direction().expect("the edge has a length"),
+                    edges[i].direction().expect("the edge has a length"),
+                );
+                let parting = (da.0 * db.1 - da.1 * db.0) * outside * distance > 0.;
+                let miter_ratio = (2. / (1. + cos)).sqrt();
+                if cos >= 1. - f32::EPSILON {
+                    (end, end, None)
+                } else if !parting || (join == Join::Miter && miter_ratio <= MITER_LIMIT) {
+                    let factor = distance / (1. + cos);
+                    let miter = corner.add((ax + bx) * factor, (ay + by) * factor);
+                    (miter, miter, None)
+                } else if join == Join::Round {
+                    let curve = Curve::Arc {
+                        center: corner,
+                        counterclockwise: (outside > 0.) != (distance > 0.),
+                    };
+                    (end, start, Some(curve))
+                } else {
+                    (end, start, Some(Curve::Line))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut offset = Vec::with_capacity(edges.len() * 2);
+        for (i, edge) in edges.iter().enumerate() {
+            let next = (i + 1) % edges.len();
+            offset.push(Edge {
+                from: joins[i].1,
+                to: joins[next].0,
+                curve: Curve::Line,
+                ..*edge
+            });
+            if let (end, start, Some(curve)) = joins[next] {
+                offset.push(Edge {
+                    from: end,
+                    to: start,
+                    curve,
+                    ..edges[next]
+                });
+            }
+        }
+        Some(Shape::from(offset))
+    }
+}
+
+/// Way the offset edges of a shape are joined where they part.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Join {
+    /// By extending them until they meet, or by an edge between their ends when they would meet
+    /// too far away from the corner.
+    #[default]
+    Miter,
+    /// By an arc around the corner.
+    Round,
+}
+
+/// Area of the polygon having `points` as corners, with the shoelace formula. The areas of the
+/// parts of self-intersecting polygons going around in opposite directions cancel out.
+pub fn polygon_area(points: &[Point]) -> f32 {
+    let doubled = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>();
+    doubled.abs() / 2.
+}
+
+impl<T: Coordinate> Bound<T> for &Shape<T> {
+    fn boundaries(self) -> Option<(Point<T>, Point<T>)> {
+        self.edges.iter().boundaries()
+    }
+}
+
+impl Transformable for Shape {
+    /// Moves the edges by `transform`, the arcs being flattened when it does not keep circles.
+    fn transform(&mut self, transform: &Transform) {
+        if !transform.is_similarity()
+            && self
+                .edges
+                .iter()
+                .any(|edge| matches!(edge.curve, Curve::Arc { .. }))
+        {
+            self.edges = self
+                .edges
+                .iter()
+                .flat_map(|edge| match edge.curve {
+                    Curve::Arc { .. } => edge.flatten(FLATNESS).collect(),
+                    _ => vec![*edge],
+                })
+                .collect();
+        }
+        self.edges
+            .iter_mut()
+            .for_each(|edge| edge.transform(transform));
+    }
+}
+
+impl Draw for Shape {
+    fn draw(&self, canvas: &mut Canvas) {
+        for edge in self.edges.iter() {
+            edge.draw(canvas);
+        }
+    }
+}
+
+impl<T: Coordinate> From<Vec<Edge<T>>> for Shape<T> {
+    /// Builds a shape from its `edges`, the dash pattern of each edge continuing the one of the
+    /// previous edge when they are connected and share a style.
+    fn from(mut value: Vec<Edge<T>>) -> Self {
+        for i in 1..value.len() {
+            let (previous, edge) = (value[i - 1], &mut value[i]);
+            if previous.to == edge.from && previous.style == edge.style {
+                edge.dash_offset = previous.dash_offset + previous.length();
+            }
+        }
+        Self {
+            edges: value,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Edge<T: Coordinate = f32> {
+    pub from: Point<T>,
+    pub to: Point<T>,
+    pub color: Color,
+    /// Stroke width, in blueprint units.
+    pub width: f32,
+    pub style: LineStyle,
+    /// Distance into the dash pattern at which the edge starts.
+    pub dash_offset: f32,
+    /// Index of the layer of the edge in its blueprint.
+    pub layer: Option<usize>,
+    /// Index of the custom attributes of the edge in its blueprint.
+    pub attributes: Option<usize>,
+    pub span: SourceSpan,
+    /// Path from `from` to `to`.
+    pub curve: Curve<T>,
+}
+
+/// Way coordinates are rounded to a grid.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// To the closest line of the grid, halfway values away from zero.
+    #[default]
+    Nearest,
+    /// To the line of the grid below, towards negative infinity.
+    Down,
+    /// To the line of the grid above, towards positive infinity.
+    Up,
+    /// To the line of the grid closer to zero.
+    TowardZero,
+}
+
+/// Path of an edge from its start to its end.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Curve<T: Coordinate = f32> {
+    #[default]
+    Line,
+    /// Arc of the circle centered on `center` through the start of the edge, going clockwise as
+    /// drawn, or counterclockwise. An arc ending where it starts is a full circle.
+    Arc {
+        center: Point<T>,
+        counterclockwise: bool,
+    },
+    /// Cubic Bézier curve, pulled towards its control points.
+    Bezier {
+        control1: Point<T>,
+        control2: Point<T>,
+    },
+}
+
+impl<T: Coordinate> Curve<T> {
+    /// The curve with its points moved by `f`.
+    fn map<U: Coordinate>(self, f: impl Fn(Point<T>) -> Point<U>) -> Curve<U> {
+        match self {
+            Curve::Line => Curve::Line,
+            Curve::Arc {
+                center,
+                counterclockwise,
+            } => Curve::Arc {
+                center: f(center),
+                counterclockwise,
+            },
+            Curve::Bezier { control1, control2 } => Curve::Bezier {
+                control1: f(control1),
+                control2: f(control2),
+            },
+        }
+    }
+}
+
+impl Curve {
+    /// Bits of the kind and of the points of the curve, comparable and hashable.
+    fn bits(&self) -> [u32; 5] {
+        match self {
+            Curve::Line => [0; 5],
+            Curve::Arc {
+                center,
+                counterclockwise,
+            } => [
+                1,
+                center.x.to_bits(),
+                center.y.to_bits(),
+                *counterclockwise as u32,
+                0,
+            ],
+            Curve::Bezier { control1, control2 } => [
+                2,
+                control1.x.to_bits(),
+                control1.y.to_bits(),
+                control2.x.to_bits(),
+                control2.y.to_bits(),
+            ],
+        }
+    }
+}
+
+impl<T: Coordinate> Edge<T> {
+    pub fn new_from_points(from: Point<T>, to: Point<T>, color: Color, span: SourceSpan) -> Self {
+        Self {
+            from,
+            to,
+            color,
+            width: 1.,
+            style: LineStyle::default(),
+            dash_offset: 0.,
+            layer: None,
+            attributes: None,
+            span,
+            curve: Curve::Line,
+        }
+    }
+
+    pub fn with_curve(mut self, curve: Curve<T>) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_style(mut self, style: LineStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: Option<usize>) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn with_attributes(mut self, attributes: Option<usize>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// The edge with its coordinates converted to `U`.
+    pub fn cast<U: Coordinate>(&self) -> Edge<U> {
+        self.map_points(Point::cast)
+    }
+
+    /// Point the geometry of the edge is computed relative to, in `f32`, for it to stay precise
+    /// however far from the origin the edge lies: its start, or the origin for `f32` edges.
+    pub fn origin(&self) -> Point<T> {
+        Point {
+            x: self.from.x.frame(),
+            y: self.from.y.frame(),
+            span: SourceSpan::default(),
+        }
+    }
+
+    /// The edge in `f32`, moved so that `origin` is the origin.
+    pub fn relative_to(&self, origin: Point<T>) -> Edge {
+        self.map_points(|point| point.relative_to(origin))
+    }
+
+    pub fn scale(&self, factor: f32) -> Edge<T> {
+        Edge {
+            dash_offset: self.dash_offset * factor,
+            ..self.map_points(|point| point.scale(factor))
+        }
+    }
+
+    /// The edge with its points moved by `f`.
+    fn map_points<U: Coordinate>(&self, f: impl Fn(Point<T>) -> Point<U>) -> Edge<U> {
+        Edge {
+            from: f(self.from),
+            to: f(self.to),
+            color: self.color,
+            width: self.width,
+            style: self.style,
+            dash_offset: self.dash_offset,
+            layer: self.layer,
+            attributes: self.attributes,
+            span: self.span,
+            curve: self.curve.map(f),
+        }
+    }
+
+    /// Length of the path of the edge, the one of the straight edges it is flattened into for
+    /// curves.
+    pub fn length(&self) -> f32 {
+        match self.curve {
+            Curve::Line => self.from.distance_to_point(&self.to),
+            _ => self.flatten(FLATNESS).map(|edge| edge.length()).sum(),
+        }
+    }
+
+    /// Straight edges following the edge at most `tolerance` away from it, the edge itself when
+    /// it is straight, the dash pattern continuing from one to the next.
+    pub fn flatten(&self, tolerance: f32) -> impl Iterator<Item = Edge<T>> + use<T> {
+        let origin = self.origin();
+        let segments = match self.curve {
+            Curve::Line => vec![*self],
+            _ => self
+                .relative_to(origin)
+                .local_flatten(tolerance)
+                .map(|segment| segment.at(origin))
+                .collect(),
+        };
+        segments.into_iter()
+    }
+
+    /// Point of the edge at `t`, from 0 at its start to 1 at its end. `t` goes evenly along
+    /// straight edges and arcs, and is the parameter of the curve for Béziers.
+    pub fn point_at(&self, t: f32) -> Point<T> {
+        let origin = self.origin();
+        self.relative_to(origin).local_point_at(t).at(origin)
+    }
+
+    /// Radius, start angle and signed sweep, in radians clockwise as drawn, of an arc, or `None`
+    /// for other curves and arcs without radius.
+    pub(crate) fn arc(&self) -> Option<(f32, f32, f32)> {
+        self.relative_to(self.origin()).local_arc()
+    }
+
+    /// Top left and bottom right corners of the smallest box holding the edge, which always has
+    /// some.
+    pub(crate) fn extent(&self) -> (Point<T>, Point<T>) {
+        if self.curve == Curve::Line {
+            return (
+                self.from.top_left(&self.to),
+                self.from.bottom_right(&self.to),
+            );
+        }
+        let origin = self.origin();
+        let (top_left, bottom_right) = self.relative_to(origin).local_extent();
+        (top_left.at(origin), bottom_right.at(origin))
+    }
+}
+
+impl Edge {
+    pub fn new(x1: f32, y1: f32, x2: f32, y2: f32, color: Color, span: SourceSpan) -> Self {
+        Self::new_from_points(Point::new(x1, y1), Point::new(x2, y2), color, span)
+    }
+
+    /// What is drawn of the edge, comparable and hashable: its ends, in order, its width and its
+    /// color and style.
+    fn drawing(&self) -> ([u32; 5], [u32; 5], Color, LineStyle) {
+        // adding zero turns -0 into 0
+        let bits = |point: Point| ((point.x + 0.).to_bits(), (point.y + 0.).to_bits());
+        let (from, to) = (bits(self.from), bits(self.to));
+        // curves are not the same the other way round
+        let (from, to) = if from <= to || self.curve != Curve::Line {
+            (from, to)
+        } else {
+            (to, from)
+        };
+        (
+            [from.0, from.1, to.0, to.1, self.width.to_bits()],
+            self.curve.bits(),
+            self.color,
+            self.style,
+        )
+    }
+
+    /// The edge, relative to `origin`, moved back to where `origin` is, see [`Edge::relative_to`].
+    pub fn at<T: Coordinate>(&self, origin: Point<T>) -> Edge<T> {
+        self.map_points(|point| point.at(origin))
+    }
+
+    /// [`Edge::flatten`] of a curve, relative to its origin.
+    fn local_flatten(&self, tolerance: f32) -> impl Iterator<Item = Edge> + use<> {
+        let segments = |count: f32| (count.ceil() as usize).clamp(1, MAX_SEGMENTS);
+        let points = match self.curve {
+            Curve::Line => vec![self.from, self.to],
+            Curve::Arc { .. } => match self.arc() {
+                Some((radius, _, sweep)) => {
+                    // the chords of the steps stray from the arc by `tolerance` at most
+                    let step = 2. * (1. - (tolerance / radius).min(1.)).acos();
+                    let count = segments(sweep.abs() / step);
+                    (0..count)
+                        .map(|i| self.point_at(i as f32 / count as f32))
+                        .chain([self.to])
+                        .collect()
+                }
+                None => vec![self.from, self.to],
+            },
+            Curve::Bezier { control1, control2 } => {
+                let (p0, p1, p2, p3) = (self.from, control1, control2, self.to);
+                // the polyline of `count` steps strays from the curve by 3/4 of its largest
+                // second difference divided by `count` squared, at most
+                let second = |a: Point, b: Point, c: Point| {
+                    Point::new(a.x - 2. * b.x + c.x, a.y - 2. * b.y + c.y)
+                        .distance_to_point(&Point::default())
+                };
+                let deviation = second(p0, p1, p2).max(second(p1, p2, p3));
+                let count = segments((0.75 * deviation / tolerance).sqrt());
+                (0..count)
+                    .map(|i| self.point_at(i as f32 / count as f32))
+                    .chain([self.to])
+                    .collect()
+            }
+        };
+
+        let edge = *self;
+        let mut dash_offset = self.dash_offset;
+        points
+            .windows(2)
+            .map(|pair| {
+                let segment = Edge {
+                    from: pair[0],
+                    to: pair[1],
+                    curve: Curve::Line,
+                    dash_offset,
+                    ..edge
+                };
+                dash_offset += segment.length();
+                segment
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// [`Edge::point_at`] of an edge relative to its origin.
+    fn local_point_at(&self, t: f32) -> Point {
+        match (self.curve, self.arc()) {
+            (Curve::Arc { center, .. }, Some((radius, start, sweep))) => {
+                let angle = start + sweep * t;
+                center.add(radius * angle.cos(), radius * angle.sin())
+            }
+            (Curve::Bezier { control1, control2 }, _) => {
+                let (p0, p1, p2, p3) = (self.from, control1, control2, self.to);
+                let u = 1. - t;
+                let (a, b, c, d) = (u * u * u, 3. * u * u * t, 3. * u * t * t, t * t * t);
+                Point::new(
+                    a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+                    a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+                )
+            }
+            _ => Point::new(
+                self.from.x + (self.to.x - self.from.x) * t,
+                self.from.y + (self.to.y - self.from.y) * t,
+            ),
+        }
+    }
+
+    /// [`Edge::arc`] of an edge relative to its origin.
+    fn local_arc(&self) -> Option<(f32, f32, f32)> {
+        let Curve::Arc {
+            center,
+            counterclockwise,
+        } = self.curve
+        else {
+            return None;
+        };
+        let radius = self.from.distance_to_point(&center);
+        if radius == 0. {
+            return None;
+        }
+        let angle = |point: Point| (point.y - center.y).atan2(point.x - center.x);
+        let start = angle(self.from);
+        let sweep = match (angle(self.to) - start).rem_euclid(TAU) {
+            0. if self.from == self.to => TAU,
+            sweep => sweep,
+        };
+        Some(match counterclockwise {
+            true => (radius, start, sweep - TAU),
+            false => (radius, start, sweep),
+        })
+    }
+
+    /// Middle of the chord from `from` to `to`, which curves do not go through in general.
+    pub fn midpoint(&self) -> Point {
+        Point::new(
+            (self.from.x + self.to.x) / 2.,
+            (self.from.y + self.to.y) / 2.,
+        )
+    }
+
+    /// Angle from the x axis to the chord from `from` to `to`, in degrees from 0 to 360,
+    /// counterclockwise as drawn, the y axis pointing down.
+    pub fn angle(&self) -> f32 {
+        (self.from.y - self.to.y)
+            .atan2(self.to.x - self.from.x)
+            .to_degrees()
+            .rem_euclid(360.)
+    }
+
+    /// Unit vector along the chord from `from` to `to`, or `None` when they are the same, as for
+    /// edges without length and full circles.
+    pub fn direction(&self) -> Option<(f32, f32)> {
+        let length = self.from.distance_to_point(&self.to);
+        (length > 0.).then(|| {
+            (
+                (self.to.x - self.from.x) / length,
+                (self.to.y - self.from.y) / length,
+            )
+        })
+    }
+
+    /// Unit vector perpendicular to the chord from `from` to `to`, a quarter turn counterclockwise
+    /// from its direction as drawn, or `None` when they are the same.
+    pub fn normal(&self) -> Option<(f32, f32)> {
+        self.direction().map(|(dx, dy)| (dy, -dx))
+    }
+
+    /// Clips the edge to the rectangle between `min` and `max` with the Cohen–Sutherland
+    /// algorithm. Returns `None` if the edge is entirely outside of the rectangle.
+    pub fn clip(&self, min: Point, max: Point) -> Option<Edge> {
+        const LEFT: u8 = 1;
+        const RIGHT: u8 = 2;
+        const TOP: u8 = 4;
+        const BOTTOM: u8 = 8;
+
+        let outcode = |p: &Point| {
+            let horizontal = if p.x < min.x {
+                LEFT
+            } else if p.x > max.x {
+                RIGHT
+            } else {
+                0
+            };
+            let vertical = if p.y < min.y {
+                TOP
+            } else if p.y > max.y {
+                BOTTOM
+            } else {
+                0
+            };
+            horizontal | vertical
+        };
+
+        let (mut from, mut to) = (self.from, self.to);
+        let (mut from_code, mut to_code) = (outcode(&from), outcode(&to));
+        loop {
+            if from_code | to_code == 0 {
+                // the dash pattern of the edge is not moved by clipping its start
+                let dash_offset = self.dash_offset + self.from.distance_to_point(&from);
+                return Some(Edge {
+                    from,
+                    to,
+                    dash_offset,
+                    ..*self
+                });
+            }
+            if from_code & to_code != 0 {
+                return None;
+            }
+
+            // move the outside end to the boundary it crosses
+            let code = if from_code != 0 { from_code } else { to_code };
+            let (dx, dy) = (to.x - from.x, to.y - from.y);
+            let point = if code & TOP != 0 {
+                Point::new(from.x + dx * (min.y - from.y) / dy, min.y)
+            } else if code & BOTTOM != 0 {
+                Point::new(from.x + dx * (max.y - from.y) / dy, max.y)
+            } else if code & RIGHT != 0 {
+                Point::new(max.x, from.y + dy * (max.x - from.x) / dx)
+            } else {
+                Point::new(min.x, from.y + dy * (min.x - from.x) / dx)
+            };
+
+            if code == from_code {
+                from = point;
+                from_code = outcode(&from);
+            } else {
+                to = point;
+                to_code = outcode(&to);
+            }
+        }
+    }
+
+    /// Draws the edge as a capsule: every pixel whose center is within half the width of the
+    /// segment is set.
+    fn draw_thick(&self, canvas: &mut Canvas) {
+        let radius = self.width / 2.;
+        let (top_left, bottom_right) = self.extent();
+
+        let (dx, dy) = (self.to.x - self.from.x, self.to.y - self.from.y);
+        let length_squared = dx * dx + dy * dy;
+
+        let min_x = (top_left.x - radius).floor().max(0.) as usize;
+        let min_y = (top_left.y - radius).floor().max(0.) as usize;
+        let max_x = ((bottom_right.x + radius).ceil() as usize).min(canvas.width.saturating_sub(1));
+        let max_y =
+            ((bottom_right.y + radius).ceil() as usize).min(canvas.height.saturating_sub(1));
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                // pixels are sampled at their integer coordinates, like thin edges are drawn
+                let (px, py) = (x as f32, y as f32);
+                let t = if length_squared == 0. {
+                    0.
+                } else {
+                    (((px - self.from.x) * dx + (py - self.from.y) * dy) / length_squared)
+                        .clamp(0., 1.)
+                };
+                let closest = self.from.add(t * dx, t * dy);
+
+                if (px - closest.x).powi(2) + (py - closest.y).powi(2) <= radius * radius {
+                    canvas.set(x, y, self.color);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Coordinate> Bound<T> for &Edge<T> {
+    fn boundaries(self) -> Option<(Point<T>, Point<T>)> {
+        Some(self.extent())
+    }
+}
+
+impl Edge {
+    /// [`Edge::extent`] of an edge relative to its origin.
+    fn local_extent(&self) -> (Point, Point) {
+        let points = match (self.curve, self.arc()) {
+            (Curve::Line, _) => vec![self.from, self.to],
+            (Curve::Arc { center, .. }, Some((radius, start, sweep))) => {
+                // arcs reach out the furthest at the quarter turns they go through
+                let mut points = vec![self.from, self.to];
+                for quarter in 0..4 {
+                    let angle = quarter as f32 * TAU / 4.;
+                    if ((angle - start) * sweep.signum()).rem_euclid(TAU) <= sweep.abs() {
+                        points.push(center.add(radius * angle.cos(), radius * angle.sin()));
+                    }
+                }
+                points
+            }
+            _ => self
+                .flatten(FLATNESS)
+                .flat_map(|edge| [edge.from, edge.to])
+                .collect(),
+        };
+        points
+            .iter()
+            .skip(1)
+            .fold((points[0], points[0]), |(min, max), point| {
+                (
+                    Point::new(min.x.min(point.x), min.y.min(point.y)),
+                    Point::new(max.x.max(point.x), max.y.max(point.y)),
+                )
+            })
+    }
+}
+
+impl Transformable for Edge {
+    /// Moves the ends and the curve of the edge by `transform`, its dash pattern following the
+    /// scaling. Arcs stay arcs, and are only moved exactly by transforms keeping circles.
+    fn transform(&mut self, transform: &Transform) {
+        self.from = transform.apply(self.from);
+        self.to = transform.apply(self.to);
+        self.dash_offset *= transform.determinant().abs().sqrt();
+        self.curve = match self.curve.map(|point| transform.apply(point)) {
+            // mirrors go round the other way
+            Curve::Arc {
+                center,
+                counterclockwise,
+            } if transform.determinant() < 0. => Curve::Arc {
+                center,
+                counterclockwise: !counterclockwise,
+            },
+            curve => curve,
+        };
+    }
+}
+
+impl Draw for Edge {
+    fn draw(&self, canvas: &mut Canvas) {
+        let color = self.color;
+
+        if color.as_rgba().3 == 0 {
+            return;
+        }
+
+        // curves are drawn as straight edges straying from them by a quarter of a pixel at most
+        if self.curve != Curve::Line {
+            self.flatten(0.25).for_each(|edge| edge.draw(canvas));
+            return;
+        }
+
+        if self.style != LineStyle::Solid {
+            self.dashes().for_each(|dash| dash.draw(canvas));
+            return;
+        }
+
+        if self.width > 1. {
+            self.draw_thick(canvas);
+            return;
+        }
+
+        let max = Point::new(canvas.width as f32 - 1., canvas.height as f32 - 1.);
+        if let Some(edge) = self.clip(Point::default(), max) {
+            edge.draw_thin(canvas);
+        }
+    }
+}
+
+impl Edge {
+    /// Solid edges covering the dashes of the edge, following its dash pattern from
+    /// `dash_offset` on.
+    fn dashes(&self) -> impl Iterator<Item = Edge> {
+        let length = self.length();
+        let pattern = self
+            .style
+            .pattern()
+            .map(|segment| segment * self.width.max(1.));
+        let period = pattern.iter().sum::<f32>();
+
+        let mut start = -(self.dash_offset % period);
+        let mut dashes = Vec::new();
+        while start < length {
+            for (i, segment) in pattern.iter().enumerate() {
+                let (from, to) = (start.max(0.), (start + segment).min(length));
+                if i % 2 == 0 && from < to {
+                    let point = |distance: f32| {
+                        let ratio = distance / length;
+                        Point::new(
+                            self.from.x + (self.to.x - self.from.x) * ratio,
+                            self.from.y + (self.to.y - self.from.y) * ratio,
+                        )
+                    };
+                    dashes.push(Edge {
+                        from: point(from),
+                        to: point(to),
+                        style: LineStyle::Solid,
+                        ..*self
+                    });
+                }
+                start += segment;
+            }
+        }
+
+        dashes.into_iter()
+    }
+
+    /// Draws the edge one pixel wide, the edge must be within the canvas.
+    fn draw_thin(&self, canvas: &mut Canvas) {
+        let color = self.color;
+
+        let x1 = self.from.x as i32;
+        let x2 = self.to.x as i32;
+        let y1 = self.from.y as i32;
+        let y2 = self.to.y as i32;
+
+        let dx = x2 - x1;
+        let dy = y2 - y1;
+
+        if dx == 0 {
+            let start_y = y1.min(y2) as usize;
+            for y in start_y..start_y + dy.unsigned_abs() as usize + 1 {
+                canvas.set(x1 as usize, y, color)
+            }
+            return;
+        }
+
+        let slope = dy as f32 / dx as f32;
+
+        if dx > 0 {
+            for step in 0..(dx + 1) as usize {
+                let x = x1 as usize + step;
+                let y = (self.from.y + (step as f32 * slope)) as usize;
+                canvas.set(x, y, color)
+            }
+        } else {
+            for x in 0..(dx.abs() + 1) {
+                let y = (self.from.y - (x as f32 * slope)) as usize;
+                let x = x1 as usize - x as usize;
+                canvas.set(x, y, color)
+            }
+        }
+    }
+}
+
+/// Location of the source code an element was created from.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SourceSpan {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset past the last character.
+    pub end: usize,
+    /// 1-based line of `start`.
+    pub line: usize,
+    /// 1-based column of `start`.
+    pub column: usize,
+}
+
+/// Point of a blueprint. Points are equal when at the same place, wherever they come from.
+#[derive(Debug, Copy, Clone, Default)]
+#[non_exhaustive]
+pub struct Point<T: Coordinate = f32> {
+    pub x: T,
+    pub y: T,
+    /// Command of the source that placed the point, the default span for points computed.
+    pub span: SourceSpan,
+}
+
+impl<T: Coordinate> PartialEq for Point<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl<T: Coordinate> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self {
+            x,
+            y,
+            span: SourceSpan::default(),
+        }
+    }
+}
+
+impl<T: Coordinate> Point<T> {
+    pub fn with_span(mut self, span: SourceSpan) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// The point with its coordinates converted to `U`.
+    pub fn cast<U: Coordinate>(self) -> Point<U> {
+        Point {
+            x: self.x.cast(),
+            y: self.y.cast(),
+            span: self.span,
+        }
+    }
+
+    /// The point scaled by `factor` about the origin, rounded to whole units.
+    fn scale(&self, factor: f32) -> Point<T> {
+        let scale = |value: T| T::from_f64((value.to_f64() * factor as f64).round());
+        Point {
+            x: scale(self.x),
+            y: scale(self.y),
+            span: self.span,
+        }
+    }
+
+    /// The point in `f32`, moved so that `origin` is the origin.
+    pub fn relative_to(&self, origin: Point<T>) -> Point {
+        Point {
+            x: (self.x - origin.x).to_f32(),
+            y: (self.y - origin.y).to_f32(),
+            span: self.span,
+        }
+    }
+
+    pub(crate) fn top_left(&self, other: &Self) -> Self {
+        let min = |a: T, b: T| if b < a { b } else { a };
+        Point {
+            x: min(self.x, other.x),
+            y: min(self.y, other.y),
+            span: SourceSpan::default(),
+        }
+    }
+
+    pub(crate) fn bottom_right(&self, other: &Self) -> Self {
+        let max = |a: T, b: T| if b > a { b } else { a };
+        Point {
+            x: max(self.x, other.x),
+            y: max(self.y, other.y),
+            span: SourceSpan::default(),
+        }
+    }
+
+    pub fn distance_to_point(&self, point: &Point<T>) -> f32 {
+        let (dx, dy) = ((self.x - point.x).to_f32(), (self.y - point.y).to_f32());
+        (dx.powf(2.) + dy.powf(2.)).sqrt()
+    }
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            span: SourceSpan::default(),
+        }
+    }
+
+    /// The point with its coordinates converted to `U` as [`Coordinate::from_decimal`] does,
+    /// keeping the decimal numbers they were written as.
+    pub fn widen<U: Coordinate>(self) -> Point<U> {
+        Point {
+            x: U::from_decimal(self.x),
+            y: U::from_decimal(self.y),
+            span: self.span,
+        }
+    }
+
+    pub fn add(&self, dx: f32, dy: f32) -> Self {
+        Point {
+            x: self.x + dx,
+            y: self.y + dy,
+            span: self.span,
+        }
+    }
+
+    /// The point, relative to `origin`, moved back to where `origin` is, see
+    /// [`Point::relative_to`].
+    pub fn at<T: Coordinate>(&self, origin: Point<T>) -> Point<T> {
+        Point {
+            x: origin.x + T::from_f32(self.x),
+            y: origin.y + T::from_f32(self.y),
+            span: self.span,
+        }
+    }
+
+    /// Closest point of the grid of `grid` units, e.g. `0.5`, or the point itself when `grid` is
+    /// not positive.
+    pub fn snap(&self, grid: f32) -> Point {
+        self.snap_with(grid, Rounding::Nearest)
+    }
+
+    /// Point of the grid of `grid` units `rounding` leads to, or the point itself when `grid` is
+    /// not positive.
+    pub fn snap_with(&self, grid: f32, rounding: Rounding) -> Point {
+        if grid <= 0. {
+            return *self;
+        }
+        let snap = |value: f32| {
+            let value = value / grid;
+            // adding zero turns -0 into 0
+            let value = match rounding {
+                Rounding::Nearest => value.round(),
+                Rounding::Down => value.floor(),
+                Rounding::Up => value.ceil(),
+                Rounding::TowardZero => value.trunc(),
+            };
+            value * grid + 0.
+        };
+        Point::new(snap(self.x), snap(self.y)).with_span(self.span)
+    }
+
+    pub fn distance_to_edge(&self, edge: &Edge) -> Option<(f32, Point)> {
+        if edge.curve != Curve::Line {
+            return edge
+                .flatten(FLATNESS)
+                .filter_map(|edge| self.distance_to_edge(&edge))
+                .min_by(|(a, _), (b, _)| a.total_cmp(b));
+        }
+        let point = edge.point_at(self.project_on_edge(edge));
+        Some((self.distance_to_point(&point), point))
+    }
+
+    /// Where the point is projected on the edge, taken as straight from its start to its end, as
+    /// the `t` of [`Edge::point_at`] of the closest point of the edge, from 0 to 1.
+    pub fn project_on_edge(&self, edge: &Edge) -> f32 {
+        // http://stackoverflow.com/questions/64330618/finding-the-projection-of-a-point-onto-a-line
+        let a = edge.from;
+        let b = edge.to;
+
+        let ab_dx = b.x - a.x;
+        let ab_dy = b.y - a.y;
+
+        let c = self;
+
+        let acx = c.x - a.x;
+        let acy = c.y - a.y;
+
+        let length_squared = ab_dx * ab_dx + ab_dy * ab_dy;
+        if length_squared == 0. {
+            return 0.;
+        }
+        ((ab_dx * acx + ab_dy * acy) / length_squared).clamp(0., 1.)
+    }
+}
+
+impl Transformable for Point {
+    fn transform(&mut self, transform: &Transform) {
+        *self = transform.apply(*self);
+    }
+}
+
+impl Draw for Point {
+    fn draw(&self, canvas: &mut Canvas) {
+        if self.x < 0. || self.y < 0. {
+            return;
+        }
+        let (x, y) = (self.x as usize, self.y as usize);
+        if x < canvas.width && y < canvas.height {
+            canvas.set(x, y, Color::Black);
+        }
+    }
+}
+
+/// Pattern edges are drawn with.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    pub const ALL: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+
+    /// Lengths of the dash and of the gap following it, in stroke widths.
+    pub fn pattern(&self) -> [f32; 2] {
+        match self {
+            LineStyle::Solid => [1., 0.],
+            LineStyle::Dashed => [6., 4.],
+            LineStyle::Dotted => [1., 3.],
+        }
+    }
+
+    /// The name of the style, as accepted by `TryFrom<&str>`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineStyle::Solid => "solid",
+            LineStyle::Dashed => "dashed",
+            LineStyle::Dotted => "dotted",
+        }
+    }
+}
+
+impl TryFrom<&str> for LineStyle {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        LineStyle::ALL
+            .into_iter()
+            .find(|style| style.name() == value)
+            .ok_or(())
+    }
+}
+
+/// Largest sine, or relative difference of lengths, between edges still satisfying a constraint.
+const CONSTRAINT_TOLERANCE: f32 = 1e-3;
+
+/// Geometric relation an edge is drawn to satisfy, checked when the blueprint is loaded, e.g.
+/// `[constraint:perpendicular(#a)]`. Other edges are referred to by the tag of their end, and
+/// curves by the straight line between their ends.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    Horizontal,
+    Vertical,
+    /// Perpendicular to the edge ending at the tag.
+    Perpendicular(String),
+    /// Parallel to the edge ending at the tag.
+    Parallel(String),
+    /// As long as the edge ending at the tag.
+    Equal(String),
+}
+
+impl Constraint {
+    /// Names of the constraints, the last three relating to the edge of a tag given in
+    /// parentheses.
+    pub const NAMES: [&str; 5] = [
+        "horizontal",
+        "vertical",
+        "perpendicular",
+        "parallel",
+        "equal",
+    ];
+
+    /// Constraint named `name`, relating to the edge ending at `tag`, `None` when there is no
+    /// such constraint or when `tag` is given to a constraint without one, or missing.
+    pub fn new(name: &str, tag: Option<&str>) -> Option<Self> {
+        match (name, tag) {
+            ("horizontal", None) => Some(Constraint::Horizontal),
+            ("vertical", None) => Some(Constraint::Vertical),
+            ("perpendicular", Some(tag)) => Some(Constraint::Perpendicular(tag.to_string())),
+            ("parallel", Some(tag)) => Some(Constraint::Parallel(tag.to_string())),
+            ("equal", Some(tag)) => Some(Constraint::Equal(tag.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Tag of the end of the edge the constraint relates to, if any.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Constraint::Horizontal | Constraint::Vertical => None,
+            Constraint::Perpendicular(tag) | Constraint::Parallel(tag) | Constraint::Equal(tag) => {
+                Some(tag)
+            }
+        }
+    }
+
+    /// Whether `edge` satisfies the constraint, `other` being the edge it relates to. Constraints
+    /// relating to an edge are satisfied when it is missing.
+    pub fn is_satisfied(&self, edge: &Edge, other: Option<&Edge>) -> bool {
+        let vector = |edge: &Edge| (edge.to.x - edge.from.x, edge.to.y - edge.from.y);
+        let (dx, dy) = vector(edge);
+        let length = edge.from.distance_to_point(&edge.to);
+        match (self, other) {
+            (Constraint::Horizontal, _) => dy.abs() <= CONSTRAINT_TOLERANCE * length,
+            (Constraint::Vertical, _) => dx.abs() <= CONSTRAINT_TOLERANCE * length,
+            (_, None) => true,
+            (Constraint::Perpendicular(_), Some(other)) => {
+                let (ox, oy) = vector(other);
+                let other_length = other.from.distance_to_point(&other.to);
+                (dx * ox + dy * oy).abs() <= CONSTRAINT_TOLERANCE * length * other_length
+            }
+            (Constraint::Parallel(_), Some(other)) => {
+                let (ox, oy) = vector(other);
+                let other_length = other.from.distance_to_point(&other.to);
+                (dx * oy - dy * ox).abs() <= CONSTRAINT_TOLERANCE * length * other_length
+            }
+            (Constraint::Equal(_), Some(other)) => {
+                let other_length = other.from.distance_to_point(&other.to);
+                (length - other_length).abs() <= CONSTRAINT_TOLERANCE * length.max(other_length)
+            }
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Horizontal => write!(f, "horizontal"),
+            Constraint::Vertical => write!(f, "vertical"),
+            Constraint::Perpendicular(tag) => write!(f, "perpendicular to #{tag}"),
+            Constraint::Parallel(tag) => write!(f, "parallel to #{tag}"),
+            Constraint::Equal(tag) => write!(f, "as long as #{tag}"),
+        }
+    }
+}
+
+/// g, b, b, alpha (true=transparent)
+pub type RgbaColor = (u8, u8, u8, u8);
+
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[allow(unused)]
+pub enum Color {
+    Transparent,
+    White,
+    #[default]
+    Black,
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Magenta,
+    Cyan,
+    Custom(RgbaColor),
+}
+
+impl Color {
+    /// Colors having a name.
+    pub const NAMED: [Color; 9] = [
+        Color::Transparent,
+        Color::White,
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Blue,
+        Color::Yellow,
+        Color::Magenta,
+        Color::Cyan,
+    ];
+
+    pub fn as_rgba(&self) -> RgbaColor {
+        match self {
+            Color::Transparent => (0, 0, 0, 0),
+            Color::White => (255, 255, 255, 255),
+            Color::Black => (0, 0, 0, 255),
+            Color::Red => (255, 0, 0, 255),
+            Color::Green => (0, 255, 0, 255),
+            Color::Blue => (0, 0, 255, 255),
+            Color::Yellow => (255, 255, 0, 255),
+            Color::Magenta => (255, 0, 255, 255),
+            Color::Cyan => (0, 255, 255, 255),
+            Color::Custom(c) => *c,
+        }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        matches!(self, Color::Transparent)
+    }
+
+    /// The name of the color, as accepted by `TryFrom<&str>`, or `None` for custom colors.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Color::Transparent => Some("transparent"),
+            Color::White => Some("white"),
+            Color::Black => Some("black"),
+            Color::Red => Some("red"),
+            Color::Green => Some("green"),
+            Color::Blue => Some("blue"),
+            Color::Yellow => Some("yellow"),
+            Color::Magenta => Some("magenta"),
+            Color::Cyan => Some("cyan"),
+            Color::Custom(_) => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for Color {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "transparent" => Ok(Color::Transparent),
+            "white" => Ok(Color::White),
+            "black" => Ok(Color::Black),
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            "blue" => Ok(Color::Blue),
+            "yellow" => Ok(Color::Yellow),
+            "magenta" => Ok(Color::Magenta),
+            "cyan" => Ok(Color::Cyan),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_thick_edge() {
+        let mut canvas = Canvas::new(7, 5);
+        Edge::new(1., 2., 5., 2., Color::Black, SourceSpan::default())
+            .with_width(3.)
+            .draw(&mut canvas);
+
+        assert_eq!(
+            rows(&canvas),
+            vec![".......", "#######", "#######", "#######", "......."]
+        );
+    }
+
+    #[test]
+    fn test_draw_clipped_edges() {
+        let mut canvas = Canvas::new(5, 4);
+        for (x1, y1, x2, y2) in [(-5., 1., 20., 1.), (2., -10., 2., -1.), (-2., 0., 6., 8.)] {
+            Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default()).draw(&mut canvas);
+        }
+
+        assert_eq!(rows(&canvas), vec![".....", "#####", "#....", ".#..."]);
+    }
+
+    #[test]
+    fn test_draw_dashed_edges() {
+        let edge = |x1, x2| {
+            Edge::new(x1, 0., x2, 0., Color::Black, SourceSpan::default())
+                .with_style(LineStyle::Dashed)
+        };
+
+        let mut canvas = Canvas::new(14, 1);
+        edge(0., 13.).draw(&mut canvas);
+        assert_eq!(rows(&canvas), vec!["#######...####"]);
+
+        // the pattern continues along connected edges
+        let mut canvas = Canvas::new(14, 1);
+        Shape::from(vec![edge(0., 3.), edge(3., 13.)]).draw(&mut canvas);
+        assert_eq!(rows(&canvas), vec!["#######...####"]);
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            Edge::new(0., 0., 10., 0., Color::Black, SourceSpan::default()),
+            Edge::new(10., 0., 10., 10., Color::Black, SourceSpan::default()),
+            Edge::new(10., 10., 20., 10., Color::Black, SourceSpan::default()),
+        ]));
+        blueprint.push(Shape::from(vec![Edge::new(
+            20.,
+            20.,
+            30.,
+            30.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        blueprint.tag("in".to_string(), Point::new(5., 0.));
+        blueprint.tag("out".to_string(), Point::new(20., 20.));
+
+        let cropped = blueprint.crop(Point::new(15., 5.), Point::new(5., -5.));
+
+        let edges = cropped
+            .shapes_iter()
+            .map(|shape| {
+                shape
+                    .edges_iter()
+                    .map(|edge| (edge.from, edge.to))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![vec![
+                (Point::new(0., 5.), Point::new(5., 5.)),
+                (Point::new(5., 5.), Point::new(5., 10.)),
+            ]]
+        );
+        assert_eq!(
+            cropped.tags_iter().collect::<Vec<_>>(),
+            vec![("in", &Point::new(0., 5.))]
+        );
+    }
+
+    #[test]
+    fn test_area() {
+        let edge = |x1, y1, x2, y2| Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default());
+        let mut blueprint = Blueprint::default();
+        // an L, made of a 10x10 square missing its 5x5 top-right corner
+        blueprint.push(Shape::from(vec![
+            edge(0., 0., 5., 0.),
+            edge(5., 0., 5., 5.),
+            edge(5., 5., 10., 5.),
+            edge(10., 5., 10., 10.),
+            edge(10., 10., 0., 10.),
+            edge(0., 10., 0., 0.),
+        ]));
+        blueprint.push(Shape::from(vec![
+            edge(1., 6., 3., 6.),
+            edge(3., 6., 3., 8.),
+            edge(3., 8., 1., 8.),
+            edge(1., 8., 1., 6.),
+        ]));
+        blueprint.push(Shape::from(vec![edge(0., 20., 10., 20.)]));
+
+        let shapes = blueprint.shapes_iter().collect::<Vec<_>>();
+        assert_eq!(shapes[0].area(), Some(75.));
+        assert_eq!(shapes[2].area(), None);
+        assert_eq!(shapes[0].perimeter(), 40.);
+        assert!(shapes[1].is_closed(0.));
+        assert!(!shapes[2].is_closed(0.));
+        assert!(shapes[0].contains(Point::new(2., 2.)));
+        assert!(!shapes[0].contains(Point::new(7., 2.)));
+        // a half disc, bounded by its diameter and an arc
+        let half_disc = Shape::from(vec![
+            edge(-1., 0., 1., 0.),
+            edge(1., 0., -1., 0.).with_curve(Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: false,
+            }),
+        ]);
+        assert!((half_disc.area().unwrap() - TAU / 4.).abs() < 0.05);
+        assert!(half_disc.contains(Point::new(0., 0.5)));
+        assert!(!half_disc.contains(Point::new(0., -0.5)));
+
+        assert_eq!(
+            blueprint
+                .find_enclosing_shape(Point::new(2., 7.))
+                .map(|(index, _)| index),
+            Some(1)
+        );
+        assert_eq!(
+            blueprint
+                .find_enclosing_shape(Point::new(8., 8.))
+                .map(|(index, _)| index),
+            Some(0)
+        );
+        assert!(blueprint.find_enclosing_shape(Point::new(7., 2.)).is_none());
+        assert_eq!(
+            blueprint
+                .edges_within(Point::new(10., 10.), Point::new(0., 5.))
+                .count(),
+            7
+        );
+        assert_eq!(
+            polygon_area(&[Point::new(0., 0.), Point::new(4., 0.), Point::new(0., 3.)]),
+            6.
+        );
+    }
+
+    #[test]
+    fn test_project_on_edge() {
+        let edge = Edge::new(0., 10., 10., 0., Color::Black, SourceSpan::default());
+        // beyond the end of a diagonal edge, the end is the closest
+        assert_eq!(Point::new(20., 5.).project_on_edge(&edge), 1.);
+        assert_eq!(
+            Point::new(20., 5.).distance_to_edge(&edge),
+            Some((11.18034, Point::new(10., 0.)))
+        );
+        assert_eq!(Point::new(10., 10.).project_on_edge(&edge), 0.5);
+        assert_eq!(Point::new(-5., 20.).project_on_edge(&edge), 0.);
+        assert_eq!(edge.point_at(0.25), Point::new(2.5, 7.5));
+
+        let arc = edge.with_curve(Curve::Arc {
+            center: Point::new(0., 0.),
+            counterclockwise: true,
+        });
+        let middle = arc.point_at(0.5);
+        assert!((middle.x - 7.0710).abs() < 0.001 && (middle.y - 7.0710).abs() < 0.001);
+        let point = Edge::new(1., 1., 1., 1., Color::Black, SourceSpan::default());
+        assert_eq!(Point::new(5., 5.).project_on_edge(&point), 0.);
+    }
+
+    #[test]
+    fn test_flatten() {
+        let line = Edge::new(0., 0., 10., 0., Color::Black, SourceSpan::default());
+        assert_eq!(line.flatten(0.1).collect::<Vec<_>>(), vec![line]);
+
+        // a quarter of a circle of radius 10, from the right of its center to the top of it
+        let arc = line.with_curve(Curve::Arc {
+            center: Point::new(0., 10.),
+            counterclockwise: true,
+        });
+        let arc = Edge {
+            from: Point::new(10., 10.),
+            to: Point::new(0., 0.),
+            ..arc
+        };
+        let segments = arc.flatten(0.1).collect::<Vec<_>>();
+        assert_eq!(segments.len(), 6);
+        assert_eq!(segments[0].from, Point::new(10., 10.));
+        assert_eq!(segments[5].to, Point::new(0., 0.));
+        assert!(segments.iter().all(|segment| {
+            let middle = segment.midpoint().distance_to_point(&Point::new(0., 10.));
+            segment.curve == Curve::Line && (10. - middle) <= 0.1
+        }));
+        assert!((arc.length() - 5. * std::f32::consts::PI).abs() < 0.05);
+        assert_eq!(
+            arc.boundaries(),
+            Some((Point::new(0., 0.), Point::new(10., 10.)))
+        );
+
+        // the other way round, it goes through the bottom of the circle
+        let arc = arc.with_curve(Curve::Arc {
+            center: Point::new(0., 10.),
+            counterclockwise: false,
+        });
+        assert!((arc.length() - 15. * std::f32::consts::PI).abs() < 0.05);
+        assert_eq!(arc.extent().1.y, 20.);
+
+        let bezier = line.with_curve(Curve::Bezier {
+            control1: Point::new(0., 10.),
+            control2: Point::new(10., 10.),
+        });
+        let segments = bezier.flatten(0.1).collect::<Vec<_>>();
+        assert_eq!(segments.first().unwrap().from, Point::new(0., 0.));
+        assert_eq!(segments.last().unwrap().to, Point::new(10., 0.));
+        assert!((bezier.extent().1.y - 7.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let mut blueprint = Blueprint::default();
+        assert_eq!(blueprint.boundaries(), None);
+        blueprint.push(Shape::from(vec![]));
+        assert_eq!(blueprint.boundaries(), None);
+        blueprint.translate_to_origin();
+
+        blueprint.push(Shape::from(vec![Edge::new(
+            3.,
+            4.,
+            1.,
+            2.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        assert_eq!(
+            blueprint.boundaries(),
+            Some((Point::new(1., 2.), Point::new(3., 4.)))
+        );
+        let canvas = Canvas::from(Blueprint::default());
+        assert_eq!((canvas.width, canvas.height), (1, 1));
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let edge = Edge::new(0.4, -0.2, 10.5, 0.1, Color::Black, SourceSpan::default());
+        let integer = edge.cast::<i32>();
+        assert_eq!(
+            (integer.from.x, integer.from.y, integer.to.x, integer.to.y),
+            (0, 0, 11, 0)
+        );
+        assert_eq!(integer.length(), 11.);
+        // f32 values widen to the decimal numbers they were written as
+        assert_eq!(edge.cast::<f64>().from.x, 0.4_f32 as f64);
+        assert_eq!(edge.from.widen::<f64>().x, 0.4);
+        assert_eq!(integer.cast::<f32>().to, Point::new(11., 0.));
+
+        // far from the origin, where f32 has no fractions, the geometry of f64 edges stays precise
+        let origin = Point::<f64> {
+            x: 1e8 + 0.5,
+            ..Default::default()
+        };
+        let local =
+            Edge::new(1., 0., 1., 0., Color::Black, SourceSpan::default()).with_curve(Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: false,
+            });
+        let circle = local.at(origin);
+        assert_eq!(circle.from.x, 1e8 + 1.5);
+        assert_eq!(circle.length(), local.length());
+        let (top_left, bottom_right) = (&circle).boundaries().unwrap();
+        assert_eq!((top_left.x, top_left.y), (1e8 - 0.5, -1.));
+        assert_eq!((bottom_right.x, bottom_right.y), (1e8 + 1.5, 1.));
+
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![circle]));
+        let (_, closest, distance) = blueprint
+            .find_closest_edge(Point::from((1e8 + 3.5, 0.)))
+            .unwrap();
+        assert_eq!((closest.x, closest.y, distance), (1e8 + 1.5, 0., 2.));
+        blueprint.translate_to_origin();
+        let circle = blueprint.edges_iter().next().unwrap();
+        assert_eq!((circle.from.x, circle.from.y), (2., 1.));
+    }
+
+    #[test]
+    fn test_visit() {
+        #[derive(Default)]
+        struct Counter {
+            visited: Vec<String>,
+        }
+
+        impl Visitor for Counter {
+            fn visit_shape(&mut self, shape: &Shape) {
+                self.visited
+                    .push(format!("shape of {}", shape.edges_iter().count()));
+            }
+
+            fn visit_edge(&mut self, _shape: &Shape, edge: &Edge) {
+                self.visited.push(format!("edge to {}", edge.to.x));
+            }
+
+            fn visit_tag(&mut self, tag: &str, _point: &Point) {
+                self.visited.push(format!("#{tag}"));
+            }
+        }
+
+        let edge = |x1, x2| Edge::new(x1, 0., x2, 0., Color::Black, SourceSpan::default());
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![edge(0., 1.), edge(1., 2.)]));
+        blueprint.push(Shape::from(vec![edge(2., 3.)]));
+        blueprint.tag("b".to_string(), Point::new(1., 0.));
+        blueprint.tag("a".to_string(), Point::new(0., 0.));
+
+        assert_eq!(
+            blueprint
+                .edges_iter()
+                .map(|edge| edge.to.x)
+                .collect::<Vec<_>>(),
+            vec![1., 2., 3.]
+        );
+        let mut counter = Counter::default();
+        blueprint.visit(&mut counter);
+        assert_eq!(
+            counter.visited,
+            vec![
+                "shape of 2",
+                "edge to 1",
+                "edge to 2",
+                "shape of 1",
+                "edge to 3",
+                "#a",
+                "#b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let edge = |x1, y1, x2, y2, color| Edge::new(x1, y1, x2, y2, color, SourceSpan::default());
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            edge(0., 0., 5., 0., Color::Black),
+            edge(5., 0., 10., 0., Color::Black),
+            edge(10., 0., 10., 10., Color::Black),
+            edge(10., 10., 0., 10., Color::Black),
+            edge(0., 10., 0., 0., Color::Black),
+        ]));
+        blueprint.push(Shape::from(vec![
+            edge(2., 2., 8., 8., Color::Red),
+            edge(10., 10., 20., 5., Color::Red),
+            edge(0., 0., -10., -10., Color::Transparent),
+        ]));
+
+        let hull = blueprint.convex_hull();
+        assert_eq!(
+            hull.edges_iter()
+                .map(|edge| (edge.from.x, edge.from.y))
+                .collect::<Vec<_>>(),
+            vec![(0., 0.), (10., 0.), (20., 5.), (10., 10.), (0., 10.)]
+        );
+        assert!(hull.is_closed(0.));
+
+        let mut line = Blueprint::default();
+        line.push(Shape::from(vec![edge(0., 0., 10., 0., Color::Black)]));
+        assert_eq!(line.convex_hull(), Shape::default());
+    }
+
+    #[test]
+    fn test_merge() {
+        let edge = |x1, y1, x2, y2| Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default());
+        let mut blueprint = Blueprint::default();
+        let walls = blueprint.layer("walls");
+        blueprint.push(Shape::from(vec![
+            edge(0., 0., 10., 0.).with_layer(Some(walls)),
+        ]));
+        blueprint.tag("door".to_string(), Point::new(5., 0.));
+        blueprint.tag("corner".to_string(), Point::new(10., 0.));
+
+        let mut other = Blueprint::default();
+        let furniture = other.layer("furniture");
+        let other_walls = other.layer("walls");
+        other.push(Shape::from(vec![
+            edge(0., 0., 0., 5.).with_layer(Some(other_walls)),
+            edge(0., 5., 5., 5.).with_layer(Some(furniture)),
+        ]));
+        other.tag("corner".to_string(), Point::new(0., 5.));
+        other.tag("window".to_string(), Point::new(5., 5.));
+
+        assert_eq!(
+            blueprint.merge(&other, Point::new(20., 0.)),
+            vec!["corner".to_string()]
+        );
+        // both blueprints have a single unnamed shape, identified the same
+        let ids = blueprint.shapes_iter().map(Shape::id).collect::<Vec<_>>();
+        assert_eq!(ids[0], other.shapes[0].id);
+        assert_ne!(ids[1], ids[0]);
+        assert_eq!(
+            blueprint
+                .layers_iter()
+                .map(|layer| layer.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["walls", "furniture"]
+        );
+        assert_eq!(
+            blueprint
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .map(|edge| (edge.from, edge.to, edge.layer))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point::new(0., 0.), Point::new(10., 0.), Some(0)),
+                (Point::new(20., 0.), Point::new(20., 5.), Some(0)),
+                (Point::new(20., 5.), Point::new(25., 5.), Some(1)),
+            ]
+        );
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![
+                ("corner", &Point::new(20., 5.)),
+                ("door", &Point::new(5., 0.)),
+                ("window", &Point::new(25., 5.)),
+            ]
+        );
+
+        blueprint.extend([Shape::from(vec![edge(0., 10., 10., 10.)])]);
+        assert_eq!(blueprint.shapes_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_composed() {
+        let edge = |x1, y1, x2, y2, layer| {
+            Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default()).with_layer(layer)
+        };
+        let mut blueprint = Blueprint::default();
+        let (walls, furniture, notes) = (
+            blueprint.layer("walls"),
+            blueprint.layer("furniture"),
+            blueprint.layer("notes"),
+        );
+        blueprint.push(Shape::from(vec![
+            edge(0., 0., 10., 0., Some(furniture)),
+            edge(10., 0., 10., 10., Some(walls)),
+            edge(10., 10., 0., 10., None),
+            edge(0., 10., 0., 0., Some(notes)),
+        ]));
+        assert_eq!(blueprint.composed().shapes, blueprint.shapes);
+
+        for layer in blueprint.layers_iter_mut() {
+            match layer.name.as_str() {
+                "walls" => layer.z_order = -1,
+                "furniture" => layer.color = Some(Color::Red),
+                _ => layer.visible = false,
+            }
+        }
+        assert_eq!(
+            blueprint
+                .composed()
+                .shapes_iter()
+                .map(|shape| {
+                    shape
+                        .edges_iter()
+                        .map(|edge| (edge.from, edge.color))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                vec![(Point::new(10., 0.), Color::Black)],
+                vec![
+                    (Point::new(0., 0.), Color::Red),
+                    (Point::new(10., 10.), Color::Black)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snap() {
+        let point = Point::new(1.26, -0.74);
+        assert_eq!(point.snap(0.5), Point::new(1.5, -0.5));
+        assert_eq!(point.snap(0.), point);
+        assert_eq!(point.snap_with(0.5, Rounding::Down), Point::new(1., -1.));
+        assert_eq!(point.snap_with(0.5, Rounding::Up), Point::new(1.5, -0.5));
+        assert_eq!(
+            point.snap_with(0.5, Rounding::TowardZero),
+            Point::new(1., -0.5)
+        );
+
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            Edge::new(0.1, 0.2, 9.8, 0.1, Color::Black, SourceSpan::default())
+                .with_style(LineStyle::Dashed),
+            Edge::new(9.8, 0.1, 10.3, 4.9, Color::Black, SourceSpan::default())
+                .with_style(LineStyle::Dashed),
+        ]));
+        blueprint.tag("corner".to_string(), Point::new(9.8, 0.1));
+        blueprint.snap_all(1., Rounding::Nearest);
+
+        let edges = blueprint.shapes[0].edges.clone();
+        assert_eq!(
+            edges
+                .iter()
+                .map(|edge| (edge.from, edge.to))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point::new(0., 0.), Point::new(10., 0.)),
+                (Point::new(10., 0.), Point::new(10., 5.))
+            ]
+        );
+        assert_eq!(edges[1].dash_offset, 10.);
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("corner", &Point::new(10., 0.))]
+        );
+    }
+
+    #[test]
+    fn test_offset() {
+        let polygon = |corners: &[(f32, f32)]| {
+            Shape::from(
+                corners
+                    .iter()
+                    .zip(corners.iter().cycle().skip(1))
+                    .map(|(&(x1, y1), &(x2, y2))| {
+                        Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let corners = |shape: Shape| {
+            shape
+                .edges_iter()
+                .map(|edge| (edge.from.x, edge.from.y))
+                .collect::<Vec<_>>()
+        };
+        let clockwise = polygon(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let counterclockwise = polygon(&[(0., 0.), (0., 10.), (10., 10.), (10., 0.)]);
+
+        for shape in [&clockwise, &counterclockwise] {
+            assert_eq!(shape.offset(1., Join::Miter).unwrap().area(), Some(144.));
+            assert_eq!(shape.offset(-1., Join::Miter).unwrap().area(), Some(64.));
+        }
+        assert_eq!(
+            corners(clockwise.offset(1., Join::Miter).unwrap()),
+            vec![(-1., -1.), (11., -1.), (11., 11.), (-1., 11.)]
+        );
+
+        let round = clockwise.offset(1., Join::Round).unwrap();
+        assert!(round.is_closed(0.));
+        assert_eq!(
+            round
+                .edges_iter()
+                .map(|edge| (edge.from.x, edge.from.y, edge.curve != Curve::Line))
+                .collect::<Vec<_>>(),
+            vec![
+                (0., -1., false),
+                (10., -1., true),
+                (11., 0., false),
+                (11., 10., true),
+                (10., 11., false),
+                (0., 11., true),
+                (-1., 10., false),
+                (-1., 0., true),
+            ]
+        );
+        assert!((round.perimeter() - (40. + TAU)).abs() < 0.05);
+        // insets meet at the corners, without arcs
+        assert_eq!(
+            corners(clockwise.offset(-1., Join::Round).unwrap()),
+            vec![(1., 1.), (9., 1.), (9., 9.), (1., 9.)]
+        );
+
+        // the miter of the sharp corner is beveled
+        let sharp = polygon(&[(0., 0.), (20., 1.), (0., 2.)]);
+        assert_eq!(
+            sharp.offset(1., Join::Miter).unwrap().edges_iter().count(),
+            4
+        );
+        assert_eq!(Shape::default().offset(1., Join::Miter), None);
+    }
+
+    #[test]
+    fn test_transform() {
+        let point = Point::new(3., 4.);
+        let transform =
+            Transform::rotation(Point::new(1., 1.), 90.).then(&Transform::translation(10., 0.));
+        let mut moved = point;
+        moved.rotate(Point::new(1., 1.), 90.);
+        moved.translate(10., 0.);
+        assert_eq!(transform.apply(point), moved);
+        assert_eq!(
+            Transform::scaling(2., 3.)
+                .then(&Transform::IDENTITY)
+                .apply(point),
+            Point::new(6., 12.)
+        );
+        assert_eq!(
+            Transform::mirror(Point::new(0., 0.), Point::new(1., 1.)).apply(point),
+            Point::new(4., 3.)
+        );
+
+        let arc = Edge::new(10., 0., 0., 10., Color::Black, SourceSpan::default())
+            .with_style(LineStyle::Dashed)
+            .with_curve(Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: false,
+            });
+        let mut mirrored = arc;
+        mirrored.mirror_x(0.);
+        assert_eq!(
+            mirrored.curve,
+            Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: true,
+            }
+        );
+        let mut scaled = Edge {
+            dash_offset: 1.,
+            ..arc
+        };
+        scaled.transform(&Transform::scaling(2., 2.));
+        assert_eq!((scaled.to, scaled.dash_offset), (Point::new(0., 20.), 2.));
+
+        // arcs are flattened by transforms not keeping circles
+        let mut shape = Shape::from(vec![arc]);
+        shape.transform(&Transform::scaling(2., 1.));
+        assert!(shape.edges_iter().all(|edge| edge.curve == Curve::Line));
+        assert_eq!(shape.edges_iter().last().unwrap().to, Point::new(0., 10.));
+    }
+
+    #[test]
+    fn test_geometry() {
+        let edge = Edge::new(0., 10., 30., -30., Color::Black, SourceSpan::default());
+        assert_eq!(edge.length(), 50.);
+        assert_eq!(edge.midpoint(), Point::new(15., -10.));
+        assert_eq!(edge.direction(), Some((0.6, -0.8)));
+        assert_eq!(edge.normal(), Some((-0.8, -0.6)));
+        assert!((edge.angle() - 53.130_1).abs() < 1e-3);
+
+        let edge = Edge::new(0., 0., -10., 0., Color::Black, SourceSpan::default());
+        assert_eq!(edge.angle(), 180.);
+        assert_eq!(edge.normal(), Some((0., 1.)));
+        let edge = Edge::new(5., 5., 5., 5., Color::Black, SourceSpan::default());
+        assert_eq!(edge.direction(), None);
+
+        // curves are measured along their chords
+        let arc = Edge::new(10., 0., -10., 0., Color::Black, SourceSpan::default()).with_curve(
+            Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: false,
+            },
+        );
+        assert_eq!(arc.direction(), Some((-1., 0.)));
+        assert_eq!(arc.midpoint(), Point::new(0., 0.));
+        let circle = Edge::new(10., 0., 10., 0., Color::Black, SourceSpan::default()).with_curve(
+            Curve::Arc {
+                center: Point::new(0., 0.),
+                counterclockwise: false,
+            },
+        );
+        assert_eq!(circle.direction(), None);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![Edge::new(
+            10.,
+            0.,
+            20.,
+            0.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        blueprint.tag("end".to_string(), Point::new(20., 0.));
+
+        blueprint.rotate(Point::new(10., 0.), 90.);
+        let edge = blueprint
+            .shapes_iter()
+            .next()
+            .unwrap()
+            .edges_iter()
+            .next()
+            .unwrap();
+        assert_eq!(
+            (edge.from, edge.to),
+            (Point::new(10., 0.), Point::new(10., -10.))
+        );
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("end", &Point::new(10., -10.))]
+        );
+
+        let mut point = Point::new(2., 0.);
+        point.rotate(Point::default(), -45.);
+        assert!((point.x - 2_f32.sqrt()).abs() < 1e-6);
+        assert!((point.y - 2_f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![Edge::new(
+            0.,
+            0.,
+            10.,
+            5.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        blueprint.tag("end".to_string(), Point::new(10., 5.));
+
+        blueprint.mirror_x(20.);
+        let ends = |blueprint: &Blueprint| {
+            let edge = blueprint
+                .shapes_iter()
+                .next()
+                .unwrap()
+                .edges_iter()
+                .next()
+                .unwrap();
+            (edge.from, edge.to)
+        };
+        assert_eq!(ends(&blueprint), (Point::new(40., 0.), Point::new(30., 5.)));
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("end", &Point::new(30., 5.))]
+        );
+
+        blueprint.mirror_y(0.);
+        assert_eq!(
+            ends(&blueprint),
+            (Point::new(40., 0.), Point::new(30., -5.))
+        );
+
+        let mut point = Point::new(3., 1.);
+        point.mirror(Point::new(0., 0.), Point::new(2., 2.));
+        assert_eq!(point, Point::new(1., 3.));
+    }
+
+    #[test]
+    fn test_is_closed() {
+        let shape = Shape::from(vec![
+            Edge::new(0., 0., 10., 0., Color::Black, SourceSpan::default()),
+            Edge::new(10., 0.1, 0., 10., Color::Black, SourceSpan::default()),
+            Edge::new(0., 10., 0., 0.2, Color::Black, SourceSpan::default()),
+        ]);
+        assert!(!shape.is_closed(0.));
+        assert!(!shape.is_closed(0.1));
+        assert!(shape.is_closed(0.2));
+        assert!(!Shape::default().is_closed(1.));
+    }
+
+    #[test]
+    fn test_diff() {
+        let edge = |x1, y1, x2, y2, color| Edge::new(x1, y1, x2, y2, color, SourceSpan::default());
+        let mut previous = Blueprint::default();
+        previous.push(Shape::from(vec![
+            edge(0., 0., 10., 0., Color::Black),
+            edge(10., 0., 10., 10., Color::Black),
+            edge(0., 5., 5., 5., Color::Black),
+        ]));
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            edge(10., 0., 0., 0., Color::Black),
+            edge(10., 0., 10., 10., Color::Red),
+        ]));
+        blueprint.push(Shape::from(vec![edge(0., 0., 10., 0., Color::Black)]));
+
+        let (added, removed) = blueprint.diff(&previous);
+        let ends = |edges: Vec<Edge>| {
+            edges
+                .iter()
+                .map(|edge| (edge.from, edge.to, edge.color))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ends(added),
+            vec![
+                (Point::new(10., 0.), Point::new(10., 10.), Color::Red),
+                (Point::new(0., 0.), Point::new(10., 0.), Color::Black)
+            ]
+        );
+        assert_eq!(
+            ends(removed),
+            vec![
+                (Point::new(10., 0.), Point::new(10., 10.), Color::Black),
+                (Point::new(0., 5.), Point::new(5., 5.), Color::Black)
+            ]
+        );
+        assert_eq!(blueprint.diff(&blueprint), (vec![], vec![]));
+    }
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        (0..canvas.height)
+            .map(|y| {
+                (0..canvas.width)
+                    .map(|x| {
+                        if canvas.get(x, y) == Color::Black {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::domain::{Blueprint, Color, Point};
+use crate::parser::{Command, CommandKind, parse};
+use crate::source_map::SourceMap;
+use std::fmt::Write;
+
+/// Describes, for debugging purposes, the commands parsed from `src` and `blueprint`, the blueprint
+/// they resolve to when it could be loaded: its edges with their absolute coordinates and colors,
+/// and its tags, with their location in the source.
+pub fn dump(src: &str, blueprint: Option<&Blueprint>) -> String {
+    let mut out = String::new();
+
+    let (commands, _) = parse(src);
+    writeln!(out, "commands:").unwrap();
+    dump_commands(&mut out, &commands, &SourceMap::new(src), 1);
+
+    if let Some(blueprint) = blueprint {
+        dump_blueprint(&mut out, blueprint);
+    }
+
+    out
+}
+
+fn dump_commands(out: &mut String, commands: &[Command], source_map: &SourceMap, depth: usize) {
+    for command in commands {
+        let span = source_map.locate(command.span);
+        let indent = "  ".repeat(depth);
+        let location = format!("{}:{}", span.line, span.column);
+
+        match &command.kind {
+            CommandKind::Error => writeln!(out, "{indent}error ({location})"),
+            CommandKind::Nested(name, commands) => {
+                match name {
+                    Some(name) => writeln!(out, "{indent}shape {name} ({location})"),
+                    None => writeln!(out, "{indent}block ({location})"),
+                }
+                .unwrap();
+                dump_commands(out, commands, source_map, depth + 1);
+                continue;
+            }
+            CommandKind::Include(include) => {
+                writeln!(out, "{indent}include {include:?} ({location})")
+            }
+            CommandKind::Move(coord) => writeln!(out, "{indent}move {coord} ({location})"),
+            CommandKind::Draw(coord, color, attributes) => {
+                let attributes = attributes
+                    .iter()
+                    .map(|attribute| format!(", {}:{}", attribute.key.node, attribute.value.node))
+                    .collect::<String>();
+                writeln!(
+                    out,
+                    "{indent}draw {coord} [color:{}{attributes}] ({location})",
+                    color_name(*color)
+                )
+            }
+        }
+        .unwrap();
+    }
+}
+
+fn dump_blueprint(out: &mut String, blueprint: &Blueprint) {
+    writeln!(out, "edges:").unwrap();
+    for (index, shape) in blueprint.shapes_iter().enumerate() {
+        match shape.name() {
+            Some(name) => writeln!(out, "  shape {index} {name}:"),
+            None => writeln!(out, "  shape {index}:"),
+        }
+        .unwrap();
+        for edge in shape.edges_iter() {
+            writeln!(
+                out,
+                "    {} -> {} {} ({}:{})",
+                point(&edge.from),
+                point(&edge.to),
+                color_name(edge.color),
+                edge.span.line,
+                edge.span.column
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "tags:").unwrap();
+    for (tag, position) in blueprint.tags_iter() {
+        writeln!(
+            out,
+            "  #{tag} {} ({}:{})",
+            point(position),
+            position.span.line,
+            position.span.column
+        )
+        .unwrap();
+    }
+}
+
+fn point(point: &Point) -> String {
+    format!("({}, {})", point.x, point.y)
+}
+
+fn color_name(color: Color) -> String {
+    match color.name() {
+        Some(name) => name.to_string(),
+        None => format!("{color:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_dump() {
+        let src = "{ move @10,10 #a\n  [color:red, opacity:50] 5,0 #b }\n@#a";
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+        let dump = dump(src, blueprint.as_ref());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            dump,
+            "commands:
+  block (1:1)
+    move @10,10 #a (1:3)
+    draw 5,0 #b [color:red, opacity:50] (2:27)
+  draw @#a [color:black] (3:1)
+edges:
+  shape 0:
+    (10, 10) -> (15, 10) red (2:27)
+  shape 1:
+    (0, 0) -> (10, 10) black (3:1)
+tags:
+  #a (10, 10) (1:3)
+  #b (15, 10) (2:27)
+"
+        );
+    }
+}
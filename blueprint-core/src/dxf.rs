@@ -0,0 +1,242 @@
+use crate::domain::{Blueprint, Color, Coordinate, Curve, Edge, FLATNESS};
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Layer of the edges without a layer, which every DXF drawing has.
+const DEFAULT_LAYER: &str = "0";
+
+/// AutoCAD R12 DXF drawing of a blueprint, made of one `LINE` entity per visible straight edge
+/// and one `ARC`, or `CIRCLE`, entity per visible arc. R12 has no splines: Bézier curves are
+/// flattened into `LINE` entities. The layers of the blueprint are the layers of the drawing, the
+/// edges without a layer being on the layer `0` every DXF drawing has.
+pub struct DxfDrawing<'b, T: Coordinate = f32> {
+    blueprint: &'b Blueprint<T>,
+}
+
+impl<T: Coordinate> DxfDrawing<'_, T> {
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<T: Coordinate> DxfDrawing<'_, T> {
+    /// Writes the `TABLES` section, holding the `LAYER` table: the layer `0`, then the layers of
+    /// the blueprint in their color, a negative color turning a hidden layer off.
+    fn layers(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let layers = self
+            .blueprint
+            .layers_iter()
+            .filter(|layer| layer.name != DEFAULT_LAYER)
+            .collect::<Vec<_>>();
+        write!(f, "0\nSECTION\n2\nTABLES\n")?;
+        write!(f, "0\nTABLE\n2\nLAYER\n70\n{}\n", layers.len() + 1)?;
+        write!(
+            f,
+            "0\nLAYER\n2\n{DEFAULT_LAYER}\n70\n0\n62\n7\n6\nCONTINUOUS\n"
+        )?;
+        for layer in layers {
+            let color = aci(layer.color.unwrap_or(Color::Black)) as i16;
+            let color = if layer.visible { color } else { -color };
+            write!(
+                f,
+                "0\nLAYER\n2\n{}\n70\n0\n62\n{color}\n6\nCONTINUOUS\n",
+                layer.name
+            )?;
+        }
+        write!(f, "0\nENDTAB\n0\nENDSEC\n")
+    }
+
+    /// Name of the layer of `edge` in the drawing.
+    fn layer(&self, edge: &Edge<T>) -> &str {
+        edge.layer
+            .and_then(|layer| self.blueprint.layers_iter().nth(layer))
+            .map_or(DEFAULT_LAYER, |layer| &layer.name)
+    }
+}
+
+impl<'b, T: Coordinate> From<&'b Blueprint<T>> for DxfDrawing<'b, T> {
+    fn from(value: &'b Blueprint<T>) -> Self {
+        Self { blueprint: value }
+    }
+}
+
+impl<T: Coordinate> Display for DxfDrawing<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // a DXF file is a list of (group code, value) pairs, each on its own line
+        write!(
+            f,
+            "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n0\nENDSEC\n"
+        )?;
+        self.layers(f)?;
+        write!(f, "0\nSECTION\n2\nENTITIES\n")?;
+
+        for edge in self.blueprint.edges_iter() {
+            if edge.color.is_transparent() {
+                continue;
+            }
+            let layer = self.layer(edge);
+
+            match (edge.curve, edge.arc()) {
+                (Curve::Arc { center, .. }, Some((radius, start, sweep))) => {
+                    // arcs go counterclockwise, in degrees, on the y axis pointing up, from
+                    // their start angle to their end angle
+                    let entity = match edge.from == edge.to {
+                        true => "CIRCLE",
+                        false => "ARC",
+                    };
+                    write!(f, "0\n{entity}\n8\n{layer}\n62\n{}\n", aci(edge.color))?;
+                    write!(
+                        f,
+                        "10\n{}\n20\n{}\n30\n0\n",
+                        center.x,
+                        T::default() - center.y
+                    )?;
+                    write!(f, "40\n{radius}\n")?;
+                    if edge.from != edge.to {
+                        // rounded to the thousandth, radians not being exact in degrees;
+                        // adding zero turns -0 into 0
+                        let degrees = |angle: f32| {
+                            ((-angle.to_degrees() * 1000.).round() / 1000.).rem_euclid(360.) + 0.
+                        };
+                        let (start, end) = match sweep > 0. {
+                            true => (degrees(start + sweep), degrees(start)),
+                            false => (degrees(start), degrees(start + sweep)),
+                        };
+                        write!(f, "50\n{start}\n51\n{end}\n")?;
+                    }
+                }
+                _ => {
+                    for segment in edge.flatten(FLATNESS) {
+                        line(f, &segment, layer)?;
+                    }
+                }
+            }
+        }
+
+        write!(f, "0\nENDSEC\n0\nEOF\n")
+    }
+}
+
+/// Writes the `LINE` entity of the straight `edge`, on `layer`.
+fn line<T: Coordinate>(f: &mut Formatter<'_>, edge: &Edge<T>, layer: &str) -> std::fmt::Result {
+    // the y axis of DXF points up, while the one of blueprints points down; `0 - y` avoids
+    // printing `-0`
+    let zero = T::default();
+    write!(f, "0\nLINE\n8\n{layer}\n62\n{}\n", aci(edge.color))?;
+    write!(
+        f,
+        "10\n{}\n20\n{}\n30\n0\n",
+        edge.from.x,
+        zero - edge.from.y
+    )?;
+    write!(f, "11\n{}\n21\n{}\n31\n0\n", edge.to.x, zero - edge.to.y)
+}
+
+/// AutoCAD color index of `color`. Black, white and custom colors use the index 7, which CAD
+/// tools display in the color contrasting with their background.
+fn aci(color: Color) -> u8 {
+    match color {
+        Color::Red => 1,
+        Color::Yellow => 2,
+        Color::Green => 3,
+        Color::Cyan => 4,
+        Color::Blue => 5,
+        Color::Magenta => 6,
+        Color::Transparent | Color::White | Color::Black | Color::Custom(_) => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Point, Shape};
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_dxf() {
+        let (blueprint, _) = load_blueprint_str(
+            "[color:red] 10,0 [color:transparent] 0,5 -10,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            DxfDrawing::from(&blueprint.unwrap()).to_string(),
+            "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n0\nENDSEC\n\
+             0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n70\n1\n\
+             0\nLAYER\n2\n0\n70\n0\n62\n7\n6\nCONTINUOUS\n0\nENDTAB\n0\nENDSEC\n\
+             0\nSECTION\n2\nENTITIES\n\
+             0\nLINE\n8\n0\n62\n1\n10\n0\n20\n0\n30\n0\n11\n10\n21\n0\n31\n0\n\
+             0\nLINE\n8\n0\n62\n7\n10\n10\n20\n-5\n30\n0\n11\n0\n21\n-5\n31\n0\n\
+             0\nENDSEC\n0\nEOF\n"
+        );
+    }
+
+    #[test]
+    fn test_dxf_layers() {
+        let (blueprint, _) = load_blueprint_str(
+            "[layer:walls] 10,0 [layer:electric] 0,5",
+            Path::new("test.bp"),
+            false,
+        );
+        let mut blueprint = blueprint.unwrap();
+        for layer in blueprint.layers_iter_mut() {
+            match layer.name.as_str() {
+                "walls" => layer.color = Some(Color::Red),
+                _ => layer.visible = false,
+            }
+        }
+        let dxf = DxfDrawing::from(&blueprint).to_string();
+
+        assert!(dxf.contains(
+            "0\nTABLE\n2\nLAYER\n70\n3\n\
+             0\nLAYER\n2\n0\n70\n0\n62\n7\n6\nCONTINUOUS\n\
+             0\nLAYER\n2\nwalls\n70\n0\n62\n1\n6\nCONTINUOUS\n\
+             0\nLAYER\n2\nelectric\n70\n0\n62\n-7\n6\nCONTINUOUS\n0\nENDTAB\n"
+        ));
+        assert!(dxf.contains("0\nLINE\n8\nwalls\n62\n7\n10\n0\n"));
+        assert!(dxf.contains("0\nLINE\n8\nelectric\n62\n7\n10\n10\n"));
+    }
+
+    #[test]
+    fn test_dxf_curves() {
+        let edge = |x1, y1, x2, y2, curve| {
+            Edge::new(x1, y1, x2, y2, Color::Black, Default::default()).with_curve(curve)
+        };
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            edge(
+                10.,
+                10.,
+                0.,
+                0.,
+                Curve::Arc {
+                    center: Point::new(0., 10.),
+                    counterclockwise: true,
+                },
+            ),
+            edge(
+                10.,
+                0.,
+                10.,
+                0.,
+                Curve::Arc {
+                    center: Point::new(10., 5.),
+                    counterclockwise: false,
+                },
+            ),
+        ]));
+
+        assert_eq!(
+            DxfDrawing::from(&blueprint).to_string(),
+            "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n0\nENDSEC\n\
+             0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n70\n1\n\
+             0\nLAYER\n2\n0\n70\n0\n62\n7\n6\nCONTINUOUS\n0\nENDTAB\n0\nENDSEC\n\
+             0\nSECTION\n2\nENTITIES\n\
+             0\nARC\n8\n0\n62\n7\n10\n0\n20\n-10\n30\n0\n40\n10\n50\n0\n51\n90\n\
+             0\nCIRCLE\n8\n0\n62\n7\n10\n10\n20\n-5\n30\n0\n40\n5\n\
+             0\nENDSEC\n0\nEOF\n"
+        );
+    }
+}
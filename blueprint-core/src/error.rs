@@ -0,0 +1,216 @@
+use crate::lexer::Span;
+use ariadne::{Label, Report, ReportKind, sources};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A diagnostic produced while reading, parsing or loading a blueprint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlueprintError {
+    pub severity: Severity,
+    pub message: String,
+    /// Byte range of the offending source, if the error relates to a specific location.
+    pub span: Option<Span>,
+    /// 1-based line of the start of `span`.
+    pub line: Option<usize>,
+    /// Additional locations that help understand the error, such as the enclosing construct.
+    pub labels: Vec<(String, Span)>,
+    /// File the span refers to, when it is not the loaded file itself but one it includes.
+    pub path: Option<PathBuf>,
+}
+
+impl BlueprintError {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span: None,
+            line: None,
+            labels: Vec::new(),
+            path: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    pub fn with_span(mut self, span: Span, line: usize) -> Self {
+        self.span = Some(span);
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_label(mut self, message: impl Into<String>, span: Span) -> Self {
+        self.labels.push((message.into(), span));
+        self
+    }
+
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for BlueprintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.path, self.line) {
+            (None, None) => write!(f, "{}: {}", self.severity, self.message),
+            (None, Some(line)) => write!(f, "{} (line {}): {}", self.severity, line, self.message),
+            (Some(path), None) => {
+                write!(
+                    f,
+                    "{} ({}): {}",
+                    self.severity,
+                    path.display(),
+                    self.message
+                )
+            }
+            (Some(path), Some(line)) => write!(
+                f,
+                "{} ({} line {}): {}",
+                self.severity,
+                path.display(),
+                line,
+                self.message
+            ),
+        }
+    }
+}
+
+/// Failure preventing an operation on blueprints from completing. The problems found in the
+/// blueprints themselves are `BlueprintError` diagnostics, reported along with the result when
+/// they are not fatal.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not open {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Could not write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    /// Writing to a stream that is not a file failed.
+    #[error("Could not write the output: {0}")]
+    Output(#[from] io::Error),
+    #[error("Could not watch {}: {source}", path.display())]
+    Watch { path: PathBuf, source: io::Error },
+    #[error("Could not listen on {address}: {source}")]
+    Listen { address: String, source: io::Error },
+    /// The blueprint could not be lexed, parsed or loaded, `errors` telling why.
+    #[error("{} has {} error(s)", path.display(), errors.iter().filter(|e| e.is_error()).count())]
+    Invalid {
+        path: PathBuf,
+        errors: Vec<BlueprintError>,
+    },
+    #[error("Unknown format {format}, expected one of {}", expected.join(", "))]
+    UnknownFormat {
+        format: String,
+        expected: Vec<String>,
+    },
+    /// An option, or a combination of options, is not valid.
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("Invalid manifest {}: {message}", path.display())]
+    Manifest { path: PathBuf, message: String },
+    #[error("{failed} of {total} blueprints could not be exported")]
+    Exports { failed: usize, total: usize },
+    /// The window of the UI could not be opened, or its rendering failed.
+    #[error("Could not launch the UI: {0}")]
+    Ui(String),
+}
+
+/// Prints `errors` to stdout as JSON, one object per line, for tools to consume. Locations are
+/// given with 1-based lines and columns, the column being counted in characters.
+pub fn report_json(path: &Path, src: &str, errors: &[BlueprintError]) {
+    for error in errors {
+        println!("{}", to_json(path, src, error));
+    }
+}
+
+/// JSON object describing `error`, found in `src` (read from `path`).
+pub fn to_json(path: &Path, src: &str, error: &BlueprintError) -> serde_json::Value {
+    let location = error.span.map(|span| {
+        let line_start = src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        serde_json::json!({
+            "start": span.start,
+            "end": span.end,
+            "line": error.line,
+            "column": src[line_start..span.start].chars().count() + 1,
+        })
+    });
+    serde_json::json!({
+        "path": error.path.as_deref().unwrap_or(path),
+        "severity": error.severity.to_string(),
+        "message": error.message,
+        "location": location,
+    })
+}
+
+/// Pretty-prints `errors` to stderr, pointing into `src` (read from `path`) when they carry a
+/// span. Errors in included files are pointed into those files.
+pub fn report(path: &Path, src: &str, errors: &[BlueprintError]) {
+    for error in errors {
+        let Some(span) = error.span else {
+            eprintln!("{error}");
+            continue;
+        };
+
+        let included_src;
+        let (filename, src) = match &error.path {
+            None => (path.display().to_string(), src),
+            Some(path) => match fs::read_to_string(path) {
+                Ok(src) => {
+                    included_src = src;
+                    (path.display().to_string(), included_src.as_str())
+                }
+                Err(_) => {
+                    eprintln!("{error}");
+                    continue;
+                }
+            },
+        };
+
+        let (kind, color) = match error.severity {
+            Severity::Error => (ReportKind::Error, ariadne::Color::Red),
+            Severity::Warning => (ReportKind::Warning, ariadne::Color::Yellow),
+        };
+
+        Report::build(kind, (filename.clone(), span.into_range()))
+            .with_config(ariadne::Config::new().with_index_type(ariadne::IndexType::Byte))
+            .with_message(&error.message)
+            .with_label(
+                Label::new((filename.clone(), span.into_range()))
+                    .with_message(&error.message)
+                    .with_color(color),
+            )
+            .with_labels(error.labels.iter().map(|(message, span)| {
+                Label::new((filename.clone(), span.into_range()))
+                    .with_message(message)
+                    .with_color(ariadne::Color::Yellow)
+            }))
+            .finish()
+            .eprint(sources([(filename.clone(), src)]))
+            .unwrap()
+    }
+}
@@ -0,0 +1,532 @@
+use crate::bmp::BmpImage;
+use crate::dimension::dimensioned;
+use crate::domain::{Blueprint, Color, Coordinate};
+use crate::dxf::DxfDrawing;
+use crate::error::Error;
+use crate::gcode::GcodeProgram;
+use crate::hpgl::HpglPlot;
+use crate::image::{BlueprintRaster, ImageEncoder, Monochrome};
+use crate::pam::PamImage;
+use crate::png::PngImage;
+use crate::ppm::PpmImage;
+use crate::ps::{Paper, PostScriptDocument};
+use crate::svg::SvgDrawing;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Padding around the exported images, in pixels.
+const PADDING: usize = 50;
+
+/// Height of the text of dimensions, in pixels or millimeters for plotters.
+const DIMENSION_SIZE: f32 = 10.;
+
+/// How blueprints are exported.
+pub struct ExportOptions {
+    /// Format of one of the exporters, `ppm` for the built-in ones.
+    pub format: String,
+    pub background: Color,
+    /// Pixels, or millimeters for plotters, per blueprint unit.
+    pub scale: f32,
+    pub supersample: usize,
+    /// Whether ppm images are written in their ASCII variant.
+    pub ascii: bool,
+    /// Speed of the drawing moves of G-code programs, in millimeters per minute.
+    pub feed_rate: f32,
+    pub tag_markers: bool,
+    /// Whether the dimensions of the edges are drawn, see [`dimensioned`].
+    pub dimensions: bool,
+    /// Whether colors are reduced to black and white, and how.
+    pub monochrome: Option<Monochrome>,
+    /// Size of the pages of PostScript documents.
+    pub paper: Paper,
+}
+
+impl ExportOptions {
+    /// Checks that the options are valid, and that the built-in exporter of the format supports
+    /// them.
+    pub fn validate(&self) -> Result<(), Error> {
+        Exporters::default().exporter(self).map(|_| ())
+    }
+}
+
+/// Writer of blueprints in a format.
+pub trait Exporter: Send + Sync {
+    /// Name of the format, which is also the extension of the files written.
+    fn format(&self) -> &str;
+
+    /// Whether the format supports a transparent background.
+    fn supports_transparency(&self) -> bool {
+        false
+    }
+
+    /// Writes `blueprint` to `writer`, the options having been validated. Blueprints are given in
+    /// `f64`, for the coordinates of large plans to be written as precisely as they were loaded.
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error>;
+}
+
+/// Exporters, by format. The default registry holds the built-in formats, to which other ones may
+/// be added with `register`.
+pub struct Exporters {
+    exporters: Vec<Box<dyn Exporter>>,
+}
+
+impl Exporters {
+    /// Adds `exporter`, replacing the one of the same format if any.
+    pub fn register(&mut self, exporter: impl Exporter + 'static) {
+        self.exporters
+            .retain(|registered| registered.format() != exporter.format());
+        self.exporters.push(Box::new(exporter));
+    }
+
+    pub fn get(&self, format: &str) -> Option<&dyn Exporter> {
+        self.exporters
+            .iter()
+            .find(|exporter| exporter.format() == format)
+            .map(Box::as_ref)
+    }
+
+    /// Formats of the exporters, in the order they were registered.
+    pub fn formats(&self) -> impl Iterator<Item = &str> {
+        self.exporters.iter().map(|exporter| exporter.format())
+    }
+
+    /// Exporter of the format of `options`, if the options are valid and it supports them.
+    fn exporter(&self, options: &ExportOptions) -> Result<&dyn Exporter, Error> {
+        let exporter = self
+            .get(&options.format)
+            .ok_or_else(|| Error::UnknownFormat {
+                format: options.format.clone(),
+                expected: self.formats().map(str::to_string).collect(),
+            })?;
+        if options.background.is_transparent() && !exporter.supports_transparency() {
+            let formats = self
+                .exporters
+                .iter()
+                .filter(|exporter| exporter.supports_transparency())
+                .map(|exporter| exporter.format())
+                .collect::<Vec<_>>();
+            return Err(Error::InvalidArgument(format!(
+                "A transparent background is only supported by the {} formats",
+                formats.join(", ")
+            )));
+        }
+        let positive = |value: f32| value > 0.;
+        if !positive(options.scale) || !positive(options.feed_rate) || options.supersample == 0 {
+            return Err(Error::InvalidArgument(
+                "The scale, supersampling and feed rate must be positive".to_string(),
+            ));
+        }
+        Ok(exporter)
+    }
+
+    /// Writes `blueprint`, as [`drawn`], to `filename`.
+    pub fn export<T: Coordinate>(
+        &self,
+        blueprint: &Blueprint<T>,
+        filename: &Path,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        let exporter = self.exporter(options)?;
+        let error = |source| Error::Write {
+            path: filename.to_path_buf(),
+            source,
+        };
+        let mut file = File::create(filename).map_err(error)?;
+        exporter
+            .export(&drawn(blueprint, options), &mut file, options)
+            .map_err(error)
+    }
+
+    /// Writes `blueprint`, as [`drawn`], to `writer`.
+    pub fn export_to<T: Coordinate>(
+        &self,
+        blueprint: &Blueprint<T>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        let exporter = self.exporter(options)?;
+        Ok(exporter.export(&drawn(blueprint, options), writer, options)?)
+    }
+
+    /// Writes each layer of `blueprint` to its own file, named after `filename` with the name of
+    /// the layer inserted before the extension. Edges without a layer are written to `filename`.
+    /// Returns the files written.
+    pub fn export_layers<T: Coordinate>(
+        &self,
+        blueprint: &Blueprint<T>,
+        filename: &Path,
+        options: &ExportOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut written = Vec::new();
+
+        let layers = blueprint.layers_iter().collect::<Vec<_>>();
+        for layer in [None].into_iter().chain((0..layers.len()).map(Some)) {
+            let blueprint = blueprint.layer_only(layer);
+            if blueprint.composed().shapes_iter().next().is_none() {
+                continue;
+            }
+
+            let filename = match layer {
+                None => filename.to_path_buf(),
+                Some(layer) => layer_filename(filename, &layers[layer].name),
+            };
+            self.export(&blueprint, &filename, options)?;
+            written.push(filename);
+        }
+
+        Ok(written)
+    }
+}
+
+impl Default for Exporters {
+    fn default() -> Self {
+        Self {
+            exporters: vec![
+                Box::new(Raster("ppm")),
+                Box::new(Raster("png")),
+                Box::new(Raster("bmp")),
+                Box::new(Raster("pam")),
+                Box::new(Svg),
+                Box::new(Dxf),
+                Box::new(Gcode),
+                Box::new(Hpgl),
+                Box::new(Ps),
+            ],
+        }
+    }
+}
+
+/// Writes `blueprint` to `filename`, with the built-in exporters.
+pub fn export<T: Coordinate>(
+    blueprint: &Blueprint<T>,
+    filename: &Path,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    Exporters::default().export(blueprint, filename, options)
+}
+
+/// Writes `blueprint` to `writer`, with the built-in exporters.
+pub fn export_to<T: Coordinate>(
+    blueprint: &Blueprint<T>,
+    writer: &mut dyn io::Write,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    Exporters::default().export_to(blueprint, writer, options)
+}
+
+/// Writes each layer of `blueprint` to its own file, with the built-in exporters, as
+/// [`Exporters::export_layers`] does.
+pub fn export_layers<T: Coordinate>(
+    blueprint: &Blueprint<T>,
+    filename: &Path,
+    options: &ExportOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    Exporters::default().export_layers(blueprint, filename, options)
+}
+
+/// Blueprint written by the exporters, in `f64`: as [`Blueprint::composed`] according to its
+/// layers, with the dimensions of its edges when requested.
+fn drawn<T: Coordinate>(blueprint: &Blueprint<T>, options: &ExportOptions) -> Blueprint<f64> {
+    let composed = blueprint.composed().cast();
+    if options.dimensions {
+        dimensioned(&composed, DIMENSION_SIZE / options.scale)
+    } else {
+        composed
+    }
+}
+
+/// Blueprint drawn by vector formats. Plotters and CAD tools have no notion of dithering, the
+/// edges of monochrome drawings are simply drawn in black.
+fn vector<'b>(blueprint: &'b Blueprint<f64>, options: &ExportOptions) -> Cow<'b, Blueprint<f64>> {
+    match options.monochrome {
+        Some(_) => Cow::Owned(blueprint.monochrome()),
+        None => Cow::Borrowed(blueprint),
+    }
+}
+
+/// Image formats, drawn on a canvas.
+struct Raster(&'static str);
+
+impl Exporter for Raster {
+    fn format(&self) -> &str {
+        self.0
+    }
+
+    fn supports_transparency(&self) -> bool {
+        ["png", "pam"].contains(&self.0)
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        // rasterize at a higher resolution, then average blocks of pixels to smooth the edges
+        let factor = options.scale * options.supersample as f32;
+        let raster = BlueprintRaster::new(
+            blueprint.cast::<f32>().scale(factor).scale_widths(factor),
+            options.background,
+            options.supersample,
+            PADDING,
+        )
+        .tag_markers(options.tag_markers)
+        .monochrome(options.monochrome);
+
+        let image: Box<dyn ImageEncoder> = match self.0 {
+            "png" => Box::new(PngImage::from(&raster)),
+            "bmp" => Box::new(BmpImage::from(&raster)),
+            "pam" => Box::new(PamImage::from(&raster)),
+            _ => Box::new(PpmImage::from(&raster).binary(!options.ascii)),
+        };
+        image.write_to(writer)
+    }
+}
+
+struct Svg;
+
+impl Exporter for Svg {
+    fn format(&self) -> &str {
+        "svg"
+    }
+
+    fn supports_transparency(&self) -> bool {
+        true
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        SvgDrawing::from(vector(blueprint, options).as_ref())
+            .scale(options.scale)
+            .padding(PADDING as f32 / options.scale)
+            .background(options.background)
+            .write_to(writer)
+    }
+}
+
+struct Dxf;
+
+impl Exporter for Dxf {
+    fn format(&self) -> &str {
+        "dxf"
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        DxfDrawing::from(vector(blueprint, options).as_ref()).write_to(writer)
+    }
+}
+
+struct Gcode;
+
+impl Exporter for Gcode {
+    fn format(&self) -> &str {
+        "gcode"
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        GcodeProgram::from(vector(blueprint, options).as_ref())
+            .feed_rate(options.feed_rate)
+            .scale(options.scale)
+            .write_to(writer)
+    }
+}
+
+struct Hpgl;
+
+impl Exporter for Hpgl {
+    fn format(&self) -> &str {
+        "hpgl"
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        HpglPlot::from(vector(blueprint, options).as_ref())
+            .scale(options.scale)
+            .write_to(writer)
+    }
+}
+
+struct Ps;
+
+impl Exporter for Ps {
+    fn format(&self) -> &str {
+        "ps"
+    }
+
+    fn export(
+        &self,
+        blueprint: &Blueprint<f64>,
+        writer: &mut dyn io::Write,
+        options: &ExportOptions,
+    ) -> Result<(), io::Error> {
+        PostScriptDocument::from(vector(blueprint, options).as_ref())
+            .scale(options.scale)
+            .paper(options.paper)
+            .write_to(writer)
+    }
+}
+
+/// `plan.ppm` becomes `plan.<layer>.ppm`.
+fn layer_filename(filename: &Path, layer: &str) -> PathBuf {
+    let stem = filename.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match filename.extension() {
+        Some(extension) => format!("{stem}.{layer}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{layer}"),
+    };
+    filename.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{load_blueprint_str, load_blueprint_str_as};
+
+    #[test]
+    fn test_validate() {
+        let options = |format: &str, background| ExportOptions {
+            format: format.to_string(),
+            background,
+            scale: 1.,
+            supersample: 1,
+            ascii: false,
+            feed_rate: 1000.,
+            tag_markers: false,
+            dimensions: false,
+            monochrome: None,
+            paper: Paper::A4,
+        };
+
+        assert!(options("png", Color::Transparent).validate().is_ok());
+        assert!(matches!(
+            options("pdf", Color::White).validate(),
+            Err(Error::UnknownFormat { format, .. }) if format == "pdf"
+        ));
+        assert!(matches!(
+            options("ppm", Color::Transparent).validate(),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ExportOptions {
+                scale: 0.,
+                ..options("ppm", Color::White)
+            }
+            .validate(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_register() {
+        struct Csv;
+
+        impl Exporter for Csv {
+            fn format(&self) -> &str {
+                "csv"
+            }
+
+            fn export(
+                &self,
+                blueprint: &Blueprint<f64>,
+                writer: &mut dyn io::Write,
+                _options: &ExportOptions,
+            ) -> Result<(), io::Error> {
+                for edge in blueprint.shapes_iter().flat_map(|shape| shape.edges_iter()) {
+                    writeln!(
+                        writer,
+                        "{},{},{},{}",
+                        edge.from.x, edge.from.y, edge.to.x, edge.to.y
+                    )?;
+                }
+                Ok(())
+            }
+        }
+
+        let (blueprint, _) = load_blueprint_str("10,0 0,5", Path::new("test.bp"), false);
+        let options = ExportOptions {
+            format: "csv".to_string(),
+            background: Color::White,
+            scale: 1.,
+            supersample: 1,
+            ascii: false,
+            feed_rate: 1000.,
+            tag_markers: false,
+            dimensions: false,
+            monochrome: None,
+            paper: Paper::A4,
+        };
+        let mut exporters = Exporters::default();
+        assert!(matches!(
+            exporters.export_to(&blueprint.clone().unwrap(), &mut Vec::new(), &options),
+            Err(Error::UnknownFormat { expected, .. }) if expected.len() == 9
+        ));
+
+        exporters.register(Csv);
+        let mut csv = Vec::new();
+        exporters
+            .export_to(&blueprint.unwrap(), &mut csv, &options)
+            .unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "0,0,10,0\n10,0,10,5\n");
+    }
+
+    #[test]
+    fn test_precision() {
+        let (blueprint, _) =
+            load_blueprint_str_as::<f64>("1,0 move @16777217,0 1,0", Path::new("test.bp"), false);
+        let options = ExportOptions {
+            format: "svg".to_string(),
+            background: Color::White,
+            scale: 1.,
+            supersample: 1,
+            ascii: false,
+            feed_rate: 1000.,
+            tag_markers: false,
+            dimensions: false,
+            monochrome: None,
+            paper: Paper::A4,
+        };
+        let mut svg = Vec::new();
+        export_to(&blueprint.unwrap(), &mut svg, &options).unwrap();
+        assert!(
+            String::from_utf8(svg)
+                .unwrap()
+                .contains(r#"<line x1="16777217" y1="0" x2="16777218" y2="0""#)
+        );
+    }
+
+    #[test]
+    fn test_layer_filename() {
+        assert_eq!(
+            layer_filename(Path::new("out/plan.ppm"), "walls"),
+            Path::new("out/plan.walls.ppm")
+        );
+        assert_eq!(
+            layer_filename(Path::new("plan"), "walls"),
+            Path::new("plan.walls")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::domain::Color;
+use crate::error::BlueprintError;
+use crate::lexer::{Span, comments, escape};
+use crate::parser::{Attribute, Command, CommandKind, parse};
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+/// Reprints `src` in its canonical form: one command per line, nested blocks indented, attributes
+/// of consecutive edges aligned and spacing normalized. Comments are kept, and consecutive blank
+/// lines are collapsed into one.
+///
+/// Sources that do not parse are not formatted, their errors are returned instead.
+pub fn format(src: &str) -> Result<String, Vec<BlueprintError>> {
+    let (commands, errors) = parse(src);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut formatter = Formatter {
+        src,
+        comments: comments(src),
+        next_comment: 0,
+        last_end: 0,
+        block_start: true,
+        out: String::new(),
+    };
+    formatter.block(&commands, 0);
+    formatter.comments_before(src.len(), 0);
+
+    Ok(formatter.out)
+}
+
+struct Formatter<'s> {
+    src: &'s str,
+    /// Spans of the comments of `src`, in order.
+    comments: Vec<Span>,
+    next_comment: usize,
+    /// End of the last part of `src` that was written.
+    last_end: usize,
+    /// Whether nothing was written yet in the current block.
+    block_start: bool,
+    out: String,
+}
+
+impl Formatter<'_> {
+    fn block(&mut self, commands: &[Command], depth: usize) {
+        let attributes = commands
+            .iter()
+            .map(|command| match &command.kind {
+                CommandKind::Draw(_, color, attributes) => {
+                    Some(attributes_text(*color, attributes))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // edges separated by a blank line or a comment are not aligned together
+        let separated = commands
+            .iter()
+            .scan(None, |previous_end: &mut Option<usize>, command| {
+                let start = self.start(command);
+                let between = &self.src[previous_end.unwrap_or(start)..start];
+                *previous_end = Some(command.span.end);
+                // a comment on the line of the previous command does not separate it
+                let lines_between = between.split_once('\n').map_or("", |(_, rest)| rest);
+                Some(lines_between.contains("//") || lines_between.contains('\n'))
+            })
+            .collect::<Vec<_>>();
+        let widths = aligned_widths(&attributes, &separated);
+
+        for ((command, attributes), width) in commands.iter().zip(attributes).zip(widths) {
+            let start = self.start(command);
+            self.comments_before(start, depth);
+            self.start_line(start, depth);
+
+            match &command.kind {
+                CommandKind::Error => unreachable!("only sources without errors are formatted"),
+                CommandKind::Nested(name, commands) => {
+                    if let Some(name) = name {
+                        write!(self.out, "shape {name} ").unwrap();
+                    }
+                    self.out.push('{');
+                    let open = self.src[command.span.into_range()].find('{').unwrap_or(0);
+                    self.end_line(command.span.start + open + 1);
+                    self.block_start = true;
+                    self.block(commands, depth + 1);
+                    self.comments_before(command.span.end - 1, depth + 1);
+                    self.start_line(command.span.end - 1, depth);
+                    self.out.push('}');
+                }
+                CommandKind::Include(path) => {
+                    write!(self.out, "include \"{}\"", escape(path)).unwrap();
+                }
+                CommandKind::Move(coord) => write!(self.out, "move {coord}").unwrap(),
+                CommandKind::Draw(coord, _, _) => match attributes {
+                    Some(attributes) if width > 0 => {
+                        write!(self.out, "{attributes:width$} {coord}").unwrap()
+                    }
+                    _ => write!(self.out, "{coord}").unwrap(),
+                },
+            }
+            self.end_line(command.span.end);
+        }
+    }
+
+    /// Start of `command` in the source. The span of edges does not include their attributes,
+    /// which are looked up backwards from the coordinate.
+    fn start(&self, command: &Command) -> usize {
+        let before = &self.src[..command.span.start];
+        match command.kind {
+            CommandKind::Draw(..) if before.trim_end().ends_with(']') => {
+                before.rfind('[').unwrap_or(command.span.start)
+            }
+            _ => command.span.start,
+        }
+    }
+
+    /// Writes the comments starting before `position`, each on its own line.
+    fn comments_before(&mut self, position: usize, depth: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment).copied()
+            && comment.start < position
+        {
+            self.start_line(comment.start, depth);
+            self.out.push_str(&self.src[comment.into_range()]);
+            self.last_end = comment.end;
+            self.out.push('\n');
+            self.next_comment += 1;
+        }
+    }
+
+    /// Starts a line for the source at `position`, preceded by a blank line if there was at
+    /// least one in the source since the last written part.
+    fn start_line(&mut self, position: usize, depth: usize) {
+        if !self.block_start && self.src[self.last_end..position].matches('\n').count() > 1 {
+            self.out.push('\n');
+        }
+        self.block_start = false;
+        self.out.push_str(&INDENT.repeat(depth));
+    }
+
+    /// Ends the line of the source ending at `position`, keeping the comment that follows it on
+    /// the same line. The `;` ending commands are dropped.
+    fn end_line(&mut self, position: usize) {
+        self.last_end = position;
+        if let Some(comment) = self.comments.get(self.next_comment).copied()
+            && self.src[position..comment.start]
+                .trim_matches(|c: char| c.is_whitespace() || c == ';')
+                .is_empty()
+            && !self.src[position..comment.start].contains('\n')
+        {
+            self.out.push(' ');
+            self.out.push_str(&self.src[comment.into_range()]);
+            self.last_end = comment.end;
+            self.next_comment += 1;
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Formats the attributes of an edge, omitting the color when it is the default one.
+fn attributes_text(color: Color, attributes: &[Attribute]) -> String {
+    let color = (color != Color::default())
+        .then(|| color.name())
+        .flatten()
+        .map(|color| format!("color:{color}"));
+    let attributes = color
+        .into_iter()
+        .chain(
+            attributes
+                .iter()
+                .map(|attribute| format!("{}:{}", attribute.key.node, attribute.value.node)),
+        )
+        .collect::<Vec<_>>();
+
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", attributes.join(", "))
+    }
+}
+
+/// Computes the width to which each edge's attributes are padded, so that coordinates of
+/// consecutive edges are aligned. Groups of consecutive edges are ended by other commands (whose
+/// `attributes` are `None`) and by edges that are `separated` from the previous command.
+fn aligned_widths(attributes: &[Option<String>], separated: &[bool]) -> Vec<usize> {
+    let mut widths = vec![0; attributes.len()];
+
+    let mut start = 0;
+    while start < attributes.len() {
+        if attributes[start].is_none() {
+            start += 1;
+            continue;
+        }
+        let end = (start + 1..attributes.len())
+            .find(|&i| attributes[i].is_none() || separated[i])
+            .unwrap_or(attributes.len());
+        let width = attributes[start..end]
+            .iter()
+            .flatten()
+            .map(String::len)
+            .max()
+            .unwrap_or(0);
+        widths[start..end].fill(width);
+        start = end;
+    }
+
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let src = r#"// a square
+{move   @0,0 #p0; [color:red]5,0; // top
+0,5 [color:black,style:dashed] - 5 , 0
+// back to start
+
+
+@#p0 shape   inner{  @ #p0.x+1 ,-2 } }
+include   "a \"b\".bp"
+// end
+"#;
+
+        assert_eq!(
+            format(src),
+            Ok(r#"// a square
+{
+  move @0,0 #p0
+  [color:red]    5,0 // top
+                 0,5
+  [style:dashed] -5,0
+  // back to start
+
+  @#p0
+  shape inner {
+    @#p0.x + 1,-2
+  }
+}
+include "a \"b\".bp"
+// end
+"#
+            .to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let src = include_str!("../../examples/example.bp");
+        let formatted = format(src).unwrap();
+        assert_eq!(format(&formatted), Ok(formatted));
+    }
+}
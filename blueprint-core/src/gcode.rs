@@ -0,0 +1,103 @@
+use crate::domain::{Blueprint, Coordinate, Point};
+use crate::toolpath::toolpaths;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Height of the pen above the paper while moving between toolpaths, in millimeters.
+const PEN_UP: f32 = 5.;
+
+/// G-code program drawing a blueprint with a pen plotter or a CNC machine: the pen is lowered
+/// along the Z axis to draw each toolpath and raised in between.
+pub struct GcodeProgram<'b, T: Coordinate = f32> {
+    blueprint: &'b Blueprint<T>,
+    feed_rate: f32,
+    scale: f32,
+}
+
+impl<T: Coordinate> GcodeProgram<'_, T> {
+    /// Speed of the drawing moves, in millimeters per minute.
+    pub fn feed_rate(mut self, feed_rate: f32) -> Self {
+        self.feed_rate = feed_rate;
+        self
+    }
+
+    /// Millimeters per blueprint unit.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+
+    /// `X` and `Y` words of `point`, scaled by `scale`. The y axis of machines points up, while the
+    /// one of blueprints points down; `0 - y` avoids printing `-0`.
+    fn coordinates(point: Point<T>, scale: T) -> String {
+        format!("X{} Y{}", point.x * scale, T::default() - point.y * scale)
+    }
+}
+
+impl<'b, T: Coordinate> From<&'b Blueprint<T>> for GcodeProgram<'b, T> {
+    fn from(value: &'b Blueprint<T>) -> Self {
+        Self {
+            blueprint: value,
+            feed_rate: 1000.,
+            scale: 1.,
+        }
+    }
+}
+
+impl<T: Coordinate> Display for GcodeProgram<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let scale = T::from_decimal(self.scale);
+        writeln!(f, "G21 ; millimeters")?;
+        writeln!(f, "G90 ; absolute coordinates")?;
+        writeln!(f, "G0 Z{PEN_UP}")?;
+
+        for toolpath in toolpaths(self.blueprint) {
+            writeln!(f, "G0 {}", Self::coordinates(toolpath.points[0], scale))?;
+            writeln!(f, "G1 Z0 F{}", self.feed_rate)?;
+            for point in &toolpath.points[1..] {
+                writeln!(f, "G1 {}", Self::coordinates(*point, scale))?;
+            }
+            writeln!(f, "G0 Z{PEN_UP}")?;
+        }
+
+        writeln!(f, "G0 X0 Y0")?;
+        writeln!(f, "M2")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_gcode() {
+        let (blueprint, _) = load_blueprint_str(
+            "10,0 0,5 [color:transparent] -10,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            GcodeProgram::from(&blueprint.unwrap())
+                .feed_rate(500.)
+                .scale(2.)
+                .to_string(),
+            "G21 ; millimeters\n\
+             G90 ; absolute coordinates\n\
+             G0 Z5\n\
+             G0 X0 Y0\n\
+             G1 Z0 F500\n\
+             G1 X20 Y0\n\
+             G1 X20 Y-10\n\
+             G0 Z5\n\
+             G0 X0 Y0\n\
+             M2\n"
+        );
+    }
+}
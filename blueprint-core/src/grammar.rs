@@ -0,0 +1,210 @@
+use crate::domain::{Color, Constraint, LineStyle};
+use crate::lexer::{ESCAPES, KEYWORDS, PUNCTUATION, Token};
+use crate::parser::KNOWN_ATTRIBUTES;
+use serde_json::json;
+use std::fmt::{Display, Formatter};
+
+/// Numbers, as lexed: `1_000`, `2.5` or `2.5e3`.
+const NUMBER: &str = r"\b\d+(?:_\d+)*(?:\.\d+(?:_\d+)*)?(?:[eE][+-]?\d+(?:_\d+)*)?\b";
+
+/// Tags, as lexed: `#` followed by letters, digits, `_` and `-`.
+const TAG: &str = r"#[\w-]*";
+
+/// Formats of the syntax-highlighting definitions of editors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrammarFormat {
+    /// TextMate grammar, as used by VS Code, Sublime Text or Zed.
+    TextMate,
+    /// Vim syntax file.
+    Vim,
+}
+
+impl GrammarFormat {
+    pub const ALL: [GrammarFormat; 2] = [GrammarFormat::TextMate, GrammarFormat::Vim];
+}
+
+impl Display for GrammarFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrammarFormat::TextMate => write!(f, "textmate"),
+            GrammarFormat::Vim => write!(f, "vim"),
+        }
+    }
+}
+
+impl TryFrom<&str> for GrammarFormat {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        GrammarFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == value.to_lowercase())
+            .ok_or(())
+    }
+}
+
+/// Syntax-highlighting definition of blueprint sources in `format`. Its keywords, punctuation and
+/// escape sequences are the ones of the lexer, and its attributes and their values the ones known
+/// to the parser, so that it does not drift from them.
+pub fn grammar(format: GrammarFormat) -> String {
+    match format {
+        GrammarFormat::TextMate => textmate(),
+        GrammarFormat::Vim => vim(),
+    }
+}
+
+fn keywords() -> Vec<&'static str> {
+    KEYWORDS.iter().map(|(keyword, _)| *keyword).collect()
+}
+
+/// Names of the colors, styles and constraints given to attributes.
+fn constants() -> Vec<&'static str> {
+    Color::NAMED
+        .iter()
+        .filter_map(Color::name)
+        .chain(LineStyle::ALL.iter().map(LineStyle::name))
+        .chain(Constraint::NAMES)
+        .collect()
+}
+
+/// Punctuation that are operators of coordinates (`true`) or separators (`false`).
+fn punctuation(operators: bool) -> Vec<char> {
+    PUNCTUATION
+        .iter()
+        .filter(|(_, token)| matches!(token, Token::At | Token::Plus | Token::Minus) == operators)
+        .map(|(punctuation, _)| *punctuation)
+        .collect()
+}
+
+/// Regex character class of `chars`, in the syntax of both Oniguruma and Vim.
+fn class(chars: impl IntoIterator<Item = char>) -> String {
+    let chars = chars
+        .into_iter()
+        .map(|c| match c {
+            '\\' | ']' | '[' | '^' | '-' => format!("\\{c}"),
+            c => c.to_string(),
+        })
+        .collect::<String>();
+    format!("[{chars}]")
+}
+
+fn textmate() -> String {
+    let escapes = class(ESCAPES.map(|(escape, _)| escape));
+    let grammar = json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "Blueprint",
+        "scopeName": "source.blueprint",
+        "fileTypes": [crate::loader::EXTENSION],
+        "patterns": [
+            { "name": "comment.line.double-slash.blueprint", "match": "//.*$" },
+            {
+                "name": "string.quoted.double.blueprint",
+                "begin": "\"",
+                "end": "\"",
+                "patterns": [
+                    {
+                        "name": "constant.character.escape.blueprint",
+                        "match": format!(r"\\(?:{escapes}|u[0-9a-fA-F]{{4}})"),
+                    },
+                    { "name": "invalid.illegal.escape.blueprint", "match": r"\\." },
+                ],
+            },
+            { "name": "entity.name.tag.blueprint", "match": TAG },
+            {
+                "name": "keyword.control.blueprint",
+                "match": format!(r"\b(?:{})\b", keywords().join("|")),
+            },
+            {
+                "name": "variable.other.property.blueprint",
+                "match": format!(r"\b(?:{})\b(?=\s*:)", KNOWN_ATTRIBUTES.join("|")),
+            },
+            {
+                "name": "constant.language.blueprint",
+                "match": format!(r"\b(?:{})\b", constants().join("|")),
+            },
+            { "name": "constant.numeric.blueprint", "match": NUMBER },
+            { "name": "keyword.operator.blueprint", "match": class(punctuation(true)) },
+            { "name": "punctuation.blueprint", "match": class(punctuation(false)) },
+        ],
+    });
+    format!("{}\n", serde_json::to_string_pretty(&grammar).unwrap())
+}
+
+fn vim() -> String {
+    // Vim's regexes escape groups rather than their literal parentheses. Patterns are delimited by
+    // `"`, or by `+` when they contain quotes.
+    let vim_regex = |regex: &str| {
+        regex
+            .replace("(?:", r"\%(")
+            .replace(')', r"\)")
+            .replace('|', r"\|")
+            .replace('+', r"\+")
+            .replace('?', r"\=")
+            .replace(r"\b", "")
+    };
+    let number = vim_regex(NUMBER).replace(r"[eE][\+-]", r"[eE][+-]");
+    let escapes = class(ESCAPES.map(|(escape, _)| escape));
+
+    [
+        "\" Vim syntax file for blueprint sources, generated by `blueprint grammar --format=vim`"
+            .to_string(),
+        "if exists(\"b:current_syntax\")\n  finish\nendif\n".to_string(),
+        format!("syn keyword blueprintKeyword {}", keywords().join(" ")),
+        format!("syn keyword blueprintConstant {}", constants().join(" ")),
+        format!(
+            "syn match blueprintAttribute \"\\<\\%({}\\)\\>\\ze\\s*:\"",
+            KNOWN_ATTRIBUTES.join("\\|")
+        ),
+        r##"syn match blueprintTag "#\%(\k\|-\)*""##.to_string(),
+        format!("syn match blueprintNumber \"\\<{number}\\>\""),
+        format!(
+            "syn match blueprintOperator \"{}\"",
+            class(punctuation(true))
+        ),
+        format!("syn match blueprintEscape contained +\\\\\\%({escapes}\\|u\\x\\{{4}}\\)+"),
+        r#"syn region blueprintString start=+"+ skip=+\\\\\|\\"+ end=+"+ contains=blueprintEscape"#
+            .to_string(),
+        "syn match blueprintComment \"//.*$\"\n".to_string(),
+        "hi def link blueprintKeyword Keyword".to_string(),
+        "hi def link blueprintConstant Constant".to_string(),
+        "hi def link blueprintAttribute Identifier".to_string(),
+        "hi def link blueprintTag Label".to_string(),
+        "hi def link blueprintNumber Number".to_string(),
+        "hi def link blueprintOperator Operator".to_string(),
+        "hi def link blueprintEscape SpecialChar".to_string(),
+        "hi def link blueprintString String".to_string(),
+        "hi def link blueprintComment Comment\n".to_string(),
+        "let b:current_syntax = \"blueprint\"\n".to_string(),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grammar() {
+        let textmate = grammar(GrammarFormat::TextMate);
+        let json = serde_json::from_str::<serde_json::Value>(&textmate).unwrap();
+        assert_eq!(json["scopeName"], "source.blueprint");
+        assert_eq!(json["patterns"][3]["match"], r"\b(?:move|include|shape)\b");
+        assert_eq!(json["patterns"][7]["match"], r"[@+\-]");
+        assert_eq!(
+            json["patterns"][1]["patterns"][0]["match"],
+            r#"\\(?:["\\n]|u[0-9a-fA-F]{4})"#
+        );
+
+        let vim = grammar(GrammarFormat::Vim);
+        assert!(vim.contains("\nsyn keyword blueprintKeyword move include shape\n"));
+        assert!(vim.contains(
+            r#"syn match blueprintNumber "\<\d\+\%(_\d\+\)*\%(\.\d\+\%(_\d\+\)*\)\=\%([eE][+-]\=\d\+\%(_\d\+\)*\)\=\>""#
+        ));
+        assert!(vim.contains(r#"syn match blueprintEscape contained +\\\%(["\\n]\|u\x\{4}\)+"#));
+        assert_eq!(
+            GrammarFormat::try_from("TextMate"),
+            Ok(GrammarFormat::TextMate)
+        );
+        assert_eq!(GrammarFormat::try_from("emacs"), Err(()));
+    }
+}
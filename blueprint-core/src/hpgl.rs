@@ -0,0 +1,104 @@
+use crate::domain::{Blueprint, Bound, Color, Coordinate, Point};
+use crate::toolpath::toolpaths;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Plotter units per millimeter.
+const UNITS_PER_MM: f32 = 40.;
+
+/// HP-GL plot of a blueprint, for pen plotters and vinyl cutters. Each color is drawn with its own
+/// pen, selected once.
+pub struct HpglPlot<'b, T: Coordinate = f32> {
+    blueprint: &'b Blueprint<T>,
+    scale: f32,
+}
+
+impl<T: Coordinate> HpglPlot<'_, T> {
+    /// Millimeters per blueprint unit.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<'b, T: Coordinate> From<&'b Blueprint<T>> for HpglPlot<'b, T> {
+    fn from(value: &'b Blueprint<T>) -> Self {
+        Self {
+            blueprint: value,
+            scale: 1.,
+        }
+    }
+}
+
+impl<T: Coordinate> Display for HpglPlot<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the y axis of plotters points up, from the bottom of the drawing, while the one of
+        // blueprints points down
+        let bottom = self.blueprint.boundaries().unwrap_or_default().1.y;
+        let (scale, units) = (T::from_decimal(self.scale), T::from_decimal(UNITS_PER_MM));
+        let coordinates = |point: Point<T>| {
+            let x = (point.x * scale * units).to_f64().round() as i32;
+            let y = ((bottom - point.y) * scale * units).to_f64().round() as i32;
+            format!("{x},{y}")
+        };
+
+        let mut toolpaths = toolpaths(self.blueprint);
+        toolpaths.sort_by_key(|toolpath| pen(toolpath.color));
+
+        write!(f, "IN;")?;
+        let mut current_pen = None;
+        for toolpath in toolpaths {
+            let pen = pen(toolpath.color);
+            if current_pen != Some(pen) {
+                write!(f, "SP{pen};")?;
+                current_pen = Some(pen);
+            }
+
+            write!(f, "PU{};", coordinates(toolpath.points[0]))?;
+            let points = toolpath.points[1..]
+                .iter()
+                .map(|point| coordinates(*point))
+                .collect::<Vec<_>>();
+            write!(f, "PD{};", points.join(","))?;
+        }
+        writeln!(f, "PU;SP0;")
+    }
+}
+
+/// Pen drawing `color`. Black, white and custom colors use the first pen.
+fn pen(color: Color) -> u8 {
+    match color {
+        Color::Red => 2,
+        Color::Green => 3,
+        Color::Blue => 4,
+        Color::Yellow => 5,
+        Color::Magenta => 6,
+        Color::Cyan => 7,
+        Color::Transparent | Color::White | Color::Black | Color::Custom(_) => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_hpgl() {
+        let (blueprint, _) = load_blueprint_str(
+            "[color:red] 10,0 [color:black] 0,5 -10,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            HpglPlot::from(&blueprint.unwrap()).scale(0.5).to_string(),
+            "IN;SP1;PU200,100;PD200,0,0,0;SP2;PU0,100;PD200,100;PU;SP0;\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::canvas::Canvas;
+use crate::domain::{Blueprint, Bound, Color, Draw, Edge, SourceSpan, Translate};
+use std::borrow::Cow;
+use std::io;
+use std::ops::Range;
+
+/// Maximum number of pixels of a band, so that large images are never held in memory at once.
+const BAND_PIXELS: usize = 1 << 22;
+
+/// Image format a canvas is written in.
+pub trait ImageEncoder {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error>;
+}
+
+/// Pixels of an image, provided in horizontal bands of rows.
+pub trait Raster {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    /// Canvas holding the `rows` of the image.
+    fn band(&self, rows: Range<usize>) -> Cow<'_, Canvas>;
+}
+
+impl Raster for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn band(&self, rows: Range<usize>) -> Cow<'_, Canvas> {
+        if rows == self.rows() {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.crop_rows(rows))
+        }
+    }
+}
+
+/// Splits the rows of `raster`, from top to bottom, in bands small enough to be held in memory.
+pub fn bands(raster: &dyn Raster) -> impl DoubleEndedIterator<Item = Range<usize>> + use<> {
+    let height = raster.height();
+    let rows = (BAND_PIXELS / raster.width().max(1)).max(1);
+    (0..height)
+        .step_by(rows)
+        .map(move |top| top..(top + rows).min(height))
+}
+
+/// How the colors of an image are reduced to black and white.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Monochrome {
+    /// Any color is black.
+    Threshold,
+    /// Colors are black in a proportion of pixels depending on their lightness.
+    Dither,
+}
+
+/// Blueprint drawn band by band, when the bands are needed.
+pub struct BlueprintRaster {
+    blueprint: Blueprint,
+    background: Color,
+    supersample: usize,
+    /// Size of the image drawn, before downsampling.
+    size: (usize, usize),
+    tag_markers: bool,
+    monochrome: Option<Monochrome>,
+}
+
+impl BlueprintRaster {
+    /// Prepares the drawing of `blueprint` on an image just large enough to hold it, surrounded
+    /// by `padding` pixels. The blueprint is drawn `supersample` times larger than the image, and
+    /// blocks of pixels are averaged back down to smooth the edges.
+    pub fn new(
+        mut blueprint: Blueprint,
+        background: Color,
+        supersample: usize,
+        padding: usize,
+    ) -> Self {
+        let padding = padding * supersample;
+        blueprint.translate(padding as f32, padding as f32);
+
+        let (_, bottom_right) = blueprint.boundaries().unwrap_or_default();
+        let size = (
+            (bottom_right.x + 1.).ceil().max(padding as f32) as usize + padding,
+            (bottom_right.y + 1.).ceil().max(padding as f32) as usize + padding,
+        );
+
+        Self {
+            blueprint,
+            background,
+            supersample,
+            size,
+            tag_markers: false,
+            monochrome: None,
+        }
+    }
+
+    /// Whether a cross and the name of the tag are drawn at each tagged point.
+    pub fn tag_markers(mut self, tag_markers: bool) -> Self {
+        self.tag_markers = tag_markers;
+        self
+    }
+
+    /// Whether the image is reduced to black and the background color, and how.
+    pub fn monochrome(mut self, monochrome: Option<Monochrome>) -> Self {
+        self.monochrome = monochrome;
+        self
+    }
+
+    fn draw_tag_markers(&self, canvas: &mut Canvas) {
+        let scale = self.supersample;
+        let arm = 3. * scale as f32;
+
+        for (tag, point) in self.blueprint.tags_iter() {
+            for (dx, dy) in [(arm, 0.), (0., arm)] {
+                Edge::new(
+                    point.x - dx,
+                    point.y - dy,
+                    point.x + dx,
+                    point.y + dy,
+                    Color::Black,
+                    SourceSpan::default(),
+                )
+                .with_width(scale as f32)
+                .draw(canvas);
+            }
+
+            // the name is written above the right arm of the cross
+            canvas.draw_text(
+                (point.x + arm + 2. * scale as f32).max(0.) as usize,
+                (point.y - arm - 4. * scale as f32).max(0.) as usize,
+                tag,
+                scale,
+                Color::Black,
+            );
+        }
+    }
+}
+
+impl Raster for BlueprintRaster {
+    fn width(&self) -> usize {
+        self.size.0.div_ceil(self.supersample)
+    }
+
+    fn height(&self) -> usize {
+        self.size.1.div_ceil(self.supersample)
+    }
+
+    fn band(&self, rows: Range<usize>) -> Cow<'_, Canvas> {
+        let rows = rows.start * self.supersample..(rows.end * self.supersample).min(self.size.1);
+        let mut canvas =
+            Canvas::new_band(self.size.0, self.size.1, rows).with_background(self.background);
+        self.blueprint.draw(&mut canvas);
+        if self.tag_markers {
+            self.draw_tag_markers(&mut canvas);
+        }
+
+        let canvas = canvas.downsample(self.supersample);
+        Cow::Owned(match self.monochrome {
+            None => canvas,
+            Some(monochrome) => canvas.monochrome(monochrome == Monochrome::Dither),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Point, Shape};
+
+    #[test]
+    fn test_blueprint_raster() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![Edge::new(
+            0.,
+            0.,
+            4.,
+            4.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        let raster = BlueprintRaster::new(blueprint, Color::White, 1, 2);
+        assert_eq!((raster.width(), raster.height()), (9, 9));
+
+        // bands drawn separately match the image drawn at once
+        let whole = raster.band(0..9);
+        for rows in [0..3, 3..7, 7..9] {
+            let band = raster.band(rows.clone());
+            for y in rows {
+                for x in 0..9 {
+                    assert_eq!(band.get(x, y), whole.get(x, y));
+                }
+            }
+        }
+        assert_eq!(whole.get(4, 4), Color::Black);
+        assert_eq!(whole.get(4, 5), Color::White);
+    }
+
+    #[test]
+    fn test_tag_markers() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![Edge::new(
+            30.,
+            0.,
+            30.,
+            30.,
+            Color::Black,
+            SourceSpan::default(),
+        )]));
+        blueprint.tag("t1".to_string(), Point::new(0., 10.));
+        let raster = BlueprintRaster::new(blueprint, Color::White, 1, 10).tag_markers(true);
+        let image = raster.band(0..raster.height());
+
+        let rows = (12..=24)
+            .map(|y| {
+                (7..=21)
+                    .map(|x| match image.get(x, y) {
+                        Color::White => '.',
+                        _ => '#',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                "...............",
+                "........###..#.",
+                ".........#..##.",
+                ".........#...#.",
+                ".........#...#.",
+                "...#.....#..###",
+                "...#...........",
+                "...#...........",
+                "#######........",
+                "...#...........",
+                "...#...........",
+                "...#...........",
+                "...............",
+            ]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::domain::{Blueprint, Bound, Edge, Point};
+use std::ops::Range;
+
+/// Most cells along a side of the grid, however thin the blueprint is.
+const MAX_SIDE: usize = 1024;
+
+/// Most cells an edge is listed in, longer edges being checked by every query instead.
+const MAX_CELLS: usize = 16;
+
+/// Blueprint whose edges are listed in the cells of a uniform grid they overlap, to find the ones
+/// in a region, e.g. the visible part of a large blueprint, without going through all of them.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedBlueprint {
+    blueprint: Blueprint,
+    /// Top left corner of the grid.
+    origin: Point,
+    /// Side of the square cells, in blueprint units.
+    cell: f32,
+    columns: usize,
+    rows: usize,
+    /// Edges overlapping each cell, row after row, as the indices of their shape and of the edge
+    /// in it.
+    cells: Vec<Vec<(usize, usize)>>,
+    /// Edges overlapping too many cells to be listed in them.
+    large: Vec<(usize, usize)>,
+}
+
+impl IndexedBlueprint {
+    pub fn blueprint(&self) -> &Blueprint {
+        &self.blueprint
+    }
+
+    /// Edges, transparent ones included, whose boundaries overlap the rectangle having `corner`
+    /// and `opposite` as corners, in the order they are drawn. Curves may pass by the rectangle
+    /// without entering it.
+    pub fn edges_in_rect(&self, corner: Point, opposite: Point) -> impl Iterator<Item = &Edge> {
+        let (min, max) = (corner.top_left(&opposite), corner.bottom_right(&opposite));
+        let (columns, rows) = self.cells_within(min, max);
+        let mut found = rows
+            .flat_map(|row| {
+                columns
+                    .clone()
+                    .flat_map(move |column| &self.cells[row * self.columns + column])
+            })
+            .chain(&self.large)
+            .copied()
+            .collect::<Vec<_>>();
+        // edges overlapping several cells are found in each of them
+        found.sort_unstable();
+        found.dedup();
+
+        let shapes = self.blueprint.shapes_iter().as_slice();
+        found
+            .into_iter()
+            .map(|(shape, edge)| &shapes[shape].edges_iter().as_slice()[edge])
+            .filter(move |edge| {
+                let (top_left, bottom_right) = edge.extent();
+                top_left.x <= max.x
+                    && min.x <= bottom_right.x
+                    && top_left.y <= max.y
+                    && min.y <= bottom_right.y
+            })
+    }
+
+    /// Columns and rows of the cells overlapping the rectangle between `min` and `max`, the ones
+    /// on the border of the grid standing for everything beyond.
+    fn cells_within(&self, min: Point, max: Point) -> (Range<usize>, Range<usize>) {
+        let index = |value: f32, origin: f32, count: usize| {
+            (((value - origin) / self.cell).max(0.) as usize).min(count.saturating_sub(1))
+        };
+        let cell = |point: Point| {
+            (
+                index(point.x, self.origin.x, self.columns),
+                index(point.y, self.origin.y, self.rows),
+            )
+        };
+        let ((left, top), (right, bottom)) = (cell(min), cell(max));
+        (left..right + 1, top..bottom + 1)
+    }
+}
+
+impl From<Blueprint> for IndexedBlueprint {
+    fn from(blueprint: Blueprint) -> Self {
+        let count = blueprint.edges_iter().count();
+        let (origin, bottom_right) = blueprint.boundaries().unwrap_or_default();
+        let (width, height) = (bottom_right.x - origin.x, bottom_right.y - origin.y);
+        // about as many cells as edges, unless the blueprint is too thin for it
+        let side = width.max(height);
+        let cell = match side > 0. {
+            true => (width * height / count as f32)
+                .sqrt()
+                .max(side / MAX_SIDE as f32),
+            false => 1.,
+        };
+        let mut index = Self {
+            origin,
+            cell,
+            columns: (width / cell) as usize + 1,
+            rows: (height / cell) as usize + 1,
+            ..Self::default()
+        };
+        index.cells = vec![Vec::new(); index.columns * index.rows];
+
+        for (shape_index, shape) in blueprint.shapes_iter().enumerate() {
+            for (edge_index, edge) in shape.edges_iter().enumerate() {
+                let (top_left, bottom_right) = edge.extent();
+                let (columns, rows) = index.cells_within(top_left, bottom_right);
+                if columns.len() * rows.len() > MAX_CELLS {
+                    index.large.push((shape_index, edge_index));
+                    continue;
+                }
+                for row in rows {
+                    for column in columns.clone() {
+                        index.cells[row * index.columns + column].push((shape_index, edge_index));
+                    }
+                }
+            }
+        }
+        index.blueprint = blueprint;
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Color, Shape, SourceSpan};
+
+    #[test]
+    fn test_edges_in_rect() {
+        let edge = |x1, y1, x2, y2| Edge::new(x1, y1, x2, y2, Color::Black, SourceSpan::default());
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            edge(0., 0., 10., 0.),
+            edge(10., 0., 10., 10.),
+            edge(20., 20., 30., 30.),
+            edge(-5., 5., 25., 5.),
+        ]));
+        // a grid of short edges, listed in the cells around them
+        for i in 0..100 {
+            let (x, y) = ((i % 10) as f32 * 3., (i / 10) as f32 * 3.);
+            blueprint.push(Shape::from(vec![edge(x, y, x + 1., y + 1.)]));
+        }
+        // overlapping too many cells to be listed in them
+        blueprint.push(Shape::from(vec![edge(0., 30., 30., 0.)]));
+        let index = IndexedBlueprint::from(blueprint.clone());
+
+        let ends = |edges: Vec<&Edge>| {
+            edges
+                .into_iter()
+                .map(|edge| (edge.from.x, edge.from.y))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ends(
+                index
+                    .edges_in_rect(Point::new(12., 8.), Point::new(2., 2.))
+                    .take(2)
+                    .collect()
+            ),
+            vec![(10., 0.), (-5., 5.)]
+        );
+        assert_eq!(
+            ends(
+                index
+                    .edges_in_rect(Point::new(40., 0.), Point::new(50., 10.))
+                    .collect()
+            ),
+            vec![]
+        );
+        // the same edges as going through all of them
+        for (corner, opposite) in [
+            (Point::new(2., 2.), Point::new(12., 8.)),
+            (Point::new(-10., 4.), Point::new(-1., 6.)),
+            (Point::new(26.5, 26.5), Point::new(100., 100.)),
+        ] {
+            let all = blueprint
+                .edges_iter()
+                .filter(|edge| {
+                    let (top_left, bottom_right) = edge.extent();
+                    top_left.x <= opposite.x
+                        && corner.x <= bottom_right.x
+                        && top_left.y <= opposite.y
+                        && corner.y <= bottom_right.y
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                index.edges_in_rect(corner, opposite).collect::<Vec<_>>(),
+                all
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::domain::{Blueprint, Bound, Edge};
+use std::fmt::Write;
+
+/// Describes `blueprint` for reports: its size, its edges and the areas of its rooms, the closed
+/// shapes having visible edges. Lengths and areas are real-world ones when the blueprint has
+/// units.
+pub fn info(blueprint: &Blueprint) -> String {
+    let mut out = String::new();
+
+    let units = blueprint.units();
+    let length = |length: f32| match units {
+        Some(units) => format!("{} {}", number(units.length(length)), units.unit),
+        None => number(length),
+    };
+    let area = |area: f32| match units {
+        Some(units) => format!("{} {}²", number(units.area(area)), units.unit),
+        None => number(area),
+    };
+
+    let (min, max) = blueprint.boundaries().unwrap_or_default();
+    let visible = |edge: &&Edge| !edge.color.is_transparent();
+    let edges = blueprint.edges_iter().filter(visible).collect::<Vec<_>>();
+    writeln!(
+        out,
+        "size: {} x {}",
+        length(max.x - min.x),
+        length(max.y - min.y)
+    )
+    .unwrap();
+    writeln!(out, "shapes: {}", blueprint.shapes_iter().count()).unwrap();
+    writeln!(
+        out,
+        "edges: {}, {} long",
+        edges.len(),
+        length(edges.iter().map(|edge| edge.length()).sum())
+    )
+    .unwrap();
+
+    writeln!(out, "rooms:").unwrap();
+    let mut total = 0.;
+    for shape in blueprint
+        .shapes_iter()
+        .filter(|shape| shape.edges_iter().any(|edge| visible(&edge)))
+    {
+        let Some(shape_area) = shape.area() else {
+            continue;
+        };
+        total += shape_area;
+        let line = shape.edges_iter().next().map_or(0, |edge| edge.span.line);
+        writeln!(
+            out,
+            "  shape at line {line}: area {}, perimeter {}",
+            area(shape_area),
+            length(shape.perimeter())
+        )
+        .unwrap();
+    }
+    writeln!(out, "total area: {}", area(total)).unwrap();
+
+    out
+}
+
+/// `value` rounded to the hundredth.
+pub(crate) fn number(value: f32) -> String {
+    // adding zero turns -0 into 0
+    ((value * 100.).round() / 100. + 0.).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Unit, Units};
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_info() {
+        let (blueprint, _) = load_blueprint_str(
+            "{ 10,0 0,5 -10,0 0,-5 }\n{ [color:transparent] 4,0 [color:transparent] 0,4 [color:transparent] -4,0 [color:transparent] 0,-4 }\n@0,10 20,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        let mut blueprint = blueprint.unwrap();
+        assert_eq!(
+            info(&blueprint),
+            "size: 20 x 10
+shapes: 3
+edges: 6, 60 long
+rooms:
+  shape at line 1: area 50, perimeter 30
+total area: 50
+"
+        );
+
+        blueprint.set_units(Some(Units::new(5., Unit::Centimeter)));
+        assert_eq!(
+            info(&blueprint),
+            "size: 100 cm x 50 cm
+shapes: 3
+edges: 6, 300 cm long
+rooms:
+  shape at line 1: area 1250 cm², perimeter 150 cm
+total area: 1250 cm²
+"
+        );
+        assert_eq!(Units::try_from("5cm"), Ok(Units::new(5., Unit::Centimeter)));
+        assert_eq!(Units::try_from(" 0.5 M"), Ok(Units::new(0.5, Unit::Meter)));
+        assert_eq!(Units::try_from("ft"), Ok(Units::new(1., Unit::Foot)));
+        assert_eq!(Units::try_from("-2mm"), Err(()));
+        assert_eq!(Units::try_from("5"), Err(()));
+        assert_eq!(
+            Units::new(5., Unit::Centimeter).length_in(20., Unit::Meter),
+            1.
+        );
+    }
+}
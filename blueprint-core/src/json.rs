@@ -0,0 +1,245 @@
+use crate::domain::{Blueprint, Color, Edge, LineStyle, Point, Shape, SourceSpan};
+use crate::error::BlueprintError;
+use crate::source_map::SourceMap;
+use serde_json::{Map, Value};
+
+/// Extension of the files loaded as JSON documents rather than as blueprint sources.
+pub const EXTENSION: &str = "json";
+
+/// Loads the blueprint described by the JSON document `src`, for programs to generate blueprints
+/// without writing their syntax. The document mirrors the domain model:
+///
+/// ```json
+/// {
+///   "shapes": [
+///     {
+///       "edges": [
+///         { "from": { "x": 0, "y": 0 }, "to": { "x": 10, "y": 0 }, "color": "red" },
+///         {
+///           "from": { "x": 10, "y": 0 },
+///           "to": { "x": 10, "y": 5 },
+///           "width": 2,
+///           "style": "dashed",
+///           "layer": "walls"
+///         }
+///       ]
+///     }
+///   ],
+///   "tags": { "corner": { "x": 10, "y": 0 } }
+/// }
+/// ```
+///
+/// The attributes of the edges are optional and take the values of the edge attributes of
+/// blueprint sources. Invalid edges are reported and skipped, the other ones being loaded.
+pub fn load_json_str(src: &str) -> (Option<Blueprint>, Vec<BlueprintError>) {
+    let document = match serde_json::from_str::<Value>(src) {
+        Ok(document) => document,
+        Err(e) => {
+            // serde_json locates errors by line and column, in characters
+            let line_start = SourceMap::new(src)
+                .line_start(e.line().max(1))
+                .unwrap_or(src.len());
+            let start = src[line_start..]
+                .char_indices()
+                .nth(e.column().saturating_sub(1))
+                .map_or(src.len(), |(i, _)| line_start + i);
+            let error = BlueprintError::error(format!("Invalid JSON: {e}"))
+                .with_span((start..start).into(), e.line().max(1));
+            return (None, vec![error]);
+        }
+    };
+
+    let mut loader = JsonLoader::default();
+    loader.document(&document);
+    loader.blueprint.translate_to_origin();
+    (Some(loader.blueprint), loader.errors)
+}
+
+#[derive(Default)]
+struct JsonLoader {
+    blueprint: Blueprint,
+    errors: Vec<BlueprintError>,
+}
+
+impl JsonLoader {
+    fn document(&mut self, document: &Value) {
+        let Some(document) = self.object(document, "the document") else {
+            return;
+        };
+
+        for (i, shape) in self
+            .array(document.get("shapes"), "shapes")
+            .iter()
+            .enumerate()
+        {
+            let location = format!("shapes[{i}]");
+            let Some(shape) = self.object(shape, &location) else {
+                continue;
+            };
+            let edges = self
+                .array(shape.get("edges"), &format!("{location}.edges"))
+                .iter()
+                .enumerate()
+                .filter_map(|(j, edge)| self.edge(edge, &format!("{location}.edges[{j}]")))
+                .collect::<Vec<_>>();
+            self.blueprint.push(Shape::from(edges));
+        }
+
+        match document.get("tags") {
+            None => {}
+            Some(Value::Object(tags)) => {
+                for (tag, point) in tags {
+                    if let Some(point) = self.point(Some(point), &format!("tags.{tag}")) {
+                        self.blueprint.tag(tag.clone(), point);
+                    }
+                }
+            }
+            Some(_) => self.error("tags", "an object"),
+        }
+    }
+
+    fn edge(&mut self, edge: &Value, location: &str) -> Option<Edge> {
+        let edge = self.object(edge, location)?;
+        let from = self.point(edge.get("from"), &format!("{location}.from"));
+        let to = self.point(edge.get("to"), &format!("{location}.to"));
+
+        let color = match edge.get("color") {
+            None => Color::default(),
+            Some(color) => match color.as_str().map(Color::try_from) {
+                Some(Ok(color)) => color,
+                _ => {
+                    self.error(&format!("{location}.color"), "a color name");
+                    Color::default()
+                }
+            },
+        };
+        let width = match edge.get("width") {
+            None => 1.,
+            Some(width) => match width.as_f64() {
+                Some(width) if width > 0. => width as f32,
+                _ => {
+                    self.error(&format!("{location}.width"), "a positive number");
+                    1.
+                }
+            },
+        };
+        let style = match edge.get("style") {
+            None => LineStyle::default(),
+            Some(style) => match style.as_str().map(LineStyle::try_from) {
+                Some(Ok(style)) => style,
+                _ => {
+                    self.error(&format!("{location}.style"), "a style name");
+                    LineStyle::default()
+                }
+            },
+        };
+        let layer = match edge.get("layer") {
+            None => None,
+            Some(layer) => match layer.as_str() {
+                Some(name) => Some(self.blueprint.layer(name)),
+                None => {
+                    self.error(&format!("{location}.layer"), "a layer name");
+                    None
+                }
+            },
+        };
+
+        Some(
+            Edge::new_from_points(from?, to?, color, SourceSpan::default())
+                .with_width(width)
+                .with_style(style)
+                .with_layer(layer),
+        )
+    }
+
+    fn point(&mut self, point: Option<&Value>, location: &str) -> Option<Point> {
+        let coordinate = |point: &Value, key| point.get(key).and_then(Value::as_f64);
+        match point.and_then(|point| coordinate(point, "x").zip(coordinate(point, "y"))) {
+            Some((x, y)) => Some(Point::new(x as f32, y as f32)),
+            None => {
+                self.error(location, "a point with numeric `x` and `y`");
+                None
+            }
+        }
+    }
+
+    fn object<'v>(&mut self, value: &'v Value, location: &str) -> Option<&'v Map<String, Value>> {
+        let object = value.as_object();
+        if object.is_none() {
+            self.error(location, "an object");
+        }
+        object
+    }
+
+    fn array<'v>(&mut self, value: Option<&'v Value>, location: &str) -> &'v [Value] {
+        match value {
+            None => &[],
+            Some(Value::Array(values)) => values,
+            Some(_) => {
+                self.error(location, "an array");
+                &[]
+            }
+        }
+    }
+
+    fn error(&mut self, location: &str, expected: &str) {
+        self.errors.push(BlueprintError::error(format!(
+            "{location} must be {expected}"
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_json() {
+        let (blueprint, errors) = load_json_str(
+            r#"{
+                "shapes": [{
+                    "edges": [
+                        { "from": { "x": -5, "y": 0 }, "to": { "x": 5, "y": 0 }, "color": "red" },
+                        { "from": { "x": 5, "y": 0 }, "to": { "x": 5, "y": 5.5 }, "width": 2, "layer": "walls" },
+                        { "from": { "x": 5 }, "to": { "x": 0, "y": 0 }, "style": "wavy" }
+                    ]
+                }],
+                "tags": { "corner": { "x": 5, "y": 0 } }
+            }"#,
+        );
+
+        let blueprint = blueprint.unwrap();
+        let edges = blueprint
+            .shapes_iter()
+            .flat_map(Shape::edges_iter)
+            .collect::<Vec<_>>();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].color, Color::Red);
+        assert_eq!(edges[1].from, Point::new(10., 0.));
+        assert_eq!(edges[1].to, Point::new(10., 5.5));
+        assert_eq!((edges[1].width, edges[1].layer), (2., Some(0)));
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("corner", &Point::new(10., 0.))]
+        );
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "shapes[0].edges[2].from must be a point with numeric `x` and `y`",
+                "shapes[0].edges[2].style must be a style name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_invalid_json() {
+        let (blueprint, errors) = load_json_str("{\n  \"shapes\": [,\n}");
+
+        assert!(blueprint.is_none());
+        assert_eq!(errors[0].line, Some(2));
+        assert_eq!(errors[0].span, Some((15..15).into()));
+    }
+}
@@ -0,0 +1,590 @@
+use crate::error::BlueprintError;
+use crate::source_map::SourceMap;
+use chumsky::prelude::*;
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::fmt::{Debug, Display};
+
+pub type Span = SimpleSpan;
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct Spanned<T: Clone + Debug + PartialEq> {
+    pub node: T,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<'src> {
+    Num(i32),
+    /// A number with a fractional part, e.g. `2.5`, or too large for an integer.
+    Float(f32),
+    Ident(&'src str),
+    Move,
+    Include,
+    Shape,
+    /// A string literal, with its escape sequences already resolved.
+    Str(Cow<'src, str>),
+    Tag(&'src str),
+    At,
+    Dot,
+    Plus,
+    Minus,
+    Comma,
+    Colon,
+    /// Optional end of a command.
+    Semicolon,
+    OpenCurly,
+    CloseCurly,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+}
+
+impl Token<'_> {
+    /// Name of the kind of the token, e.g. `tag` or `open_curly`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::Num(_) => "num",
+            Token::Float(_) => "float",
+            Token::Ident(_) => "ident",
+            Token::Move => "move",
+            Token::Include => "include",
+            Token::Shape => "shape",
+            Token::Str(_) => "str",
+            Token::Tag(_) => "tag",
+            Token::At => "at",
+            Token::Dot => "dot",
+            Token::Plus => "plus",
+            Token::Minus => "minus",
+            Token::Comma => "comma",
+            Token::Colon => "colon",
+            Token::Semicolon => "semicolon",
+            Token::OpenCurly => "open_curly",
+            Token::CloseCurly => "close_curly",
+            Token::OpenSquare => "open_square",
+            Token::CloseSquare => "close_square",
+            Token::OpenParen => "open_paren",
+            Token::CloseParen => "close_paren",
+        }
+    }
+
+    /// Value of numbers, strings, identifiers and tags, as JSON.
+    fn value(&self) -> Option<Value> {
+        match self {
+            Token::Num(n) => Some(Value::from(*n)),
+            Token::Float(n) => Some(Value::from(*n)),
+            Token::Ident(s) | Token::Tag(s) => Some(Value::from(*s)),
+            Token::Str(s) => Some(Value::from(s.as_ref())),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{n}"),
+            Token::Float(n) => write!(f, "{n:?}"),
+            Token::Ident(ident) => write!(f, "{ident}"),
+            Token::Move => write!(f, "move"),
+            Token::Include => write!(f, "include"),
+            Token::Shape => write!(f, "shape"),
+            Token::Str(s) => write!(f, "\"{}\"", escape(s)),
+            Token::Tag(ident) => write!(f, "#{ident}"),
+            Token::At => write!(f, "@"),
+            Token::Dot => write!(f, "."),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+            Token::Semicolon => write!(f, ";"),
+            Token::OpenCurly => write!(f, "{{"),
+            Token::CloseCurly => write!(f, "}}"),
+            Token::OpenSquare => write!(f, "["),
+            Token::CloseSquare => write!(f, "]"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+        }
+    }
+}
+
+/// Identifiers that are keywords, with their token.
+pub const KEYWORDS: [(&str, Token<'static>); 3] = [
+    ("move", Token::Move),
+    ("include", Token::Include),
+    ("shape", Token::Shape),
+];
+
+/// Characters that are tokens on their own, with their token.
+pub const PUNCTUATION: [(char, Token<'static>); 13] = [
+    (',', Token::Comma),
+    (':', Token::Colon),
+    (';', Token::Semicolon),
+    ('@', Token::At),
+    ('.', Token::Dot),
+    ('+', Token::Plus),
+    ('-', Token::Minus),
+    ('{', Token::OpenCurly),
+    ('}', Token::CloseCurly),
+    ('[', Token::OpenSquare),
+    (']', Token::CloseSquare),
+    ('(', Token::OpenParen),
+    (')', Token::CloseParen),
+];
+
+/// Characters following a `\\` in string literals, with the character they stand for. `\\u` is
+/// followed by the 4 hexadecimal digits of a character instead.
+pub const ESCAPES: [(char, char); 3] = [('"', '"'), ('\\', '\\'), ('n', '\n')];
+
+pub fn lexer<'src>()
+-> impl Parser<'src, &'src str, Vec<Spanned<Token<'src>>>, extra::Err<Rich<'src, char, Span>>> {
+    // `1_000`, `2.5` or `2.5e3`: numbers having an integral value are integers
+    let digits = text::digits(10).separated_by(just('_')).at_least(1);
+    let num = digits
+        .then(just('.').then(digits).or_not())
+        .then(
+            one_of("eE")
+                .then(one_of("+-").or_not())
+                .then(digits)
+                .or_not(),
+        )
+        .to_slice()
+        .map(|number: &str| {
+            // the digits without their separators always are a valid float
+            let value = number.replace('_', "").parse::<f64>().unwrap();
+            match value.fract() == 0. && value.abs() <= i32::MAX as f64 {
+                true => Token::Num(value as i32),
+                false => Token::Float(value as f32),
+            }
+        });
+
+    let ident = text::unicode::ident().map(|ident: &str| {
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == ident)
+            .map_or(Token::Ident(ident), |(_, token)| token.clone())
+    });
+
+    let hex = any().filter(char::is_ascii_hexdigit);
+    let escape = just('\\')
+        .then(
+            just('u')
+                .then(hex.repeated().exactly(4))
+                .ignored()
+                .or(any().ignored()),
+        )
+        .to_slice()
+        .validate(|sequence: &str, e, emitter| {
+            if escaped(&sequence[1..]).is_none() {
+                emitter.emit(Rich::custom(
+                    e.span(),
+                    format!(
+                        "`{sequence}` is not a valid escape sequence, expected `\\\"`, `\\\\`, `\\n` or `\\uXXXX`."
+                    ),
+                ));
+            }
+        })
+        .labelled("escape sequence");
+    let str = just('"')
+        .ignore_then(
+            none_of("\\\"")
+                .ignored()
+                .or(escape.ignored())
+                .repeated()
+                .to_slice(),
+        )
+        .then_ignore(just('"'))
+        .map(|s: &str| Token::Str(unescape(s)));
+
+    let tag = just('#')
+        .ignore_then(
+            any()
+                .filter(|c: &char| c.is_alphanumeric() || *c == '_' || *c == '-')
+                .repeated()
+                .to_slice(),
+        )
+        .map(Token::Tag);
+
+    let punctuation = any()
+        .filter(|c: &char| PUNCTUATION.iter().any(|(punctuation, _)| punctuation == c))
+        .map(|c| {
+            PUNCTUATION
+                .into_iter()
+                .find_map(|(punctuation, token)| (punctuation == c).then_some(token))
+                .expect("filtered punctuation")
+        });
+
+    let token = choice((num, ident, str, tag, punctuation));
+
+    let comment = just("//")
+        .then(any().and_is(just('\n').not()).repeated())
+        .padded();
+
+    token
+        .map_with(|tok, e| Spanned {
+            node: tok,
+            span: e.span(),
+        })
+        .padded_by(comment.repeated())
+        .padded()
+        .recover_with(skip_then_retry_until(any().ignored(), end()))
+        .repeated()
+        .collect()
+}
+
+/// Tokens and comments of `src`, in order, for the tools highlighting it, along with the lexing
+/// errors.
+pub fn tokenize(src: &str) -> (Vec<Spanned<Lexeme<'_>>>, Vec<BlueprintError>) {
+    let (tokens, errors) = lexer().parse(src).into_output_errors();
+    let source_map = SourceMap::new(src);
+
+    let mut lexemes = tokens
+        .unwrap_or_default()
+        .into_iter()
+        .map(|token| Spanned {
+            node: Lexeme::Token(token.node),
+            span: token.span,
+        })
+        .chain(comments(src).into_iter().map(|span| Spanned {
+            node: Lexeme::Comment,
+            span,
+        }))
+        .collect::<Vec<_>>();
+    lexemes.sort_by_key(|lexeme| lexeme.span.start);
+
+    let errors = errors
+        .into_iter()
+        .map(|e| {
+            let (line, _) = source_map.line_col(e.span().start);
+            BlueprintError::error(e.to_string()).with_span(*e.span(), line)
+        })
+        .collect();
+    (lexemes, errors)
+}
+
+/// Token or comment of a source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lexeme<'src> {
+    Token(Token<'src>),
+    Comment,
+}
+
+impl Lexeme<'_> {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Lexeme::Token(token) => token.kind(),
+            Lexeme::Comment => "comment",
+        }
+    }
+}
+
+/// JSON object describing `lexeme`, found in `src` (mapped by `source_map`): its kind, its text,
+/// its location, with its column in characters, and the value of numbers, strings, identifiers
+/// and tags.
+pub fn to_json(src: &str, source_map: &SourceMap, lexeme: &Spanned<Lexeme>) -> Value {
+    let (line, column) = source_map.line_col(lexeme.span.start);
+    let mut json = json!({
+        "kind": lexeme.node.kind(),
+        "text": &src[lexeme.span.into_range()],
+        "start": lexeme.span.start,
+        "end": lexeme.span.end,
+        "line": line,
+        "column": column,
+    });
+    if let Lexeme::Token(token) = &lexeme.node
+        && let Some(value) = token.value()
+    {
+        json["value"] = value;
+    }
+    json
+}
+
+/// Finds the `// ...` comments of `src`, ignoring the content of string literals.
+pub fn comments(src: &str) -> Vec<Span> {
+    let mut comments = Vec::new();
+    let mut in_str = false;
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => in_str = !in_str,
+            '\\' if in_str => {
+                chars.next();
+            }
+            '/' if !in_str && chars.peek().is_some_and(|(_, c)| *c == '/') => {
+                let end = src[i..].find('\n').map_or(src.len(), |len| i + len);
+                comments.push(Span::from(i..src[..end].trim_end().len()));
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+            }
+            _ => {}
+        }
+    }
+
+    comments
+}
+
+/// Resolves the `\"`, `\n`, `\\` and `\uXXXX` escape sequences of a string literal's content,
+/// only allocating when there is one. Invalid sequences, already reported by the lexer, are kept
+/// as they are.
+fn unescape(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('\\') {
+        unescaped.push_str(&rest[..start]);
+        let sequence = &rest[start + 1..];
+        let len = match sequence.get(..5).and_then(escaped) {
+            Some(_) => 5,
+            None => sequence.chars().next().map_or(0, char::len_utf8),
+        };
+        match escaped(&sequence[..len]) {
+            Some(c) => unescaped.push(c),
+            None => unescaped.push_str(&rest[start..start + 1 + len]),
+        }
+        rest = &sequence[len..];
+    }
+    unescaped.push_str(rest);
+    Cow::Owned(unescaped)
+}
+
+/// Character of the escape sequence `sequence`, without its `\\`.
+fn escaped(sequence: &str) -> Option<char> {
+    let mut chars = sequence.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return ESCAPES
+            .iter()
+            .find(|(escape, _)| *escape == c)
+            .map(|(_, escaped)| *escaped);
+    }
+    sequence
+        .strip_prefix('u')
+        .filter(|digits| digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .and_then(char::from_u32)
+}
+
+/// Inverse of [`unescape`].
+pub fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lexer() {
+        assert_eq!(
+            lexer().parse("123").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Num(123),
+                span: Span::from(0..3)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("123 //comment").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Num(123),
+                span: Span::from(0..3)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("move").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Move,
+                span: Span::from(0..4)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("ident").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Ident("ident"),
+                span: Span::from(0..5)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("123 ident move").into_result(),
+            Ok(vec![
+                Spanned {
+                    node: Token::Num(123),
+                    span: Span::from(0..3)
+                },
+                Spanned {
+                    node: Token::Ident("ident"),
+                    span: Span::from(4..9)
+                },
+                Spanned {
+                    node: Token::Move,
+                    span: Span::from(10..14)
+                }
+            ])
+        );
+        assert_eq!(
+            lexer().parse("{}").into_result(),
+            Ok(vec![
+                Spanned {
+                    node: Token::OpenCurly,
+                    span: Span::from(0..1)
+                },
+                Spanned {
+                    node: Token::CloseCurly,
+                    span: Span::from(1..2)
+                },
+            ])
+        );
+        assert_eq!(
+            lexer().parse("include \"a b.bp\"").into_result(),
+            Ok(vec![
+                Spanned {
+                    node: Token::Include,
+                    span: Span::from(0..7)
+                },
+                Spanned {
+                    node: Token::Str(Cow::Borrowed("a b.bp")),
+                    span: Span::from(8..16)
+                },
+            ])
+        );
+        assert_eq!(
+            lexer().parse(r#""say \"hi\"\n\\""#).into_result(),
+            Ok(vec![Spanned {
+                node: Token::Str(Cow::Owned("say \"hi\"\n\\".to_string())),
+                span: Span::from(0..16)
+            }])
+        );
+        assert!(lexer().parse(r#""\t""#).into_result().is_err());
+        assert_eq!(
+            lexer().parse(r#""caf\u00e9 \uD800""#).into_output_errors(),
+            (
+                Some(vec![Spanned {
+                    node: Token::Str(Cow::Owned("café \\uD800".to_string())),
+                    span: Span::from(0..18)
+                }]),
+                vec![Rich::custom(
+                    Span::from(11..17),
+                    "`\\uD800` is not a valid escape sequence, expected `\\\"`, `\\\\`, `\\n` or `\\uXXXX`."
+                )]
+            )
+        );
+        assert_eq!(
+            lexer().parse("#cuisine_é").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Tag("cuisine_é"),
+                span: Span::from(0..11)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("#my_tag").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Tag("my_tag"),
+                span: Span::from(0..7)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("#-my-tag").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Tag("-my-tag"),
+                span: Span::from(0..8)
+            }])
+        );
+        assert_eq!(
+            lexer().parse("#p0.x-1").into_result(),
+            Ok(vec![
+                Spanned {
+                    node: Token::Tag("p0"),
+                    span: Span::from(0..3)
+                },
+                Spanned {
+                    node: Token::Dot,
+                    span: Span::from(3..4)
+                },
+                Spanned {
+                    node: Token::Ident("x"),
+                    span: Span::from(4..5)
+                },
+                Spanned {
+                    node: Token::Minus,
+                    span: Span::from(5..6)
+                },
+                Spanned {
+                    node: Token::Num(1),
+                    span: Span::from(6..7)
+                },
+            ])
+        );
+        assert_eq!(
+            lexer().parse("2.50").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Float(2.5),
+                span: Span::from(0..4)
+            }])
+        );
+        assert_eq!(
+            lexer()
+                .parse("1_000 2.5e3 1.5E-1 1e10")
+                .into_result()
+                .map(|tokens| tokens.into_iter().map(|t| t.node).collect::<Vec<_>>()),
+            Ok(vec![
+                Token::Num(1000),
+                Token::Num(2500),
+                Token::Float(0.15),
+                Token::Float(1e10)
+            ])
+        );
+        assert_eq!(
+            lexer().parse("#12").into_result(),
+            Ok(vec![Spanned {
+                node: Token::Tag("12"),
+                span: Span::from(0..3)
+            }])
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let src = "move @0,0 // \"é\"\n[label:\"é\"] 1_0,1";
+        let (lexemes, errors) = tokenize(src);
+        assert!(errors.is_empty());
+
+        let source_map = SourceMap::new(src);
+        let json = lexemes
+            .iter()
+            .map(|lexeme| to_json(src, &source_map, lexeme))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            json.iter()
+                .map(|token| token["kind"].clone())
+                .collect::<Vec<_>>(),
+            [
+                "move",
+                "at",
+                "num",
+                "comma",
+                "num",
+                "comment",
+                "open_square",
+                "ident",
+                "colon",
+                "str",
+                "close_square",
+                "num",
+                "comma",
+                "num"
+            ]
+        );
+        assert_eq!(
+            json[5],
+            json!({"kind": "comment", "text": "// \"é\"", "start": 10, "end": 17, "line": 1, "column": 11})
+        );
+        assert_eq!(
+            json[11],
+            json!({"kind": "num", "text": "1_0", "value": 10, "start": 31, "end": 34, "line": 2, "column": 13})
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Parsing and rendering of blueprints, the text based representation of drawings read by the
+//! `blueprint` tool.
+//!
+//! A blueprint is loaded from its source with [`loader::load_blueprint_str`] (or from a file with
+//! [`loader::load_blueprint`]), which returns the [`domain::Blueprint`] along with the problems
+//! met, then exported with [`export::export_to`]:
+//!
+//! ```
+//! use blueprint_core::domain::Color;
+//! use blueprint_core::export::{ExportOptions, export_to};
+//! use blueprint_core::loader::load_blueprint_str;
+//! use blueprint_core::ps::Paper;
+//! use std::path::Path;
+//!
+//! let (blueprint, errors) =
+//!     load_blueprint_str("10,0 0,10 -10,0 0,-10", Path::new("square.bp"), false);
+//! assert!(errors.is_empty());
+//!
+//! let mut png = Vec::new();
+//! let options = ExportOptions {
+//!     format: "png".to_string(),
+//!     background: Color::White,
+//!     scale: 4.,
+//!     supersample: 1,
+//!     ascii: false,
+//!     feed_rate: 1000.,
+//!     tag_markers: false,
+//!     dimensions: false,
+//!     monochrome: None,
+//!     paper: Paper::A4,
+//! };
+//! export_to(&blueprint.unwrap(), &mut png, &options).unwrap();
+//! assert!(png.starts_with(b"\x89PNG"));
+//! ```
+
+/// Writing of images as bmp files.
+pub mod bmp;
+/// Building of blueprints without going through their source.
+pub mod builder;
+/// Pixels images are drawn on.
+pub mod canvas;
+/// Generation of Rust code building blueprints.
+pub mod codegen;
+/// Dimensions of the edges of blueprints, drawn as edges.
+pub mod dimension;
+/// Blueprints, made of shapes of edges, and the geometry they rely on.
+pub mod domain;
+/// Debugging view of the commands and edges of a blueprint.
+pub mod dump;
+/// Writing of blueprints as DXF drawings.
+pub mod dxf;
+/// Problems met while reading blueprints, and their reporting.
+pub mod error;
+/// Writing of blueprints in any of the supported formats.
+pub mod export;
+mod font;
+/// Canonical formatting of blueprint sources.
+pub mod formatter;
+/// Writing of blueprints as G-code programs.
+pub mod gcode;
+/// Syntax-highlighting definitions of blueprint sources for editors.
+pub mod grammar;
+/// Writing of blueprints as HP-GL plots.
+pub mod hpgl;
+/// Drawing of blueprints as images.
+pub mod image;
+/// Index of the edges of blueprints by location.
+pub mod index;
+/// Summary of blueprints, e.g. the areas of their rooms.
+pub mod info;
+/// Reading of blueprints from JSON documents.
+pub mod json;
+/// Tokens of blueprint sources.
+pub mod lexer;
+/// Suspicious geometry of blueprints.
+pub mod lint;
+/// Loading of blueprints from their sources, resolving tags and includes.
+pub mod loader;
+/// Writing of images as pam files.
+pub mod pam;
+/// Commands of blueprint sources.
+pub mod parser;
+/// Writing of images as png files.
+pub mod png;
+/// Writing and reading of images as ppm files.
+pub mod ppm;
+/// Writing of blueprints as PostScript documents.
+pub mod ps;
+/// Drawing of blueprints by Rhai scripts.
+#[cfg(feature = "script")]
+pub mod script;
+/// Writing of blueprints as blueprint sources.
+pub mod source;
+/// Lines and columns of the offsets of sources.
+pub mod source_map;
+/// Writing of blueprints as SVG drawings.
+pub mod svg;
+/// Drawing of images in terminals.
+pub mod term;
+/// Polylines drawn by plotters.
+pub mod toolpath;
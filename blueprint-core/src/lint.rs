@@ -0,0 +1,233 @@
+use crate::domain::{Blueprint, Curve, Edge, Point, Shape, Visitor};
+use crate::error::BlueprintError;
+use crate::lexer::Span;
+use std::collections::HashMap;
+
+/// Maximum distance between the ends of a shape for it to be considered almost closed.
+const CLOSING_EPSILON: f32 = 2.;
+
+/// Distance under which points are considered at the same place, so that rounding errors do not
+/// hide edges drawn over each other.
+const EPSILON: f32 = 1e-3;
+
+/// Sizes of the buckets straight edges are grouped in, by the angle (in radians) and the distance
+/// to the origin of the line they lie on.
+const ANGLE_STEP: f32 = 1e-3;
+const OFFSET_STEP: f32 = 1.;
+
+/// Looks for suspicious geometry in `blueprint`, reported as warnings:
+///  * zero-length edges,
+///  * edges drawn twice, in either direction, or partly drawn over another straight edge,
+///  * shapes whose last point is close to, but not exactly at, their first point,
+///  * transparent edges, which are better expressed with `move`.
+pub fn lint(blueprint: &Blueprint) -> Vec<BlueprintError> {
+    let mut linter = Linter::default();
+    blueprint.visit(&mut linter);
+    linter.warnings
+}
+
+/// Ends of a straight edge and its span.
+type Segment = (Point, Point, Span);
+
+#[derive(Default)]
+struct Linter {
+    warnings: Vec<BlueprintError>,
+    /// Straight edges drawn so far and their span, by the rounded line they lie on.
+    drawn_edges: HashMap<(i64, i64), Vec<Segment>>,
+}
+
+impl Linter {
+    /// Span of an edge drawn before over the straight edge from `from` to `to`, and whether both
+    /// edges are identical, remembering the edge when none is found.
+    fn drawn_over(&mut self, from: Point, to: Point, span: Span) -> Option<(Span, bool)> {
+        let (angle, offset) = line(from, to);
+        let drawn = (-1..=1)
+            .flat_map(|da| (-1..=1).map(move |doff| bucket(angle + da, offset + doff)))
+            .filter_map(|key| self.drawn_edges.get(&key))
+            .flatten()
+            .find_map(|&(a, b, span)| overlap(a, b, from, to).map(|identical| (span, identical)));
+        if drawn.is_none() {
+            self.drawn_edges
+                .entry(bucket(angle, offset))
+                .or_default()
+                .push((from, to, span));
+        }
+        drawn
+    }
+}
+
+impl Visitor for Linter {
+    fn visit_shape(&mut self, shape: &Shape) {
+        let (Some(first), Some(last)) = (shape.edges_iter().next(), shape.edges_iter().last())
+        else {
+            return;
+        };
+        let gap = last.to.distance_to_point(&first.from);
+        if gap > EPSILON && gap <= CLOSING_EPSILON {
+            self.warnings.push(
+                warning(
+                    format!("shape is almost closed, its ends are {gap} apart"),
+                    last,
+                )
+                .with_label("shape starts here", span(first)),
+            );
+        }
+    }
+
+    fn visit_edge(&mut self, _shape: &Shape, edge: &Edge) {
+        if edge.length() <= EPSILON {
+            self.warnings.push(warning("zero-length edge", edge));
+        } else if edge.curve == Curve::Line
+            && let Some((first, identical)) = self.drawn_over(edge.from, edge.to, span(edge))
+        {
+            let message = if identical {
+                "edge drawn over an identical edge"
+            } else {
+                "edge drawn over part of another edge"
+            };
+            self.warnings
+                .push(warning(message, edge).with_label("first drawn here", first));
+        }
+
+        if edge.color.is_transparent() {
+            self.warnings.push(warning(
+                "transparent edge, consider using `move` instead",
+                edge,
+            ));
+        }
+    }
+}
+fn warning(message: impl Into<String>, edge: &Edge) -> BlueprintError {
+    BlueprintError::warning(message).with_span(span(edge), edge.span.line)
+}
+
+fn span(edge: &Edge) -> Span {
+    Span::from(edge.span.start..edge.span.end)
+}
+
+/// Rounded angle, within a half-turn, and distance to the origin of the line through `from` and
+/// `to`, whatever their order. Both are `+ 0.` so that `-0.` and `0.` fall in the same bucket.
+fn line(from: Point, to: Point) -> (i64, i64) {
+    let length = from.distance_to_point(&to);
+    let (mut dx, mut dy) = ((to.x - from.x) / length, (to.y - from.y) / length);
+    if dy < 0. || (dy == 0. && dx < 0.) {
+        (dx, dy) = (-dx, -dy);
+    }
+    let angle = dy.atan2(dx) + 0.;
+    let offset = from.x * dy - from.y * dx + 0.;
+
+    (
+        (angle / ANGLE_STEP).round() as i64,
+        (offset / OFFSET_STEP).round() as i64,
+    )
+}
+
+/// Bucket of the lines of rounded `angle` and `offset`, lines turned by a half-turn being the
+/// same ones with an opposite offset.
+fn bucket(angle: i64, offset: i64) -> (i64, i64) {
+    let half_turn = (std::f32::consts::PI / ANGLE_STEP).round() as i64;
+    match angle {
+        angle if angle < 0 => (angle + half_turn, -offset),
+        angle if angle >= half_turn => (angle - half_turn, -offset),
+        angle => (angle, offset),
+    }
+}
+
+/// Whether the segment from `c` to `d` lies on the one from `a` to `b` over more than [`EPSILON`],
+/// `Some(true)` when both are identical, in either direction.
+fn overlap(a: Point, b: Point, c: Point, d: Point) -> Option<bool> {
+    let close = |p: Point, q: Point| p.distance_to_point(&q) <= EPSILON;
+    if (close(a, c) && close(b, d)) || (close(a, d) && close(b, c)) {
+        return Some(true);
+    }
+
+    let length = a.distance_to_point(&b);
+    let (dx, dy) = ((b.x - a.x) / length, (b.y - a.y) / length);
+    // Distance of `p` to the line through `a` and `b`, and position of its projection on it.
+    let distance = |p: Point| ((p.x - a.x) * dy - (p.y - a.y) * dx).abs();
+    let position = |p: Point| (p.x - a.x) * dx + (p.y - a.y) * dy;
+    if distance(c) > EPSILON || distance(d) > EPSILON {
+        return None;
+    }
+    let (start, end) = (position(c), position(d));
+    let overlap = start.max(end).min(length) - start.min(end).max(0.);
+
+    (overlap > EPSILON).then_some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Color, SourceSpan};
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    fn lint_messages(src: &str) -> Vec<String> {
+        let (blueprint, _) = load_blueprint_str(src, Path::new("test.bp"), false);
+        lint(&blueprint.unwrap())
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        assert_eq!(
+            lint_messages("{ 10,0 0,10 -10,0 0,-10 }"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            lint_messages("{ 10,0 0,10 -10,0 0,-9 }"),
+            vec!["shape is almost closed, its ends are 1 apart"]
+        );
+        assert_eq!(lint_messages("{ 10,0 0,0 }"), vec!["zero-length edge"]);
+        assert_eq!(
+            lint_messages("{ 10,0 } { move @10,0 @0,0 }"),
+            vec!["edge drawn over an identical edge"]
+        );
+        assert_eq!(
+            lint_messages("{ 10,0 } { move @5,0 10,0 } { move @0,5 0,10 } { move @0,20 0,-5 }"),
+            vec!["edge drawn over part of another edge"]
+        );
+        assert_eq!(
+            lint_messages("{ 10,0 } { move @10,0 10,0 }"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            lint_messages("{ [color:transparent] 10,0 }"),
+            vec!["transparent edge, consider using `move` instead"]
+        );
+    }
+
+    #[test]
+    fn test_lint_rounding() {
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            Edge::new(0., -0., 0., 10., Color::Black, SourceSpan::default()),
+            Edge::new(-0., 10., 0., 0., Color::Black, SourceSpan::default()),
+            Edge::new(0.1, 0., 10., 0., Color::Black, SourceSpan::default()),
+            Edge::new(
+                10.0001,
+                0.,
+                0.1,
+                0.00001,
+                Color::Black,
+                SourceSpan::default(),
+            ),
+            Edge::new(0., 0., 0., 0.0001, Color::Black, SourceSpan::default()),
+        ]));
+        let messages = lint(&blueprint)
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "edge drawn over an identical edge",
+                "edge drawn over an identical edge",
+                "zero-length edge",
+            ]
+        );
+    }
+}
@@ -0,0 +1,1217 @@
+use crate::domain::{
+    Attributes, Blueprint, Constraint, Coordinate, Edge, LineStyle, Point, Shape, ShapeId,
+    SourceSpan,
+};
+use crate::error::{BlueprintError, Severity};
+use crate::lexer::Span;
+use crate::parser;
+use crate::parser::{Chunk, CommandKind, Coord, Expr};
+use crate::source_map::SourceMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Extension of blueprint sources.
+pub const EXTENSION: &str = "bp";
+
+/// Size in bytes above which sources are read and parsed a chunk at a time.
+const STREAMED_SIZE: u64 = 16 << 20;
+
+/// Size in bytes from which chunks of sources read a chunk at a time end.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Loads the blueprint stored in `path`. The blueprint is returned even if parts of the source had
+/// to be skipped, unless the file could not be read or errors left nothing of it; all problems
+/// are in the returned errors. In strict mode, warnings are reported as errors.
+pub fn load_blueprint(path: &Path, strict: bool) -> (Option<Blueprint>, Vec<BlueprintError>) {
+    load_blueprint_as(path, strict)
+}
+
+/// Loads the blueprint stored in `path` as [`load_blueprint`] does, with coordinates of type `T`,
+/// e.g. `f64` for site plans whose coordinates run into the millions.
+pub fn load_blueprint_as<T: Coordinate>(
+    path: &Path,
+    strict: bool,
+) -> (Option<Blueprint<T>>, Vec<BlueprintError>) {
+    // generated blueprints can be too large to be held in memory along with their commands
+    if path
+        .extension()
+        .is_some_and(|extension| extension == EXTENSION)
+        && fs::metadata(path).is_ok_and(|metadata| metadata.len() > STREAMED_SIZE)
+    {
+        let (blueprint, errors) = BlueprintLoader::new().strict(strict).exec_file(path);
+        return (usable(&blueprint, &errors), errors);
+    }
+
+    match fs::read_to_string(path) {
+        Ok(src) => load_blueprint_str_as(&src, path, strict),
+        Err(e) => (
+            None,
+            vec![BlueprintError::error(format!(
+                "Could not open {}: {}",
+                path.display(),
+                e
+            ))],
+        ),
+    }
+}
+
+/// Loads the blueprint from `src`, read from `path`. The path is used to resolve includes, `.json`
+/// paths are loaded as JSON documents and `.rhai` paths as scripts when the `script` feature is
+/// enabled.
+pub fn load_blueprint_str(
+    src: &str,
+    path: &Path,
+    strict: bool,
+) -> (Option<Blueprint>, Vec<BlueprintError>) {
+    load_blueprint_str_as(src, path, strict)
+}
+
+/// Loads the blueprint from `src` as [`load_blueprint_str`] does, with coordinates of type `T`.
+pub fn load_blueprint_str_as<T: Coordinate>(
+    src: &str,
+    path: &Path,
+    strict: bool,
+) -> (Option<Blueprint<T>>, Vec<BlueprintError>) {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == crate::json::EXTENSION)
+    {
+        let (blueprint, errors) = crate::json::load_json_str(src);
+        return (blueprint.map(|blueprint| blueprint.widen()), errors);
+    }
+    #[cfg(feature = "script")]
+    if path
+        .extension()
+        .is_some_and(|extension| extension == crate::script::EXTENSION)
+    {
+        let (blueprint, errors) = crate::script::load_script_str(src, path);
+        return (blueprint.map(|blueprint| blueprint.widen()), errors);
+    }
+
+    let (commands, mut errors) = parser::parse(src);
+
+    let (blueprint, diagnostics) =
+        BlueprintLoader::new()
+            .strict(strict)
+            .exec(&commands, path, SourceMap::new(src));
+    errors.extend(diagnostics);
+
+    (usable(&blueprint, &errors), errors)
+}
+
+/// `blueprint`, in the precision it is loaded in, unless `errors` left nothing of it: callers such
+/// as editors can then keep the last blueprint loaded rather than an empty one.
+fn usable<T: Coordinate>(
+    blueprint: &Blueprint<f64>,
+    errors: &[BlueprintError],
+) -> Option<Blueprint<T>> {
+    let empty = blueprint.edges_iter().next().is_none() && blueprint.tags_iter().next().is_none();
+    (!empty || !errors.iter().any(BlueprintError::is_error)).then(|| blueprint.cast())
+}
+
+struct BlueprintLoader {
+    points: HashMap<String, Tag>,
+    /// Tags resolved by the previous pass, used to resolve references to tags defined later.
+    forward_points: HashMap<String, Position>,
+    /// Tags having a definition, even if its position could not be resolved.
+    declared_tags: HashSet<String>,
+    referenced_tags: HashSet<String>,
+    /// References that could not be resolved, with their located error lacking a message.
+    unresolved_references: Vec<(String, BlueprintError)>,
+    /// Last edge drawn to each tag, the constraints of other edges referring to it.
+    tagged_edges: HashMap<String, Edge<f64>>,
+    /// Constraints of the edges drawn, checked once all of them are, with their located warning
+    /// lacking a message.
+    constraints: Vec<(Edge<f64>, Constraint, BlueprintError)>,
+    /// Current position, `None` when it depends on a tag that could not be resolved.
+    position: Option<Position>,
+    stack: Vec<Option<Position>>,
+    /// Blocks being executed, the outermost first.
+    blocks: Vec<Block>,
+    /// Files being loaded, the one given to the loader first and the innermost include last.
+    include_stack: Vec<SourceFile>,
+    /// Blueprint built, in double precision until it is moved to the origin.
+    blueprint: Blueprint<f64>,
+    diagnostics: Vec<BlueprintError>,
+    strict: bool,
+}
+
+struct Block {
+    id: ShapeId,
+    /// Unnamed blocks executed so far in the block.
+    unnamed: usize,
+    /// Named blocks executed so far in the block, by name.
+    names: HashMap<String, usize>,
+}
+
+/// Block of a file executed a chunk at a time, whose commands are read over several chunks.
+struct StreamedBlock {
+    name: Option<String>,
+    id: ShapeId,
+    edges: Vec<Edge<f64>>,
+    /// Whether no command of the block was read so far, in which case it draws no shape.
+    empty: bool,
+}
+
+struct Tag {
+    point: Position,
+    span: Span,
+    line: usize,
+    /// Path of the included file defining the tag, if any.
+    path: Option<PathBuf>,
+}
+
+/// Point the loader is at, kept in double precision so that long chains of relative moves, e.g.
+/// on site plans in millimeters, do not pile up rounding errors: points are only rounded once the
+/// blueprint is moved to the origin, to the precision it is loaded in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Position {
+    x: f64,
+    y: f64,
+    /// Command that moved the loader to the position.
+    span: SourceSpan,
+}
+
+impl Position {
+    fn add(self, dx: f64, dy: f64) -> Self {
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+            span: self.span,
+        }
+    }
+
+    fn point(self) -> Point<f64> {
+        Point {
+            x: self.x,
+            y: self.y,
+            span: self.span,
+        }
+    }
+}
+
+struct SourceFile {
+    /// Path used to display the file.
+    path: PathBuf,
+    /// Canonical path, used to detect circular includes.
+    canonical_path: PathBuf,
+    source_map: SourceMap,
+    /// Span of the `include` command, in the including file.
+    included_at: Option<Span>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, source_map: SourceMap, included_at: Option<Span>) -> Self {
+        Self {
+            canonical_path: path.canonicalize().unwrap_or_else(|_| path.clone()),
+            path,
+            source_map,
+            included_at,
+        }
+    }
+}
+
+impl BlueprintLoader {
+    pub fn new() -> Self {
+        Self {
+            position: Some(Position::default()),
+            points: Default::default(),
+            forward_points: Default::default(),
+            declared_tags: Default::default(),
+            referenced_tags: Default::default(),
+            unresolved_references: Default::default(),
+            tagged_edges: Default::default(),
+            constraints: Default::default(),
+            stack: Default::default(),
+            blocks: Default::default(),
+            include_stack: Default::default(),
+            blueprint: Default::default(),
+            diagnostics: Default::default(),
+            strict: false,
+        }
+    }
+
+    /// In strict mode, warnings are reported as errors.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Builds the blueprint described by `commands`, parsed from the file at `path`.
+    ///
+    /// Tags may be referenced before being defined: the commands are executed several times,
+    /// each pass resolving such references with the tags found by the previous one, until all
+    /// of them are resolved or a pass does not resolve any new tag. Only the diagnostics of the
+    /// last pass are returned.
+    pub fn exec(
+        self,
+        commands: &[parser::Command],
+        path: &Path,
+        source_map: SourceMap,
+    ) -> (Blueprint<f64>, Vec<BlueprintError>) {
+        // self.nodes.reserve(commands.len());
+
+        self.passes(|pass| {
+            pass.include_stack.push(SourceFile::new(
+                path.to_path_buf(),
+                source_map.clone(),
+                None,
+            ));
+            pass.exec_block(commands, None, ShapeId::default());
+        })
+    }
+
+    /// Builds the blueprint of the file at `path` as [`Self::exec`] does, reading and parsing it
+    /// a chunk of [`CHUNK_SIZE`] bytes at a time: only the commands of a chunk are held in
+    /// memory. The file is read again by each pass.
+    pub fn exec_file(self, path: &Path) -> (Blueprint<f64>, Vec<BlueprintError>) {
+        self.passes(|pass| pass.exec_chunks(path, CHUNK_SIZE))
+    }
+
+    /// Runs `pass` until all the references to tags are resolved, or a pass does not resolve any
+    /// new tag.
+    fn passes(self, mut run: impl FnMut(&mut Self)) -> (Blueprint<f64>, Vec<BlueprintError>) {
+        let mut pass = self;
+        loop {
+            run(&mut pass);
+
+            let progress = pass.points.len() > pass.forward_points.len();
+            if pass.unresolved_references.is_empty() || !progress {
+                return pass.finish();
+            }
+
+            pass = Self {
+                forward_points: pass
+                    .points
+                    .into_iter()
+                    .map(|(tag, definition)| (tag, definition.point))
+                    .collect(),
+                declared_tags: pass.declared_tags,
+                strict: pass.strict,
+                ..Self::new()
+            };
+        }
+    }
+
+    fn finish(mut self) -> (Blueprint<f64>, Vec<BlueprintError>) {
+        for (tag, error) in self.unresolved_references.drain(..) {
+            let message = if self.declared_tags.contains(&tag) {
+                format!("#{tag} could not be resolved, as its position depends on itself")
+            } else {
+                format!("#{tag} not found")
+            };
+            self.diagnostics.push(BlueprintError { message, ..error });
+        }
+
+        for (edge, constraint, warning) in std::mem::take(&mut self.constraints) {
+            // checked in `f32` relative to the edge, wherever it lies
+            let origin = edge.origin();
+            let other = constraint.tag().map(|tag| {
+                let other = self.tagged_edges.get(tag);
+                (tag, other.map(|other| other.relative_to(origin)))
+            });
+            let edge = edge.relative_to(origin);
+            let message = match other {
+                Some((tag, None)) => format!("no edge is drawn to #{tag}"),
+                _ if !constraint.is_satisfied(&edge, other.and_then(|(_, edge)| edge).as_ref()) => {
+                    format!("edge is not {constraint}")
+                }
+                _ => continue,
+            };
+            self.warn(BlueprintError { message, ..warning });
+        }
+
+        let mut unused_tags = self
+            .points
+            .iter()
+            .filter(|(tag, _)| !self.referenced_tags.contains(*tag))
+            .collect::<Vec<_>>();
+        unused_tags.sort_by_key(|(_, definition)| (definition.path.clone(), definition.span.start));
+        let warnings = unused_tags
+            .into_iter()
+            .map(|(tag, definition)| {
+                let warning = BlueprintError::warning(format!("#{tag} is never referenced"))
+                    .with_span(definition.span, definition.line);
+                match &definition.path {
+                    None => warning,
+                    Some(path) => warning.with_path(path.clone()),
+                }
+            })
+            .collect::<Vec<_>>();
+        for warning in warnings {
+            self.warn(warning);
+        }
+
+        for (tag, definition) in self.points {
+            self.blueprint.tag(tag, definition.point.point());
+        }
+        self.blueprint.translate_to_origin();
+        (self.blueprint, self.diagnostics)
+    }
+
+    /// Executes the file at `path` as the outermost block, a chunk of at least `chunk_size` bytes
+    /// at a time.
+    fn exec_chunks(&mut self, path: &Path, chunk_size: usize) {
+        let error = |e: io::Error| {
+            BlueprintError::error(format!("Could not open {}: {}", path.display(), e))
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                self.diagnostics.push(error(e));
+                return;
+            }
+        };
+
+        self.include_stack.push(SourceFile::new(
+            path.to_path_buf(),
+            SourceMap::new(""),
+            None,
+        ));
+        self.blocks.push(Block {
+            id: ShapeId::default(),
+            unnamed: 0,
+            names: HashMap::new(),
+        });
+        // the file, then the blocks opened by a chunk and not closed yet
+        let mut streamed = vec![StreamedBlock {
+            name: None,
+            id: ShapeId::default(),
+            edges: Vec::new(),
+            empty: true,
+        }];
+        for chunk in parser::chunks(BufReader::new(file), chunk_size) {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    self.diagnostics.push(error(e));
+                    break;
+                }
+            };
+            let source_map = &mut self.include_stack[0].source_map;
+            source_map.extend(chunk.source());
+            let end = source_map.len();
+            let block = streamed.last_mut().expect("the file is being executed");
+            match chunk {
+                Chunk::Commands(source) => {
+                    let (commands, errors) = parser::parse_chunk(&source, source_map);
+                    self.diagnostics.extend(errors);
+                    block.empty &= commands.is_empty();
+                    self.exec_commands(&commands, &mut block.edges);
+                }
+                Chunk::Open(source, name) => {
+                    block.empty = false;
+                    let span = Span::from(end - source.len()..end);
+                    let id = self.nested_id(name.as_deref(), span);
+                    self.stack.push(self.position);
+                    self.blocks.push(Block {
+                        id,
+                        unnamed: 0,
+                        names: HashMap::new(),
+                    });
+                    streamed.push(StreamedBlock {
+                        name,
+                        id,
+                        edges: Vec::new(),
+                        empty: true,
+                    });
+                }
+                Chunk::Close(_) if streamed.len() > 1 => self.close_streamed(&mut streamed),
+                Chunk::Close(_) => {}
+            }
+        }
+        // the blocks left open are reported as the parser does
+        if streamed.len() > 1 {
+            let end = self.include_stack[0].source_map.len();
+            let error = self.locate_error(
+                BlueprintError::error("found end of input expected '}'"),
+                Span::from(end..end),
+            );
+            self.diagnostics.push(error);
+        }
+        while streamed.len() > 1 {
+            self.close_streamed(&mut streamed);
+        }
+        self.end_streamed(streamed.pop().expect("the file is being executed"));
+    }
+
+    /// Ends the innermost block opened of `streamed`, back to the position it started at.
+    fn close_streamed(&mut self, streamed: &mut Vec<StreamedBlock>) {
+        self.end_streamed(streamed.pop().expect("a block is opened"));
+        if let Some(position) = self.stack.pop() {
+            self.position = position;
+        }
+    }
+
+    /// Ends `block`, executed a chunk at a time, pushing its shape unless it is empty.
+    fn end_streamed(&mut self, block: StreamedBlock) {
+        self.blocks.pop();
+        if !block.empty {
+            self.blueprint.push(
+                Shape::from(block.edges)
+                    .with_name(block.name.as_deref())
+                    .with_id(block.id),
+            );
+        }
+    }
+
+    fn exec_block(&mut self, commands: &[parser::Command], name: Option<&str>, id: ShapeId) {
+        if commands.is_empty() {
+            return;
+        }
+        let mut edges = Vec::with_capacity(commands.len() - 1);
+        self.blocks.push(Block {
+            id,
+            unnamed: 0,
+            names: HashMap::new(),
+        });
+        self.exec_commands(commands, &mut edges);
+        self.blocks.pop();
+        self.blueprint
+            .push(Shape::from(edges).with_name(name).with_id(id));
+    }
+
+    /// Executes `commands` in the block being executed, pushing the edges they draw to `edges`.
+    fn exec_commands(&mut self, commands: &[parser::Command], edges: &mut Vec<Edge<f64>>) {
+        for command in commands {
+            let (draw, to, tag) = match &command.kind {
+                CommandKind::Move(coord) => {
+                    let (to, tag) = self.coord(coord, command.span);
+                    (None, to, tag)
+                }
+                CommandKind::Draw(coord, color, attributes) => {
+                    let mut width = 1.;
+                    let mut style = LineStyle::default();
+                    let mut layer = None;
+                    let mut constraint = None;
+                    let mut custom = Attributes::new();
+                    for attribute in attributes {
+                        match (attribute.key.node, &attribute.value.node) {
+                            ("width", value) if let Some(n) = value.as_number() => {
+                                width = n;
+                                continue;
+                            }
+                            ("style", value)
+                                if let Some(Ok(value)) =
+                                    value.as_ident().map(LineStyle::try_from) =>
+                            {
+                                style = value;
+                                continue;
+                            }
+                            ("layer", value) if let Some(name) = value.as_ident() => {
+                                layer = Some(self.blueprint.layer(name));
+                                continue;
+                            }
+                            ("constraint", value)
+                                if let Ok(value) = Constraint::try_from(value) =>
+                            {
+                                constraint = Some((value, attribute.value.span));
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        // kept on the edge for the tools using them, and reported in case of a
+                        // typo
+                        custom.insert(
+                            attribute.key.node.to_string(),
+                            attribute.value.node.to_string(),
+                        );
+                        let warning = self.locate_error(
+                            BlueprintError::warning(format!(
+                                "unknown attribute `{}`",
+                                attribute.key.node
+                            )),
+                            attribute.key.span,
+                        );
+                        self.warn(warning);
+                    }
+
+                    let custom =
+                        (!custom.is_empty()).then(|| self.blueprint.intern_attributes(custom));
+                    let from = self.position;
+                    let (to, tag) = self.coord(coord, command.span);
+                    (
+                        Some((from, color, width, style, layer, custom, constraint)),
+                        to,
+                        tag,
+                    )
+                }
+                CommandKind::Error => continue,
+                CommandKind::Include(include) => {
+                    self.include(include.as_ref(), command.span);
+                    continue;
+                }
+                CommandKind::Nested(name, commands) => {
+                    self.exec_nested(commands, *name, command.span);
+                    continue;
+                }
+            };
+
+            if let Some((Some(from), color, width, style, layer, custom, constraint)) = draw
+                && let Some(to) = to
+            {
+                let edge = Edge::new_from_points(
+                    from.point(),
+                    to.point(),
+                    *color,
+                    self.edge_span(command.span),
+                )
+                .with_width(width)
+                .with_style(style)
+                .with_layer(layer)
+                .with_attributes(custom);
+                edges.push(edge);
+
+                if let Some(tag) = tag {
+                    self.tagged_edges.insert(tag.to_string(), edge);
+                }
+                if let Some((constraint, span)) = constraint {
+                    if let Some(tag) = constraint.tag() {
+                        self.referenced_tags.insert(tag.to_string());
+                    }
+                    let warning = self
+                        .locate_error(BlueprintError::warning(""), command.span)
+                        .with_label("constraint given here", span);
+                    self.constraints.push((edge, constraint, warning));
+                }
+            }
+
+            if let Some(tag) = tag {
+                self.declared_tags.insert(tag.to_string());
+                if let Some(to) = to {
+                    self.define(tag, to, command.span);
+                }
+            }
+
+            self.position = to;
+        }
+    }
+
+    /// Computes the point `coord`, given by the command at `span`, refers to, if it can be
+    /// resolved, and the tag to attach to it.
+    fn coord<'s>(&mut self, coord: &Coord<'s>, span: Span) -> (Option<Position>, Option<&'s str>) {
+        let (position, tag) = match coord {
+            Coord::Absolute(x, y, tag) => {
+                let x = self.eval(x, span);
+                let y = self.eval(y, span);
+                (
+                    x.zip(y).map(|(x, y)| Position {
+                        x,
+                        y,
+                        ..Default::default()
+                    }),
+                    *tag,
+                )
+            }
+            Coord::Relative(dx, dy, tag) => {
+                let dx = self.eval(dx, span);
+                let dy = self.eval(dy, span);
+                (
+                    self.position
+                        .zip(dx.zip(dy))
+                        .map(|(from, (dx, dy))| from.add(dx, dy)),
+                    *tag,
+                )
+            }
+            Coord::Reference(tag) => (self.resolve(tag, span), None),
+        };
+        let span = self.edge_span(span);
+        (position.map(|position| Position { span, ..position }), tag)
+    }
+
+    /// Evaluates `expr`, if all the tags it refers to can be resolved.
+    fn eval(&mut self, expr: &Expr, span: Span) -> Option<f64> {
+        match expr {
+            Expr::Num(n) => Some(*n as f64),
+            Expr::X(tag) => self.resolve(tag, span).map(|point| point.x),
+            Expr::Y(tag) => self.resolve(tag, span).map(|point| point.y),
+            Expr::Neg(e) => self.eval(e, span).map(|v| -v),
+            Expr::Add(a, b) => {
+                let a = self.eval(a, span);
+                let b = self.eval(b, span);
+                a.zip(b).map(|(a, b)| a + b)
+            }
+            Expr::Sub(a, b) => {
+                let a = self.eval(a, span);
+                let b = self.eval(b, span);
+                a.zip(b).map(|(a, b)| a - b)
+            }
+        }
+    }
+
+    /// Executes `commands` as a block nested in the one being executed, named `name` by the
+    /// command at `span`, if any.
+    fn exec_nested(&mut self, commands: &[parser::Command], name: Option<&str>, span: Span) {
+        let id = self.nested_id(name, span);
+        self.stack.push(self.position);
+
+        self.exec_block(commands, name, id);
+
+        if let Some(position) = self.stack.pop() {
+            self.position = position;
+        }
+    }
+
+    /// Identifier of the block named `name` starting at `span`, nested in the block being
+    /// executed. Names given twice in a block are reported.
+    fn nested_id(&mut self, name: Option<&str>, span: Span) -> ShapeId {
+        let parent = self.blocks.last_mut().expect("a block is being executed");
+        let (id, duplicate) = match name {
+            Some(name) => {
+                let count = parent.names.entry(name.to_string()).or_default();
+                *count += 1;
+                // shapes of the same name are told apart by their rank
+                match *count {
+                    1 => (parent.id.named(name), false),
+                    count => (parent.id.named(name).nth(count - 1), true),
+                }
+            }
+            None => {
+                parent.unnamed += 1;
+                (parent.id.nth(parent.unnamed - 1), false)
+            }
+        };
+        if duplicate {
+            let warning = self.locate_error(
+                BlueprintError::warning(format!(
+                    "shape `{}` is already defined in this block",
+                    name.unwrap_or_default()
+                )),
+                span,
+            );
+            self.warn(warning);
+        }
+        id
+    }
+
+    /// Executes the file at `include`, relative to the current file, as a nested block. Files
+    /// that cannot be read and circular includes are reported and skipped.
+    fn include(&mut self, include: &str, span: Span) {
+        let current = self.include_stack.last().expect("a file is being loaded");
+        let path = current.path.parent().unwrap_or(Path::new("")).join(include);
+        let file = SourceFile::new(path, SourceMap::new(""), Some(span));
+
+        if let Some(position) = self
+            .include_stack
+            .iter()
+            .position(|f| f.canonical_path == file.canonical_path)
+        {
+            let cycle = self.include_stack[position..]
+                .iter()
+                .chain([&file])
+                .map(|f| f.path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            let error = self.locate_error(
+                BlueprintError::error(format!("circular include: {cycle}")),
+                span,
+            );
+            self.diagnostics.push(error);
+            return;
+        }
+
+        let src = match fs::read_to_string(&file.path) {
+            Ok(src) => src,
+            Err(e) => {
+                let error = self.locate_error(
+                    BlueprintError::error(format!("Could not open {}: {}", file.path.display(), e)),
+                    span,
+                );
+                self.diagnostics.push(error);
+                return;
+            }
+        };
+
+        let (commands, errors) = parser::parse(&src);
+        self.diagnostics.extend(
+            errors
+                .into_iter()
+                .map(|error| error.with_path(file.path.clone())),
+        );
+
+        self.include_stack.push(SourceFile {
+            source_map: SourceMap::new(&src),
+            ..file
+        });
+        self.exec_nested(&commands, None, span);
+        self.include_stack.pop();
+    }
+
+    fn define(&mut self, tag: &str, point: Position, span: Span) {
+        let file = self.include_stack.last().expect("a file is being loaded");
+        let definition = Tag {
+            point,
+            span,
+            line: file.source_map.line_col(span.start).0,
+            path: (self.include_stack.len() > 1).then(|| file.path.clone()),
+        };
+
+        if let Some(previous) = self.points.insert(tag.to_string(), definition)
+            && !self.referenced_tags.contains(tag)
+        {
+            let warning = self.locate_error(
+                BlueprintError::warning(format!(
+                    "#{tag} is redefined before being referenced; its definition on line {} \
+                    is unreachable",
+                    previous.line
+                )),
+                span,
+            );
+            self.warn(warning);
+        }
+    }
+
+    /// Resolves `tag` to the latest definition seen so far, or to the one found by the previous
+    /// pass if it is defined later.
+    fn resolve(&mut self, tag: &str, span: Span) -> Option<Position> {
+        let point = self
+            .points
+            .get(tag)
+            .map(|definition| definition.point)
+            .or_else(|| self.forward_points.get(tag).copied());
+
+        match point {
+            None => {
+                let error = self.locate_error(BlueprintError::error(""), span);
+                self.unresolved_references.push((tag.to_string(), error));
+            }
+            Some(_) => {
+                self.referenced_tags.insert(tag.to_string());
+            }
+        }
+
+        point
+    }
+
+    fn warn(&mut self, warning: BlueprintError) {
+        if self.strict {
+            self.diagnostics.push(BlueprintError {
+                severity: Severity::Error,
+                ..warning
+            });
+        } else {
+            self.diagnostics.push(warning);
+        }
+    }
+
+    /// Attaches `span`, in the file being loaded, to `error`.
+    fn locate_error(&self, error: BlueprintError, span: Span) -> BlueprintError {
+        let file = self.include_stack.last().expect("a file is being loaded");
+        let error = error.with_span(span, file.source_map.line_col(span.start).0);
+
+        if self.include_stack.len() > 1 {
+            error.with_path(file.path.clone())
+        } else {
+            error
+        }
+    }
+
+    /// Source span of an edge created by the command at `span`. Edges coming from included files
+    /// point to the `include` command of the file given to the loader.
+    fn edge_span(&self, span: Span) -> SourceSpan {
+        let root = &self.include_stack[0];
+        let span = self
+            .include_stack
+            .get(1)
+            .and_then(|file| file.included_at)
+            .unwrap_or(span);
+
+        root.source_map.locate(span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_warnings() {
+        let src = "{ @0,0 #a [opacity:50] 5,0 #b 0,5 #b @#a }";
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+        assert!(blueprint.is_some());
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.severity, e.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Severity::Warning, "unknown attribute `opacity`"),
+                (
+                    Severity::Warning,
+                    "#b is redefined before being referenced; its definition on line 1 is unreachable"
+                ),
+                (Severity::Warning, "#b is never referenced"),
+            ]
+        );
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), true);
+        assert!(blueprint.is_some());
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(BlueprintError::is_error));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let load = |src| load_blueprint_str(src, Path::new("test.bp"), false);
+
+        // partial blueprints are kept, empty ones only when the source is
+        let (blueprint, errors) = load("10,0 0,x");
+        assert_eq!(blueprint.unwrap().edges_iter().count(), 1);
+        assert!(errors.iter().any(BlueprintError::is_error));
+        let (blueprint, errors) = load("0,x");
+        assert!(blueprint.is_none());
+        assert!(errors.iter().any(BlueprintError::is_error));
+        let (blueprint, errors) = load("");
+        assert!(blueprint.is_some());
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn test_exec_chunks() {
+        let src = "move @0,0\n10,0 #a // {\n{ @#b 0,5 }\n@#a;\nshape s {\n  5,0 #b\n}\n@0,0 ] 1,1;\n[label:\"}\"] 2,2";
+        let path = std::env::temp_dir().join(format!("blueprint-chunks-{}.bp", std::process::id()));
+        fs::write(&path, src).unwrap();
+
+        let (streamed, mut streamed_errors) =
+            BlueprintLoader::new().passes(|pass| pass.exec_chunks(&path, 1));
+        fs::remove_file(&path).unwrap();
+        let (blueprint, mut errors) = load_blueprint_str_as(src, &path, false);
+
+        let shapes = |blueprint: &Blueprint<f64>| {
+            blueprint
+                .shapes_iter()
+                .map(|shape| {
+                    let edges = shape
+                        .edges_iter()
+                        .map(|edge| (edge.from, edge.to, edge.span))
+                        .collect::<Vec<_>>();
+                    (shape.id(), edges)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shapes(&streamed), shapes(&blueprint.unwrap()));
+        streamed_errors.sort_by_key(|error| error.span.map(|span| span.start));
+        errors.sort_by_key(|error| error.span.map(|span| span.start));
+        assert_eq!(streamed_errors, errors);
+        assert_eq!(errors.len(), 2);
+        // the braces of comments and strings do not delimit blocks
+        assert_eq!(
+            parser::chunks(src.as_bytes(), 1)
+                .map(|chunk| match chunk.unwrap() {
+                    Chunk::Commands(_) => "commands",
+                    Chunk::Open(..) => "open",
+                    Chunk::Close(_) => "close",
+                })
+                .collect::<Vec<_>>(),
+            [
+                "commands", "commands", "open", "commands", "commands", "close", "commands",
+                "open", "commands", "close", "commands", "commands"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = std::env::temp_dir().join(format!("blueprint-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.bp"), "include \"b.bp\"").unwrap();
+        fs::write(dir.join("b.bp"), "{ move @0,0 5,0 }\ninclude \"a.bp\"").unwrap();
+
+        let (blueprint, errors) = load_blueprint(&dir.join("a.bp"), false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            blueprint
+                .unwrap()
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .count(),
+            1
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            format!(
+                "circular include: {} -> {} -> {}",
+                dir.join("a.bp").display(),
+                dir.join("b.bp").display(),
+                dir.join("a.bp").display()
+            )
+        );
+        assert_eq!(errors[0].path, Some(dir.join("b.bp")));
+        assert_eq!(errors[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_forward_references() {
+        let src = "move @#a 0,10 #c\nmove @#b 10,0 #a\nmove @5,5 #b @#c";
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            blueprint
+                .unwrap()
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .map(|edge| (edge.from, edge.to))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point::new(10., 0.), Point::new(10., 10.)),
+                (Point::new(0., 0.), Point::new(10., 0.)),
+                (Point::new(0., 0.), Point::new(10., 10.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_references() {
+        let src = "move @#a 5,0 #b\nmove @#b 0,5 #a\n@#c 5,5";
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+        // nothing could be drawn
+        assert!(blueprint.is_none());
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.line, e.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Some(1),
+                    "#a could not be resolved, as its position depends on itself"
+                ),
+                (
+                    Some(2),
+                    "#b could not be resolved, as its position depends on itself"
+                ),
+                (Some(3), "#c not found"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tag_components() {
+        let src = "move @10,20 #a move @40,0 #b 0,10\nmove @#a.x, #b.y + 5 #c @#b.x - #a.x,-#c.y";
+
+        let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+        assert_eq!(errors, vec![]);
+        let blueprint = blueprint.unwrap();
+        let edges = blueprint
+            .shapes_iter()
+            .flat_map(Shape::edges_iter)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges
+                .iter()
+                .map(|edge| (edge.from, edge.to))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point::new(30., 5.), Point::new(30., 15.)),
+                (Point::new(0., 10.), Point::new(20., 0.)),
+            ]
+        );
+        // points are located at the command placing them
+        let location = |point: Point| (point.span.line, point.span.column);
+        assert_eq!(location(edges[0].from), (1, 16));
+        assert_eq!(location(edges[0].to), (1, 30));
+        assert_eq!(location(edges[1].from), (2, 1));
+        assert_eq!(location(edges[1].to), (2, 25));
+    }
+
+    #[test]
+    fn test_style_attribute() {
+        let (blueprint, errors) = load_blueprint_str(
+            "[style:dashed] 5,0 [style:wavy] 0,5",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["`wavy` is not a known style."]
+        );
+        assert_eq!(
+            blueprint
+                .unwrap()
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .map(|edge| edge.style)
+                .collect::<Vec<_>>(),
+            vec![LineStyle::Dashed, LineStyle::Solid]
+        );
+    }
+
+    #[test]
+    fn test_constraint_attribute() {
+        let (_, errors) = load_blueprint_str(
+            "[constraint:horizontal] 10,0 #a [constraint:perpendicular(#a)] 0,10 #b
+[constraint:equal(#b)] -10,1 [constraint:parallel(#a)] 0,-11
+[constraint:equal(#c)] 5,5 [constraint:round] 1,1 [constraint:vertical(#a)] 1,1",
+            Path::new("test.bp"),
+            false,
+        );
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.message.as_str(), error.line.unwrap()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("`round` is not a known constraint.", 3),
+                ("`vertical(#a)` is not a known constraint.", 3),
+                ("edge is not as long as #b", 2),
+                ("edge is not parallel to #a", 2),
+                ("no edge is drawn to #c", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_attributes() {
+        let (blueprint, errors) = load_blueprint_str(
+            "[material:brick, fire:30] 10,0 [fire:30, material:brick] 0,5 [material:wood] -10,0 0,-5",
+            Path::new("test.bp"),
+            false,
+        );
+        assert_eq!(errors.len(), 5);
+
+        let blueprint = blueprint.unwrap();
+        let edges = blueprint.edges_iter().collect::<Vec<_>>();
+        assert_eq!(
+            edges.iter().map(|edge| edge.attributes).collect::<Vec<_>>(),
+            vec![Some(0), Some(0), Some(1), None]
+        );
+        assert_eq!(
+            blueprint.attributes(edges[0]),
+            Some(&Attributes::from([
+                ("fire".to_string(), "30".to_string()),
+                ("material".to_string(), "brick".to_string()),
+            ]))
+        );
+        assert_eq!(blueprint.attributes(edges[3]), None);
+    }
+
+    #[test]
+    fn test_precision() {
+        // one unit is below the precision of `f32` at 2^24, where moves are accumulated exactly
+        let (blueprint, _) = load_blueprint_str(
+            "move @16777216,0 1,0 1,0 1,0 1,0",
+            Path::new("test.bp"),
+            false,
+        );
+        let blueprint = blueprint.unwrap();
+        assert_eq!(
+            blueprint
+                .edges_iter()
+                .map(|edge| (edge.from, edge.to))
+                .collect::<Vec<_>>(),
+            (0..4)
+                .map(|x| (Point::new(x as f32, 0.), Point::new(x as f32 + 1., 0.)))
+                .collect::<Vec<_>>()
+        );
+
+        // points that stay far from the origin once moved to it are kept exactly in `f64`
+        let (blueprint, _) =
+            load_blueprint_str_as::<f64>("1,0 move @16777217,0 1,0", Path::new("test.bp"), false);
+        let edge = *blueprint.unwrap().edges_iter().last().unwrap();
+        assert_eq!((edge.from.x, edge.to.x), (16777217., 16777218.));
+    }
+
+    #[test]
+    fn test_layer_attribute() {
+        let (blueprint, errors) = load_blueprint_str(
+            "[layer:walls] 5,0 0,5 [layer:electric] -5,0 [layer:walls] 0,-5",
+            Path::new("test.bp"),
+            false,
+        );
+        assert!(errors.is_empty());
+
+        let blueprint = blueprint.unwrap();
+        assert_eq!(
+            blueprint
+                .layers_iter()
+                .map(|layer| layer.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["walls", "electric"]
+        );
+        assert_eq!(
+            blueprint
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .map(|edge| edge.layer)
+                .collect::<Vec<_>>(),
+            vec![Some(0), None, Some(1), Some(0)]
+        );
+    }
+
+    #[test]
+    fn test_width_attribute() {
+        let (blueprint, errors) = load_blueprint_str(
+            "[width:3] 5,0 0,5 [width:x] 5,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["`x` is not a number."]
+        );
+        assert_eq!(
+            blueprint
+                .unwrap()
+                .shapes_iter()
+                .flat_map(Shape::edges_iter)
+                .map(|edge| edge.width)
+                .collect::<Vec<_>>(),
+            vec![3., 1., 1.]
+        );
+    }
+
+    #[test]
+    fn test_shape_names() {
+        let load = |src| {
+            let (blueprint, errors) = load_blueprint_str(src, Path::new("test.bp"), false);
+            let shapes = blueprint
+                .unwrap()
+                .shapes_iter()
+                .map(|shape| (shape.name().map(str::to_string), shape.id()))
+                .collect::<Vec<_>>();
+            (shapes, errors)
+        };
+
+        let (shapes, errors) = load("shape kitchen { 10,0 } { 0,5 } shape kitchen { 5,5 }");
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["shape `kitchen` is already defined in this block"]
+        );
+        let root = ShapeId::default();
+        assert_eq!(
+            shapes,
+            vec![
+                (Some("kitchen".to_string()), root.named("kitchen")),
+                (None, root.nth(0)),
+                (Some("kitchen".to_string()), root.named("kitchen").nth(1)),
+                (None, root),
+            ]
+        );
+
+        // named shapes keep their identifier when moved, unnamed ones when others are added
+        let (reloaded, _) = load("{ 0,5 } { 5,5 } shape kitchen { 10,0 }");
+        assert_eq!(reloaded[0], shapes[1]);
+        assert_eq!(reloaded[2], shapes[0]);
+        assert_ne!(reloaded[1].1, shapes[1].1);
+    }
+}
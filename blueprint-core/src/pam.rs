@@ -0,0 +1,58 @@
+use crate::image::{ImageEncoder, Raster, bands};
+use std::io;
+use std::io::{BufWriter, Write};
+
+/// RGBA PAM image of a canvas, the variant of the Netpbm formats keeping the transparency of its
+/// pixels.
+pub struct PamImage<'c> {
+    raster: &'c dyn Raster,
+}
+
+impl ImageEncoder for PamImage<'_> {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(writer);
+        write!(
+            writer,
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+            self.raster.width(),
+            self.raster.height()
+        )?;
+        for rows in bands(self.raster) {
+            let band = self.raster.band(rows.clone());
+            for y in rows {
+                for x in 0..band.width {
+                    let (r, g, b, a) = band.get(x, y).as_rgba();
+                    writer.write_all(&[r, g, b, a])?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+impl<'c, R: Raster> From<&'c R> for PamImage<'c> {
+    fn from(value: &'c R) -> Self {
+        Self { raster: value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::domain::Color;
+
+    #[test]
+    fn test_write_to() {
+        let mut canvas = Canvas::new(2, 1).with_background(Color::Transparent);
+        canvas.set(1, 0, Color::Red);
+
+        let mut pam = Vec::new();
+        PamImage::from(&canvas).write_to(&mut pam).unwrap();
+        assert_eq!(
+            pam,
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n\
+              \x00\x00\x00\x00\xff\x00\x00\xff"
+        );
+    }
+}
@@ -0,0 +1,1152 @@
+use crate::domain::{Color, Constraint, LineStyle};
+use crate::error::BlueprintError;
+use crate::lexer::{Span, Spanned, Token, escape, lexer};
+use crate::source_map::SourceMap;
+use chumsky::input::ValueInput;
+use chumsky::prelude::*;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Coord<'s> {
+    Absolute(Expr<'s>, Expr<'s>, Option<&'s str>),
+    Relative(Expr<'s>, Expr<'s>, Option<&'s str>),
+    Reference(&'s str),
+}
+
+/// A coordinate component.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Expr<'s> {
+    Num(i32),
+    /// The `x` component of a tagged point: `#tag.x`.
+    X(&'s str),
+    /// The `y` component of a tagged point: `#tag.y`.
+    Y(&'s str),
+    Neg(Box<Expr<'s>>),
+    Add(Box<Expr<'s>>, Box<Expr<'s>>),
+    Sub(Box<Expr<'s>>, Box<Expr<'s>>),
+}
+
+impl Display for Coord<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (prefix, x, y, tag) = match self {
+            Coord::Absolute(x, y, tag) => ("@", x, y, tag),
+            Coord::Relative(x, y, tag) => ("", x, y, tag),
+            Coord::Reference(tag) => return write!(f, "@#{tag}"),
+        };
+        write!(f, "{prefix}{x},{y}")?;
+        if let Some(tag) = tag {
+            write!(f, " #{tag}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Expr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{n}"),
+            Expr::X(tag) => write!(f, "#{tag}.x"),
+            Expr::Y(tag) => write!(f, "#{tag}.y"),
+            Expr::Neg(e) => write!(f, "-{e}"),
+            Expr::Add(a, b) => write!(f, "{a} + {b}"),
+            Expr::Sub(a, b) => write!(f, "{a} - {b}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandKind<'s> {
+    /// Placeholder for tokens that could not be parsed, kept so that the surrounding commands
+    /// still produce geometry.
+    Error,
+    /// Block of commands drawing a shape of their own, named by `shape <name> { ... }`.
+    Nested(Option<&'s str>, Vec<Command<'s>>),
+    /// Executes the file at the given path, relative to the current file, as a nested block.
+    Include(Cow<'s, str>),
+    Move(Coord<'s>),
+    /// Draws an edge to the coordinate, with its color and its other attributes.
+    Draw(Coord<'s>, Color, Vec<Attribute<'s>>),
+}
+
+/// A `key:value` edge attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'s> {
+    pub key: Spanned<&'s str>,
+    pub value: Spanned<AttributeValue<'s>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue<'s> {
+    Ident(&'s str),
+    Num(i32),
+    Float(f32),
+    /// A string literal, e.g. `"oak wood"`, with its escape sequences already resolved.
+    Str(Cow<'s, str>),
+    /// An identifier applied to a tag: `name(#tag)`.
+    Call(&'s str, &'s str),
+}
+
+impl Display for AttributeValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Ident(ident) => write!(f, "{ident}"),
+            AttributeValue::Num(n) => write!(f, "{n}"),
+            AttributeValue::Float(n) => write!(f, "{n:?}"),
+            AttributeValue::Str(s) => write!(f, "\"{}\"", escape(s)),
+            AttributeValue::Call(name, tag) => write!(f, "{name}(#{tag})"),
+        }
+    }
+}
+
+impl<'s> AttributeValue<'s> {
+    pub fn as_ident(&self) -> Option<&'s str> {
+        match self {
+            AttributeValue::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// The value of integers and of floats.
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            AttributeValue::Num(n) => Some(*n as f32),
+            AttributeValue::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&AttributeValue<'_>> for Constraint {
+    type Error = ();
+
+    fn try_from(value: &AttributeValue<'_>) -> Result<Self, Self::Error> {
+        match value {
+            AttributeValue::Ident(name) => Constraint::new(name, None),
+            AttributeValue::Call(name, tag) => Constraint::new(name, Some(tag)),
+            _ => None,
+        }
+        .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command<'s> {
+    pub kind: CommandKind<'s>,
+    pub span: Span,
+}
+
+/// Parses `src` into a list of commands. Commands that could not be parsed are replaced by
+/// `CommandKind::Error` and reported in the returned errors.
+pub fn parse(src: &str) -> (Vec<Command<'_>>, Vec<BlueprintError>) {
+    parse_chunk(src, &SourceMap::new(src))
+}
+
+/// Parses `chunk`, the end of the source mapped so far by `source_map`, as [`parse`] does: the
+/// spans and lines of its commands and errors are the ones in the whole source.
+pub fn parse_chunk<'s>(
+    chunk: &'s str,
+    source_map: &SourceMap,
+) -> (Vec<Command<'s>>, Vec<BlueprintError>) {
+    let offset = source_map.len() - chunk.len();
+    let shift = |span: Span, offset: usize| Span::from(span.start + offset..span.end + offset);
+
+    let (tokens, lexer_errors) = lexer().parse(chunk).into_output_errors();
+    let tokens = tokens
+        .unwrap_or_default()
+        .into_iter()
+        .map(|token| Spanned {
+            span: shift(token.span, offset),
+            ..token
+        })
+        .collect::<Vec<_>>();
+
+    let end = source_map.len();
+    let (commands, parser_errors) = parser()
+        .parse(
+            tokens
+                .as_slice()
+                .map((end..end).into(), |t| (&t.node, &t.span)),
+        )
+        .into_output_errors();
+
+    // the spans of the parser are the ones of the tokens, already shifted
+    let errors = lexer_errors
+        .into_iter()
+        .map(|e| (e.map_token(|c| c.to_string()), offset))
+        .chain(
+            parser_errors
+                .into_iter()
+                .map(|e| (e.map_token(|tok| tok.to_string()), 0)),
+        )
+        .map(|(e, offset)| {
+            let span = shift(*e.span(), offset);
+            let (line, _) = source_map.line_col(span.start);
+            e.contexts().fold(
+                BlueprintError::error(e.to_string()).with_span(span, line),
+                |error, (label, span)| {
+                    error.with_label(format!("while parsing this {label}"), shift(*span, offset))
+                },
+            )
+        })
+        .collect();
+
+    (commands.unwrap_or_default(), errors)
+}
+
+/// Piece of a source read by [`chunks`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chunk {
+    /// Complete commands of the innermost block opened, or of the source.
+    Commands(String),
+    /// Start of a block read over several chunks, up to its `{`, with the name of its shape.
+    Open(String, Option<String>),
+    /// `}` ending the innermost block opened.
+    Close(String),
+}
+
+impl Chunk {
+    /// Text of the chunk, following the one of the previous chunk in the source.
+    pub fn source(&self) -> &str {
+        match self {
+            Chunk::Commands(source) | Chunk::Open(source, _) | Chunk::Close(source) => source,
+        }
+    }
+}
+
+/// Reads the source of `reader` a chunk at a time, so that very large sources do not have to be
+/// held in memory to be parsed. Chunks end after a command, as told by the tokens of the source,
+/// once they are at least `size` bytes long, the next one starting with the next command of the
+/// same block. The blocks still open then are opened by chunks of their own, and closed by the
+/// chunk of their `}`.
+pub fn chunks(reader: impl BufRead, size: usize) -> impl Iterator<Item = io::Result<Chunk>> {
+    Chunker {
+        reader,
+        size,
+        text: String::new(),
+        start: 0,
+        lexed: 0,
+        in_str: false,
+        end: 0,
+        chunks: VecDeque::new(),
+        blocks: Vec::new(),
+        opened: 0,
+        shape: None,
+        squares: 0,
+        previous: None,
+        ended: false,
+        done: false,
+    }
+}
+
+struct Chunker<R> {
+    reader: R,
+    size: usize,
+    /// Source read and not chunked yet, starting at the byte `start` of the source.
+    text: String,
+    start: usize,
+    /// Bytes of `text` lexed, its end being lexed once it does not end within a string literal.
+    lexed: usize,
+    in_str: bool,
+    /// End of the last token lexed, where chunks are cut so that they all hold tokens, as sources
+    /// made of whitespace and comments only do not lex.
+    end: usize,
+    /// Chunks cut, the last one being kept until the next one is cut, for the whitespace and the
+    /// comments ending the source to be added to it.
+    chunks: VecDeque<Chunk>,
+    /// Blocks started in `text`: the end of the token before their command, the end of their `{`,
+    /// and the name of their shape.
+    blocks: Vec<(usize, usize, Option<String>)>,
+    /// Number of blocks opened by a chunk and not closed yet.
+    opened: usize,
+    /// End of the token before the `shape` keyword starting the next block, and the name
+    /// following it.
+    shape: Option<(usize, Option<String>)>,
+    /// Depth of the attributes being read, within which commands do not end.
+    squares: usize,
+    /// Kind of the last token lexed, and whether it may end a command.
+    previous: Option<&'static str>,
+    ended: bool,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for Chunker<R> {
+    type Item = io::Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.chunks.len() > 1 || self.done {
+                return self.chunks.pop_front().map(Ok);
+            }
+            let len = self.text.len();
+            match self.reader.read_line(&mut self.text) {
+                Ok(0) => {
+                    self.done = true;
+                    if self.text.is_empty() {
+                        continue;
+                    }
+                    let text = std::mem::take(&mut self.text);
+                    match self.chunks.back_mut() {
+                        Some(
+                            Chunk::Commands(source) | Chunk::Open(source, _) | Chunk::Close(source),
+                        ) if self.end == self.start && !self.in_str => source.push_str(&text),
+                        _ => self.chunks.push_back(Chunk::Commands(text)),
+                    }
+                }
+                Ok(_) => {
+                    self.in_str = ends_in_str(&self.text[len..], self.in_str);
+                    if !self.in_str {
+                        self.lex();
+                    }
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl<R> Chunker<R> {
+    /// Lexes the end of `text` not lexed yet, cutting chunks at the commands starting once
+    /// `text` is large enough.
+    fn lex(&mut self) {
+        let offset = self.start + self.lexed;
+        let source = self.text[self.lexed..].to_string();
+        let (tokens, _) = lexer().parse(&source).into_output_errors();
+        for token in tokens.unwrap_or_default() {
+            self.token(&token.node, offset + token.span.end);
+        }
+        self.lexed = self.text.len();
+    }
+
+    /// Reads the token ending at the byte `end` of the source.
+    fn token(&mut self, token: &Token, end: usize) {
+        let starts = matches!(
+            token,
+            Token::Move
+                | Token::Include
+                | Token::Shape
+                | Token::OpenCurly
+                | Token::OpenSquare
+                | Token::At
+                | Token::Num(_)
+        );
+        if self.squares == 0 && self.ended && starts && self.end - self.start >= self.size {
+            self.cut();
+        }
+
+        match token {
+            Token::Shape => self.shape = Some((self.end, None)),
+            Token::Ident(name) if self.previous == Some("shape") => {
+                if let Some((_, shape)) = &mut self.shape {
+                    *shape = Some(name.to_string());
+                }
+            }
+            Token::OpenCurly => {
+                let (after, name) = self.shape.take().unwrap_or((self.end, None));
+                self.blocks.push((after, end, name));
+            }
+            Token::CloseCurly => self.close(end),
+            Token::OpenSquare => self.squares += 1,
+            Token::CloseSquare => self.squares = self.squares.saturating_sub(1),
+            _ => {}
+        }
+        if !matches!(token, Token::Shape | Token::Ident(_) | Token::OpenCurly) {
+            self.shape = None;
+        }
+        // commands end with a number, a tag, a path, a `}` or a `;`, or with the `y` of a tagged
+        // point, and the ones of a block start after its `{`
+        self.ended = match token {
+            Token::Num(_)
+            | Token::Float(_)
+            | Token::Tag(_)
+            | Token::Str(_)
+            | Token::OpenCurly
+            | Token::CloseCurly
+            | Token::Semicolon => true,
+            Token::Ident(_) => self.previous == Some("dot"),
+            _ => false,
+        };
+        self.previous = Some(token.kind());
+        self.end = end;
+    }
+
+    /// Chunks `text` up to the last token, opening the blocks started before it.
+    fn cut(&mut self) {
+        let mut from = self.start;
+        for (after, brace, name) in std::mem::take(&mut self.blocks) {
+            self.push(Chunk::Commands(self.text(from, after)));
+            self.push(Chunk::Open(self.text(after, brace), name));
+            self.opened += 1;
+            from = brace;
+        }
+        self.push(Chunk::Commands(self.text(from, self.end)));
+        self.consume(self.end);
+    }
+
+    /// Ends the innermost block with the `}` ending at the byte `end` of the source. Blocks
+    /// opened by a chunk are closed by chunking `text` up to it.
+    fn close(&mut self, end: usize) {
+        if self.blocks.pop().is_some() || self.opened == 0 {
+            return;
+        }
+        self.push(Chunk::Commands(self.text(self.start, self.end)));
+        self.push(Chunk::Close(self.text(self.end, end)));
+        self.opened -= 1;
+        self.consume(end);
+    }
+
+    /// Text from the byte `start` to the byte `end` of the source.
+    fn text(&self, start: usize, end: usize) -> String {
+        self.text[start - self.start..end - self.start].to_string()
+    }
+
+    fn push(&mut self, chunk: Chunk) {
+        if !chunk.source().is_empty() {
+            self.chunks.push_back(chunk);
+        }
+    }
+
+    /// Drops the text before the byte `at` of the source, chunked.
+    fn consume(&mut self, at: usize) {
+        self.text.drain(..at - self.start);
+        self.start = at;
+    }
+}
+
+/// Whether `line` ends within a string literal, `in_str` telling whether it starts within one. The
+/// quotes of comments do not start string literals.
+fn ends_in_str(line: &str, mut in_str: bool) -> bool {
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_str = !in_str,
+            '\\' if in_str => {
+                chars.next();
+            }
+            '/' if !in_str && chars.peek() == Some(&'/') => break,
+            _ => {}
+        }
+    }
+    in_str
+}
+
+fn parser<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Vec<Command<'src>>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    let command = command();
+
+    command
+        .clone()
+        .recover_with(via_parser(invalid_command(command, true)))
+        .padded_by(just(Token::Semicolon).repeated())
+        .repeated()
+        .collect::<Vec<_>>()
+}
+
+fn command<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    // { command { command .... } ... }
+    recursive(|command| {
+        let name = select! {
+            Token::Ident(name) => name,
+        }
+        .labelled("shape name");
+
+        let block = just(Token::Shape)
+            .ignore_then(name)
+            .or_not()
+            .then(
+                command
+                    .clone()
+                    .recover_with(via_parser(invalid_command(command, false)))
+                    .padded_by(just(Token::Semicolon).repeated())
+                    .repeated()
+                    .collect::<Vec<_>>()
+                    .delimited_by(just(Token::OpenCurly), just(Token::CloseCurly)),
+            )
+            .map_with(|(name, c), e| Command {
+                kind: CommandKind::Nested(name, c),
+                span: e.span(),
+            });
+
+        choice((move_command(), draw_command(), include_command(), block))
+    })
+}
+
+/// Recovery parser used when `command` fails: skips the offending token and every following one
+/// until either a valid command, a `;` or a `}` is found, and yields a `CommandKind::Error` in place
+/// of the skipped tokens. A `}` is only skipped when it is the offending token and `top_level` is
+/// set, as it would otherwise close the enclosing block.
+fn invalid_command<'tokens, 'src: 'tokens, I, P>(
+    command: P,
+    top_level: bool,
+) -> impl Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+    P: Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone,
+{
+    let sync = just(Token::CloseCurly)
+        .or(just(Token::Semicolon))
+        .ignored()
+        .or(command.ignored());
+
+    any()
+        .filter(move |t: &Token| top_level || *t != Token::CloseCurly)
+        .then(any().and_is(sync.not()).repeated())
+        .map_with(|_, e| Command {
+            kind: CommandKind::Error,
+            span: e.span(),
+        })
+}
+
+fn move_command<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    just(Token::Move)
+        .ignore_then(coord())
+        .map_with(|coord, e| Command {
+            kind: CommandKind::Move(coord.node),
+            span: e.span(),
+        })
+}
+
+fn include_command<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    let path = select! {
+        Token::Str(s) => s,
+    }
+    .labelled("path");
+
+    just(Token::Include)
+        .ignore_then(path)
+        .map_with(|path, e| Command {
+            kind: CommandKind::Include(path),
+            span: e.span(),
+        })
+}
+
+fn draw_command<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Command<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    edge_attributes()
+        .or_not()
+        .then(coord())
+        .validate(|(attrs, coord), _extra, emitter| {
+            let mut attrs = attrs.unwrap_or_default();
+
+            let color = match attrs.remove("color") {
+                None => Color::default(),
+                Some(Attribute { value: color, .. }) => match &color.node {
+                    AttributeValue::Ident(name) if let Ok(color) = Color::try_from(*name) => color,
+                    _ => {
+                        emitter.emit(Rich::custom(
+                            color.span,
+                            format!("`{color}` is not a known color.", color = color.node),
+                        ));
+                        Color::default()
+                    }
+                },
+            };
+
+            if let Some(width) = attrs.get("width") {
+                let message = match width.value.node.as_number() {
+                    Some(n) if n > 0. => None,
+                    Some(_) => Some("is not a positive number"),
+                    None => Some("is not a number"),
+                };
+                if let Some(message) = message {
+                    emitter.emit(Rich::custom(
+                        width.value.span,
+                        format!("`{}` {message}.", width.value.node),
+                    ));
+                    attrs.remove("width");
+                }
+            }
+
+            if let Some(style) = attrs.get("style")
+                && style
+                    .value
+                    .node
+                    .as_ident()
+                    .is_none_or(|name| LineStyle::try_from(name).is_err())
+            {
+                emitter.emit(Rich::custom(
+                    style.value.span,
+                    format!("`{}` is not a known style.", style.value.node),
+                ));
+                attrs.remove("style");
+            }
+
+            if let Some(layer) = attrs.get("layer")
+                && layer.value.node.as_ident().is_none()
+            {
+                emitter.emit(Rich::custom(
+                    layer.value.span,
+                    format!("`{}` is not a layer name.", layer.value.node),
+                ));
+                attrs.remove("layer");
+            }
+
+            if let Some(constraint) = attrs.get("constraint")
+                && Constraint::try_from(&constraint.value.node).is_err()
+            {
+                emitter.emit(Rich::custom(
+                    constraint.value.span,
+                    format!("`{}` is not a known constraint.", constraint.value.node),
+                ));
+                attrs.remove("constraint");
+            }
+
+            let mut attributes = attrs.into_values().collect::<Vec<_>>();
+            attributes.sort_by_key(|attribute| attribute.key.span.start);
+
+            Command {
+                kind: CommandKind::Draw(coord.node, color, attributes),
+                span: coord.span,
+            }
+        })
+}
+
+/// Keys of the attributes having a meaning, other ones are reported as unknown.
+pub const KNOWN_ATTRIBUTES: [&str; 5] = ["color", "width", "style", "layer", "constraint"];
+
+/// Attributes, by key.
+type Attributes<'src> = HashMap<&'src str, Attribute<'src>>;
+
+/// Parses a potentially empty list of key/value pairs of the following form:
+/// `[ key : value , ... ]`, where values are identifiers, signed integers or floats, strings or
+/// identifiers applied to a tag, e.g. `perpendicular(#a)`. A training comma is allowed.
+fn edge_attributes<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Attributes<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    let ident = select! {
+        Token::Ident(t) => t,
+    }
+    .labelled("ident");
+
+    let tag = select! {
+        Token::Tag(t) => t,
+    }
+    .labelled("tag");
+
+    let call = ident
+        .then(tag.delimited_by(just(Token::OpenParen), just(Token::CloseParen)))
+        .map(|(name, tag)| AttributeValue::Call(name, tag));
+
+    let number = select! {
+        Token::Num(n) => AttributeValue::Num(n),
+        Token::Float(n) => AttributeValue::Float(n),
+    }
+    .labelled("number");
+
+    let signed = just(Token::Minus)
+        .ignore_then(number)
+        .map(|number| match number {
+            AttributeValue::Num(n) => AttributeValue::Num(-n),
+            AttributeValue::Float(n) => AttributeValue::Float(-n),
+            value => value,
+        })
+        .or(just(Token::Plus).or_not().ignore_then(number));
+
+    let value = call
+        .or(select! {
+            Token::Ident(t) => AttributeValue::Ident(t),
+            Token::Str(s) => AttributeValue::Str(s),
+        })
+        .or(signed)
+        .labelled("value");
+
+    let edge_attr = ident
+        .map_with(|i, e| Spanned {
+            node: i,
+            span: e.span(),
+        })
+        .then_ignore(just(Token::Colon))
+        .then(value.map_with(|v, e| Spanned {
+            node: v,
+            span: e.span(),
+        }))
+        .map(|(key, value)| (key.node, Attribute { key, value }));
+
+    let edge_attrs = edge_attr
+        .separated_by(just(Token::Comma))
+        .allow_trailing()
+        .collect::<HashMap<_, _>>();
+
+    edge_attrs.delimited_by(just(Token::OpenSquare), just(Token::CloseSquare))
+}
+
+/// Parses any of the following:
+///  * `x,y` optionally followed by `#tag` into `Coord::Relative(x, y, "tag")`
+///  * `@x,y` optionally followed by `#tag` into `Coord::Absolute(x, y, "tag")`
+///  * `@#tag` into `Coord::Reference("tag")`
+///
+/// Where `x` and `y` are expressions, as parsed by `expr()`.
+fn coord<'tokens, 'src: 'tokens, I>()
+-> impl Parser<'tokens, I, Spanned<Coord<'src>>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    let tag = select! {
+        Token::Tag(t) => t,
+    }
+    .labelled("tag");
+    // a tag followed by a `.` is the beginning of the next coordinate, such as in `#p0.x,5`
+    let trailing_tag = tag.then_ignore(just(Token::Dot).not());
+
+    let expr_pair = expr(false).then_ignore(just(Token::Comma)).then(expr(true));
+    let coord_rel = expr_pair
+        .clone()
+        .then(trailing_tag.clone().or_not())
+        .map(|((x, y), t)| Coord::Relative(x, y, t));
+    let coord_abs = just(Token::At)
+        .ignore_then(expr_pair)
+        .then(trailing_tag.or_not())
+        .map(|((x, y), t)| Coord::Absolute(x, y, t));
+    let coord_ref = just(Token::At).ignore_then(tag).map(Coord::Reference);
+
+    choice((coord_rel, coord_abs, coord_ref)).map_with(|c, e| Spanned {
+        node: c,
+        span: e.span(),
+    })
+}
+
+/// Parses additions and subtractions of numbers and tagged point components (`#tag.x` or
+/// `#tag.y`), each of them optionally negated, such as `#p0.x + 10` or `-5`.
+///
+/// When `last` is set, the expression is the last one of a coordinate and an operand followed by
+/// a `,` is not part of it, but the `x` of the next coordinate: `0,5 -5,0` is two coordinates.
+fn expr<'tokens, 'src: 'tokens, I>(
+    last: bool,
+) -> impl Parser<'tokens, I, Expr<'src>, extra::Err<Rich<'tokens, Token<'src>, Span>>> + Clone
+where
+    I: ValueInput<'tokens, Token = Token<'src>, Span = Span>,
+{
+    let num = select! {
+        Token::Num(n) => Expr::Num(n),
+    }
+    .labelled("number");
+    let tag = select! {
+        Token::Tag(t) => t,
+    }
+    .labelled("tag");
+    let component = select! {
+        Token::Ident("x") => Expr::X as fn(&'src str) -> Expr<'src>,
+        Token::Ident("y") => Expr::Y as fn(&'src str) -> Expr<'src>,
+    }
+    .labelled("`x` or `y`");
+
+    let tag_component = tag
+        .then_ignore(just(Token::Dot))
+        .then(component)
+        .map(|(tag, component)| component(tag));
+
+    let atom = just(Token::Minus)
+        .repeated()
+        .foldr(num.or(tag_component), |_, e| Expr::Neg(Box::new(e)));
+
+    let operand = if last {
+        atom.clone().then_ignore(just(Token::Comma).not()).boxed()
+    } else {
+        atom.clone().boxed()
+    };
+
+    atom.foldl(
+        just(Token::Plus)
+            .to(Expr::Add as fn(_, _) -> _)
+            .or(just(Token::Minus).to(Expr::Sub as fn(_, _) -> _))
+            .then(operand)
+            .repeated(),
+        |a, (op, b)| op(Box::new(a), Box::new(b)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser() {
+        let src = "{ move @0,0 #p0 0,5 5,5 5,0 [color:blue] @#p0 }";
+        let tokens = lexer().parse(src).unwrap();
+        let res = parser()
+            .parse(
+                tokens
+                    .as_slice()
+                    .map((src.len()..src.len()).into(), |t| (&t.node, &t.span)),
+            )
+            .unwrap();
+        assert_eq!(
+            res,
+            vec![Command {
+                kind: CommandKind::Nested(
+                    None,
+                    vec![
+                        Command {
+                            kind: CommandKind::Move(Coord::Absolute(
+                                Expr::Num(0),
+                                Expr::Num(0),
+                                Some("p0")
+                            )),
+                            span: Span::from(2..15),
+                        },
+                        Command {
+                            kind: CommandKind::Draw(
+                                Coord::Relative(Expr::Num(0), Expr::Num(5), None),
+                                Color::Black,
+                                vec![]
+                            ),
+                            span: Span::from(16..19),
+                        },
+                        Command {
+                            kind: CommandKind::Draw(
+                                Coord::Relative(Expr::Num(5), Expr::Num(5), None),
+                                Color::Black,
+                                vec![]
+                            ),
+                            span: Span::from(20..23),
+                        },
+                        Command {
+                            kind: CommandKind::Draw(
+                                Coord::Relative(Expr::Num(5), Expr::Num(0), None),
+                                Color::Black,
+                                vec![]
+                            ),
+                            span: Span::from(24..27),
+                        },
+                        Command {
+                            kind: CommandKind::Draw(Coord::Reference("p0"), Color::Blue, vec![]),
+                            span: Span::from(41..45),
+                        },
+                    ]
+                ),
+                span: Span::from(0..47),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parser_attribute_values() {
+        let (commands, errors) =
+            parse(r#"[width:0.5, count:3, label:"oak \"wood\"", style:dotted] 1,0"#);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        let CommandKind::Draw(_, _, attributes) = &commands[0].kind else {
+            panic!("not an edge: {commands:?}");
+        };
+        let values = attributes
+            .iter()
+            .map(|attribute| &attribute.value.node)
+            .collect::<Vec<_>>();
+        assert_eq!(values[0].as_number(), Some(0.5));
+        assert_eq!(values[1].as_number(), Some(3.));
+        assert_eq!(values[2].as_str(), Some("oak \"wood\""));
+        assert_eq!(values[2].to_string(), r#""oak \"wood\"""#);
+        assert_eq!(values[3].as_ident(), Some("dotted"));
+        assert_eq!(values[3].as_number(), None);
+
+        let (_, errors) = parse("[width:0.0] 1,0");
+        assert_eq!(errors.len(), 1);
+
+        let (commands, errors) = parse("[offset:-2.5, shift:+3, depth:-4, width:-1] 1,0");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "`-1` is not a positive number.");
+        let CommandKind::Draw(_, _, attributes) = &commands[0].kind else {
+            panic!("not an edge: {commands:?}");
+        };
+        assert_eq!(
+            attributes
+                .iter()
+                .map(|attribute| attribute.value.node.as_number())
+                .collect::<Vec<_>>(),
+            [Some(-2.5), Some(3.), Some(-4.)]
+        );
+
+        let (_, errors) = parse("[width:thick] 1,0");
+        assert_eq!(errors[0].message, "`thick` is not a number.");
+        assert_eq!(errors[0].span, Some(Span::from(7..12)));
+        let (_, errors) = parse("[offset:-x] 1,0");
+        assert_eq!(errors[0].span, Some(Span::from(9..10)));
+        assert!(errors[0].message.contains("expected number"), "{errors:?}");
+    }
+
+    #[test]
+    fn test_parser_shape() {
+        let (commands, errors) = parse("shape kitchen { 10,0 } shape { 0,5 }");
+
+        assert_eq!(
+            commands,
+            vec![
+                Command {
+                    kind: CommandKind::Nested(
+                        Some("kitchen"),
+                        vec![Command {
+                            kind: CommandKind::Draw(
+                                Coord::Relative(Expr::Num(10), Expr::Num(0), None),
+                                Color::Black,
+                                vec![]
+                            ),
+                            span: Span::from(16..20),
+                        }]
+                    ),
+                    span: Span::from(0..22),
+                },
+                // a shape without name is reported, its block still being drawn
+                Command {
+                    kind: CommandKind::Error,
+                    span: Span::from(23..28),
+                },
+                Command {
+                    kind: CommandKind::Nested(
+                        None,
+                        vec![Command {
+                            kind: CommandKind::Draw(
+                                Coord::Relative(Expr::Num(0), Expr::Num(5), None),
+                                Color::Black,
+                                vec![]
+                            ),
+                            span: Span::from(31..34),
+                        }]
+                    ),
+                    span: Span::from(29..36),
+                }
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_parser_recovery() {
+        let src = "{ 0,5 ] 5,5 } } { move @0,0 5 0,5";
+        let tokens = lexer().parse(src).unwrap();
+        let (res, errors) = parser()
+            .parse(
+                tokens
+                    .as_slice()
+                    .map((src.len()..src.len()).into(), |t| (&t.node, &t.span)),
+            )
+            .into_output_errors();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            res,
+            Some(vec![
+                Command {
+                    kind: CommandKind::Nested(
+                        None,
+                        vec![
+                            Command {
+                                kind: CommandKind::Draw(
+                                    Coord::Relative(Expr::Num(0), Expr::Num(5), None),
+                                    Color::Black,
+                                    vec![]
+                                ),
+                                span: Span::from(2..5),
+                            },
+                            Command {
+                                kind: CommandKind::Error,
+                                span: Span::from(6..7),
+                            },
+                            Command {
+                                kind: CommandKind::Draw(
+                                    Coord::Relative(Expr::Num(5), Expr::Num(5), None),
+                                    Color::Black,
+                                    vec![]
+                                ),
+                                span: Span::from(8..11),
+                            },
+                        ]
+                    ),
+                    span: Span::from(0..13),
+                },
+                Command {
+                    kind: CommandKind::Error,
+                    span: Span::from(14..17),
+                },
+                Command {
+                    kind: CommandKind::Move(Coord::Absolute(Expr::Num(0), Expr::Num(0), None)),
+                    span: Span::from(18..27),
+                },
+                Command {
+                    kind: CommandKind::Error,
+                    span: Span::from(28..29),
+                },
+                Command {
+                    kind: CommandKind::Draw(
+                        Coord::Relative(Expr::Num(0), Expr::Num(5), None),
+                        Color::Black,
+                        vec![]
+                    ),
+                    span: Span::from(30..33),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parser_separators() {
+        let (commands, errors) = parse("{ 0,5; 5 5;; 5,0; }; move @0,0");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            commands,
+            vec![
+                Command {
+                    kind: CommandKind::Nested(
+                        None,
+                        vec![
+                            Command {
+                                kind: CommandKind::Draw(
+                                    Coord::Relative(Expr::Num(0), Expr::Num(5), None),
+                                    Color::Black,
+                                    vec![]
+                                ),
+                                span: Span::from(2..5),
+                            },
+                            // the recovery stops at the `;`
+                            Command {
+                                kind: CommandKind::Error,
+                                span: Span::from(7..10),
+                            },
+                            Command {
+                                kind: CommandKind::Draw(
+                                    Coord::Relative(Expr::Num(5), Expr::Num(0), None),
+                                    Color::Black,
+                                    vec![]
+                                ),
+                                span: Span::from(13..16),
+                            },
+                        ]
+                    ),
+                    span: Span::from(0..19),
+                },
+                Command {
+                    kind: CommandKind::Move(Coord::Absolute(Expr::Num(0), Expr::Num(0), None)),
+                    span: Span::from(21..30),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_expressions() {
+        let src = "5,-2 #a #a.x + 1 - -2, 3 move @#a.x,#b.y - 1 -1,0";
+        let tokens = lexer().parse(src).unwrap();
+        let res = parser()
+            .parse(
+                tokens
+                    .as_slice()
+                    .map((src.len()..src.len()).into(), |t| (&t.node, &t.span)),
+            )
+            .unwrap();
+        assert_eq!(
+            res,
+            vec![
+                Command {
+                    kind: CommandKind::Draw(
+                        Coord::Relative(Expr::Num(5), Expr::Neg(Box::new(Expr::Num(2))), Some("a")),
+                        Color::Black,
+                        vec![]
+                    ),
+                    span: Span::from(0..7),
+                },
+                Command {
+                    kind: CommandKind::Draw(
+                        Coord::Relative(
+                            Expr::Sub(
+                                Box::new(Expr::Add(Box::new(Expr::X("a")), Box::new(Expr::Num(1)))),
+                                Box::new(Expr::Neg(Box::new(Expr::Num(2))))
+                            ),
+                            Expr::Num(3),
+                            None
+                        ),
+                        Color::Black,
+                        vec![]
+                    ),
+                    span: Span::from(8..24),
+                },
+                Command {
+                    kind: CommandKind::Move(Coord::Absolute(
+                        Expr::X("a"),
+                        Expr::Sub(Box::new(Expr::Y("b")), Box::new(Expr::Num(1))),
+                        None
+                    )),
+                    span: Span::from(25..44),
+                },
+                Command {
+                    kind: CommandKind::Draw(
+                        Coord::Relative(Expr::Neg(Box::new(Expr::Num(1))), Expr::Num(0), None),
+                        Color::Black,
+                        vec![]
+                    ),
+                    span: Span::from(45..49),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunks() {
+        let chunks = |src: &str, size| {
+            chunks(src.as_bytes(), size)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        };
+
+        // commands on their own lines, or on the same line
+        assert_eq!(
+            chunks("move @0,0\n10,0 #a\n@#a 0,5;", 1),
+            vec![
+                Chunk::Commands("move @0,0".to_string()),
+                Chunk::Commands("\n10,0 #a".to_string()),
+                Chunk::Commands("\n@#a".to_string()),
+                Chunk::Commands(" 0,5;".to_string()),
+            ]
+        );
+        // commands spread over several lines are kept whole, as are small blocks
+        assert_eq!(
+            chunks("[color:red]\n10,\n5 { 1,0 2,0 } 3,0\n", 10),
+            vec![
+                Chunk::Commands("[color:red]\n10,\n5".to_string()),
+                Chunk::Commands(" { 1,0 2,0 }".to_string()),
+                Chunk::Commands(" 3,0\n".to_string()),
+            ]
+        );
+        // a source wrapped in a block, with a string literal over two lines
+        assert_eq!(
+            chunks("shape s {\n  [label:\"a\nb\"] 1,0\n  2,0\n} // end\n", 1),
+            vec![
+                Chunk::Open("shape s {".to_string(), Some("s".to_string())),
+                Chunk::Commands("\n  [label:\"a\nb\"] 1,0".to_string()),
+                Chunk::Commands("\n  2,0".to_string()),
+                Chunk::Close("\n} // end\n".to_string()),
+            ]
+        );
+    }
+}
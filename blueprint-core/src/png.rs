@@ -0,0 +1,62 @@
+use crate::image::{ImageEncoder, Raster, bands};
+use std::io;
+use std::io::{BufWriter, Write};
+
+/// RGBA PNG image of a canvas, keeping the transparency of its pixels.
+pub struct PngImage<'c> {
+    raster: &'c dyn Raster,
+}
+
+impl ImageEncoder for PngImage<'_> {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(writer),
+            self.raster.width() as u32,
+            self.raster.height() as u32,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+        for rows in bands(self.raster) {
+            let band = self.raster.band(rows.clone());
+            for y in rows {
+                for x in 0..band.width {
+                    let (r, g, b, a) = band.get(x, y).as_rgba();
+                    stream.write_all(&[r, g, b, a])?;
+                }
+            }
+        }
+        stream.finish()?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+impl<'c, R: Raster> From<&'c R> for PngImage<'c> {
+    fn from(value: &'c R) -> Self {
+        Self { raster: value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::domain::Color;
+
+    #[test]
+    fn test_write_to() {
+        let mut canvas = Canvas::new(2, 1).with_background(Color::Transparent);
+        canvas.set(1, 0, Color::Red);
+
+        let mut png = Vec::new();
+        PngImage::from(&canvas).write_to(&mut png).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, [0, 0, 0, 0, 255, 0, 0, 255]);
+    }
+}
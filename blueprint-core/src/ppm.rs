@@ -0,0 +1,231 @@
+use crate::canvas::Canvas;
+use crate::image::{ImageEncoder, Raster, bands};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::ops::Range;
+
+pub struct PpmImage<'c> {
+    raster: &'c dyn Raster,
+    binary: bool,
+}
+
+impl PpmImage<'_> {
+    /// Whether the image is written in the binary (P6) variant of the format, which is the
+    /// default, or in the ASCII (P3) one.
+    pub fn binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    fn reader(&self) -> PpmImageReader<'_> {
+        PpmImageReader::new(self)
+    }
+}
+
+impl ImageEncoder for PpmImage<'_> {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(writer);
+        if self.binary {
+            write!(
+                writer,
+                "P6\n{} {}\n255\n",
+                self.raster.width(),
+                self.raster.height()
+            )?;
+            for rows in bands(self.raster) {
+                let band = self.raster.band(rows.clone());
+                for y in rows {
+                    for x in 0..band.width {
+                        let (r, g, b, _) = band.get(x, y).as_rgba();
+                        writer.write_all(&[r, g, b])?;
+                    }
+                }
+            }
+        } else {
+            io::copy(&mut self.reader(), &mut writer)?;
+        }
+        writer.flush()
+    }
+}
+
+impl<'c, R: Raster> From<&'c R> for PpmImage<'c> {
+    fn from(value: &'c R) -> Self {
+        Self {
+            raster: value,
+            binary: true,
+        }
+    }
+}
+
+/// Formats the image in the ASCII (P3) variant of the format, whatever `binary` is.
+impl Display for PpmImage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "P3")?;
+        writeln!(f, "{} {}", self.raster.width(), self.raster.height())?;
+        writeln!(f, "255")?;
+
+        for rows in bands(self.raster) {
+            let band = self.raster.band(rows.clone());
+            for y in rows {
+                for x in 0..band.width {
+                    let (r, g, b, _) = band.get(x, y).as_rgba();
+                    write!(f, "{r} {g} {b} ",)?
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct PpmImageReader<'c> {
+    image: &'c PpmImage<'c>,
+    /// Bands of rows not read yet.
+    bands: Box<dyn Iterator<Item = Range<usize>>>,
+    /// Band holding the row `y`.
+    band: Option<Cow<'c, Canvas>>,
+    /// Next pixel to format.
+    x: usize,
+    y: usize,
+    /// Formatted text, read from `pos` on.
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<'c> PpmImageReader<'c> {
+    /// Size from which the formatted text is handed out, rather than formatting more pixels.
+    const CAP: usize = 64 * 1024;
+
+    fn new(image: &'c PpmImage) -> Self {
+        let mut buf = Vec::with_capacity(Self::CAP);
+        write!(
+            &mut buf,
+            "P3\n{} {}\n255\n",
+            image.raster.width(),
+            image.raster.height()
+        )
+        .expect("writing to a Vec never fails");
+        Self {
+            image,
+            bands: Box::new(bands(image.raster)),
+            band: None,
+            x: 0,
+            y: 0,
+            buf,
+            pos: 0,
+        }
+    }
+
+    /// Formats pixels until `CAP` bytes are buffered or the image is fully formatted.
+    fn fill(&mut self) {
+        let (width, height) = (self.image.raster.width(), self.image.raster.height());
+
+        while self.buf.len() < Self::CAP && self.y < height {
+            if !self
+                .band
+                .as_ref()
+                .is_some_and(|band| band.rows().contains(&self.y))
+            {
+                let rows = self.bands.next().expect("rows left to read are in a band");
+                self.band = Some(self.image.raster.band(rows));
+            }
+            let band = self.band.as_ref().expect("band was just read");
+
+            let (r, g, b, _) = band.get(self.x, self.y).as_rgba();
+            let separator = if self.x + 1 < width { ' ' } else { '\n' };
+            write!(&mut self.buf, "{r} {g} {b}{separator}").expect("writing to a Vec never fails");
+
+            self.x += 1;
+            if self.x == width {
+                self.x = 0;
+                self.y += 1;
+            }
+        }
+    }
+}
+
+impl Read for PpmImageReader<'_> {
+    /// Fills `buf` as much as possible, formatting pixels whenever the buffered text is exhausted.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            if self.pos == self.buf.len() {
+                self.buf.clear();
+                self.pos = 0;
+                self.fill();
+                if self.buf.is_empty() {
+                    break;
+                }
+            }
+
+            let to_copy = (buf.len() - read).min(self.buf.len() - self.pos);
+            buf[read..read + to_copy].copy_from_slice(&self.buf[self.pos..self.pos + to_copy]);
+            self.pos += to_copy;
+            read += to_copy;
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Color;
+
+    #[test]
+    fn test_write_to() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set(1, 0, Color::Red);
+
+        let mut binary = Vec::new();
+        PpmImage::from(&canvas).write_to(&mut binary).unwrap();
+        assert_eq!(binary, b"P6\n2 1\n255\n\xff\xff\xff\xff\x00\x00");
+
+        let mut ascii = Vec::new();
+        PpmImage::from(&canvas)
+            .binary(false)
+            .write_to(&mut ascii)
+            .unwrap();
+        assert_eq!(ascii, b"P3\n2 1\n255\n255 255 255 255 0 0\n");
+    }
+
+    #[test]
+    fn test_reader() {
+        let (width, height) = (300, 200);
+        let mut canvas = Canvas::new(width, height);
+        for i in 0..height {
+            canvas.set(i, i, Color::Custom((i as u8, 7, 42, 255)));
+        }
+        let image = PpmImage::from(&canvas);
+
+        let mut expected = format!("P3\n{width} {height}\n255\n");
+        for y in 0..height {
+            let row = (0..width)
+                .map(|x| {
+                    let (r, g, b, _) = canvas.get(x, y).as_rgba();
+                    format!("{r} {g} {b}")
+                })
+                .collect::<Vec<_>>();
+            expected.push_str(&row.join(" "));
+            expected.push('\n');
+        }
+
+        // whatever the size of the buffers read into, the whole image is read
+        for size in [1, 7, 4096, 1 << 20] {
+            let mut reader = image.reader();
+            let mut read = Vec::new();
+            let mut buf = vec![0; size];
+            loop {
+                match reader.read(&mut buf).unwrap() {
+                    0 => break,
+                    n => read.extend_from_slice(&buf[..n]),
+                }
+            }
+            assert_eq!(String::from_utf8(read).unwrap(), expected, "size {size}");
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::domain::{Blueprint, Bound, Color, Coordinate, Curve, LineStyle, Point};
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// PostScript points per millimeter.
+const POINTS_PER_MM: f32 = 72. / 25.4;
+
+/// Size of the pages documents are printed on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Paper {
+    #[default]
+    A4,
+    A3,
+    Letter,
+    Legal,
+}
+
+impl Paper {
+    pub const ALL: [Paper; 4] = [Paper::A4, Paper::A3, Paper::Letter, Paper::Legal];
+
+    /// Width and height of the page, in portrait orientation, in millimeters.
+    pub fn size(&self) -> (f32, f32) {
+        match self {
+            Paper::A4 => (210., 297.),
+            Paper::A3 => (297., 420.),
+            Paper::Letter => (215.9, 279.4),
+            Paper::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+impl Display for Paper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Paper::A4 => write!(f, "a4"),
+            Paper::A3 => write!(f, "a3"),
+            Paper::Letter => write!(f, "letter"),
+            Paper::Legal => write!(f, "legal"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Paper {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Paper::ALL
+            .into_iter()
+            .find(|paper| paper.to_string() == value.to_lowercase())
+            .ok_or(())
+    }
+}
+
+/// PostScript document of a blueprint, drawn at the center of a single page, for printers.
+/// Transparent edges are not drawn.
+pub struct PostScriptDocument<'b, T: Coordinate = f32> {
+    blueprint: &'b Blueprint<T>,
+    scale: f32,
+    paper: Paper,
+}
+
+impl<T: Coordinate> PostScriptDocument<'_, T> {
+    /// Millimeters per blueprint unit.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn paper(mut self, paper: Paper) -> Self {
+        self.paper = paper;
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<'b, T: Coordinate> From<&'b Blueprint<T>> for PostScriptDocument<'b, T> {
+    fn from(value: &'b Blueprint<T>) -> Self {
+        Self {
+            blueprint: value,
+            scale: 1.,
+            paper: Paper::default(),
+        }
+    }
+}
+
+/// State of the graphics of the document, to only set the parts changing from an edge to the next.
+#[derive(PartialEq)]
+struct Pen {
+    color: Color,
+    width: f32,
+    style: LineStyle,
+}
+
+impl<T: Coordinate> Display for PostScriptDocument<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (width, height) = self.paper.size();
+        let (width, height) = (width * POINTS_PER_MM, height * POINTS_PER_MM);
+        // the y axis of PostScript points up, from the bottom of the page, while the one of
+        // blueprints points down
+        let (min, max) = self.blueprint.boundaries().unwrap_or_default();
+        let two = T::from_f32(2.);
+        let (center_x, center_y) = ((min.x + max.x) / two, (min.y + max.y) / two);
+        let points = self.scale * POINTS_PER_MM;
+        let factor = T::from_decimal(points);
+        let coordinates = |point: Point<T>| {
+            format!(
+                "{:.2} {:.2}",
+                width / 2. + ((point.x - center_x) * factor).to_f32(),
+                height / 2. - ((point.y - center_y) * factor).to_f32()
+            )
+        };
+
+        writeln!(f, "%!PS-Adobe-3.0")?;
+        writeln!(f, "%%BoundingBox: 0 0 {} {}", width.round(), height.round())?;
+        writeln!(f, "%%Pages: 1")?;
+        writeln!(f, "%%EndComments")?;
+        writeln!(f, "%%Page: 1 1")?;
+        writeln!(f, "<< /PageSize [{width:.2} {height:.2}] >> setpagedevice")?;
+        writeln!(f, "1 setlinecap 1 setlinejoin")?;
+
+        let mut pen = None;
+        for edge in self.blueprint.edges_iter() {
+            if edge.color.is_transparent() {
+                continue;
+            }
+
+            let edge_pen = Pen {
+                color: edge.color,
+                width: edge.width,
+                style: edge.style,
+            };
+            let previous = pen.as_ref();
+            if previous.map(|pen: &Pen| pen.color) != Some(edge.color) {
+                let (r, g, b, _) = edge.color.as_rgba();
+                writeln!(
+                    f,
+                    "{:.3} {:.3} {:.3} setrgbcolor",
+                    r as f32 / 255.,
+                    g as f32 / 255.,
+                    b as f32 / 255.
+                )?;
+            }
+            if previous.map(|pen| pen.width) != Some(edge.width) {
+                writeln!(f, "{:.2} setlinewidth", edge.width * points)?;
+            }
+            if edge.style != LineStyle::Solid {
+                let [dash, gap] = edge
+                    .style
+                    .pattern()
+                    .map(|segment| segment * edge.width * points);
+                writeln!(
+                    f,
+                    "[{dash:.2} {gap:.2}] {:.2} setdash",
+                    edge.dash_offset * points
+                )?;
+            } else if previous.is_some_and(|pen| pen.style != LineStyle::Solid) {
+                writeln!(f, "[] 0 setdash")?;
+            }
+            pen = Some(edge_pen);
+
+            match (edge.curve, edge.arc()) {
+                (Curve::Arc { center, .. }, Some((radius, start, sweep))) => {
+                    // angles go counterclockwise on the y axis pointing up: arcs going
+                    // clockwise as drawn are drawn with `arcn`
+                    let operator = match sweep > 0. {
+                        true => "arcn",
+                        false => "arc",
+                    };
+                    writeln!(
+                        f,
+                        "newpath {} {:.2} {:.2} {:.2} {operator} stroke",
+                        coordinates(center),
+                        radius * points,
+                        -start.to_degrees(),
+                        -(start + sweep).to_degrees()
+                    )?;
+                }
+                (Curve::Bezier { control1, control2 }, _) => writeln!(
+                    f,
+                    "newpath {} moveto {} {} {} curveto stroke",
+                    coordinates(edge.from),
+                    coordinates(control1),
+                    coordinates(control2),
+                    coordinates(edge.to)
+                )?,
+                _ => writeln!(
+                    f,
+                    "newpath {} moveto {} lineto stroke",
+                    coordinates(edge.from),
+                    coordinates(edge.to)
+                )?,
+            }
+        }
+
+        writeln!(f, "showpage")?;
+        writeln!(f, "%%EOF")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_postscript() {
+        let (blueprint, _) = load_blueprint_str(
+            "[color:red] 10,0 [color:transparent] 0,5 [color:black, style:dashed] -10,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            PostScriptDocument::from(&blueprint.unwrap())
+                .scale(25.4 / 72.)
+                .paper(Paper::Letter)
+                .to_string(),
+            "%!PS-Adobe-3.0
+%%BoundingBox: 0 0 612 792
+%%Pages: 1
+%%EndComments
+%%Page: 1 1
+<< /PageSize [612.00 792.00] >> setpagedevice
+1 setlinecap 1 setlinejoin
+1.000 0.000 0.000 setrgbcolor
+1.00 setlinewidth
+newpath 301.00 398.50 moveto 311.00 398.50 lineto stroke
+0.000 0.000 0.000 setrgbcolor
+[6.00 4.00] 0.00 setdash
+newpath 311.00 393.50 moveto 301.00 393.50 lineto stroke
+showpage
+%%EOF
+"
+        );
+        assert_eq!(Paper::try_from("Letter"), Ok(Paper::Letter));
+        assert_eq!(Paper::try_from("b5"), Err(()));
+    }
+}
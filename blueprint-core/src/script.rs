@@ -0,0 +1,248 @@
+use crate::builder::BlueprintBuilder;
+use crate::domain::{Blueprint, Color, LineStyle, SourceSpan};
+use crate::error::BlueprintError;
+use crate::lexer::Span;
+use crate::source_map::SourceMap;
+use rhai::module_resolvers::FileModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, NativeCallContext, Position};
+use std::cell::RefCell;
+use std::mem;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Extension of the files loaded as scripts rather than as blueprint sources.
+pub const EXTENSION: &str = "rhai";
+
+/// Loads the blueprint drawn by the Rhai script `src`, read from `path`. The script draws with a
+/// pen, from the origin, using the following functions:
+///  - `move_to(x, y)` and `move_by(dx, dy)` lift the pen to a point, starting a new shape;
+///  - `draw_to(x, y)` and `draw_by(dx, dy)` draw an edge to a point;
+///  - `color(name)`, `width(n)`, `style(name)` and `layer(name)` set the attributes of the edges
+///    drawn next, taking the values of the edge attributes of blueprint sources;
+///  - `tag(name)` tags the position of the pen.
+///
+/// As with blueprint sources, the blueprint is returned even if the script failed, with the
+/// edges drawn until then.
+pub fn load_script_str(src: &str, path: &Path) -> (Option<Blueprint>, Vec<BlueprintError>) {
+    let pen = Rc::new(RefCell::new(Pen::new(src)));
+    let mut engine = Engine::new();
+    engine.on_print(|_| {});
+    // modules are imported relative to the script, like the includes of blueprint sources
+    if let Some(directory) = path.parent() {
+        engine.set_module_resolver(FileModuleResolver::new_with_path(directory));
+    }
+
+    let p = pen.clone();
+    engine.register_fn(
+        "move_to",
+        move |x: Dynamic, y: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let (x, y) = (number(x)?, number(y)?);
+            p.borrow_mut().update(|builder| builder.move_to(x, y));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "move_by",
+        move |dx: Dynamic, dy: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let (dx, dy) = (number(dx)?, number(dy)?);
+            p.borrow_mut().update(|builder| builder.move_by(dx, dy));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "draw_to",
+        move |context: NativeCallContext,
+              x: Dynamic,
+              y: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            let (x, y) = (number(x)?, number(y)?);
+            let mut pen = p.borrow_mut();
+            let span = pen.locate(&context);
+            pen.update(|builder| builder.span(span).line_to(x, y));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "draw_by",
+        move |context: NativeCallContext,
+              dx: Dynamic,
+              dy: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            let (dx, dy) = (number(dx)?, number(dy)?);
+            let mut pen = p.borrow_mut();
+            let span = pen.locate(&context);
+            pen.update(|builder| builder.span(span).line_by(dx, dy));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "color",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let color =
+                Color::try_from(name).map_err(|_| format!("`{name}` is not a known color."))?;
+            p.borrow_mut().update(|builder| builder.color(color));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "width",
+        move |width: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let width = number(width)?;
+            if width <= 0. {
+                return Err(format!("`{width}` is not a positive number.").into());
+            }
+            p.borrow_mut().update(|builder| builder.width(width));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn(
+        "style",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let style =
+                LineStyle::try_from(name).map_err(|_| format!("`{name}` is not a known style."))?;
+            p.borrow_mut().update(|builder| builder.style(style));
+            Ok(())
+        },
+    );
+    let p = pen.clone();
+    engine.register_fn("layer", move |name: &str| {
+        p.borrow_mut().update(|builder| builder.layer(Some(name)));
+    });
+    let p = pen.clone();
+    engine.register_fn("tag", move |name: &str| {
+        p.borrow_mut().update(|builder| builder.tag(name));
+    });
+
+    let mut errors = Vec::new();
+    if let Err(mut e) = engine.run(src) {
+        let position = e.take_position();
+        let span = pen.borrow().span(position, 0);
+        let line = pen.borrow().line(span);
+        errors.push(BlueprintError::error(e.to_string()).with_span(span, line));
+    }
+    drop(engine);
+
+    let pen = Rc::into_inner(pen)
+        .expect("the engine holding the pen is dropped")
+        .into_inner();
+    (Some(pen.finish()), errors)
+}
+
+/// Drawing state of a script.
+struct Pen {
+    src: String,
+    source_map: SourceMap,
+    builder: BlueprintBuilder,
+}
+
+impl Pen {
+    fn new(src: &str) -> Self {
+        Self {
+            src: src.to_string(),
+            source_map: SourceMap::new(src),
+            builder: BlueprintBuilder::new(),
+        }
+    }
+
+    fn update(&mut self, f: impl FnOnce(BlueprintBuilder) -> BlueprintBuilder) {
+        self.builder = f(mem::take(&mut self.builder));
+    }
+
+    /// Location of the call of the function of `context`.
+    fn locate(&self, context: &NativeCallContext) -> SourceSpan {
+        let span = self.span(context.call_position(), context.fn_name().len());
+        self.source_map.locate(span)
+    }
+
+    fn finish(self) -> Blueprint {
+        self.builder.build()
+    }
+
+    /// Span of the `len` bytes starting at `position`, given in lines and characters by Rhai.
+    fn span(&self, position: Position, len: usize) -> Span {
+        let start = match (position.line(), position.position()) {
+            (Some(line), Some(column)) => {
+                let line_start = self.source_map.line_start(line).unwrap_or(self.src.len());
+                self.src[line_start..]
+                    .char_indices()
+                    .nth(column - 1)
+                    .map_or(self.src.len(), |(i, _)| line_start + i)
+            }
+            _ => 0,
+        };
+        (start..(start + len).min(self.src.len())).into()
+    }
+
+    /// 1-based line of the start of `span`.
+    fn line(&self, span: Span) -> usize {
+        self.source_map.line_col(span.start).0
+    }
+}
+
+/// Value of a number given to a function, be it an integer or a float.
+fn number(value: Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    match value.as_float() {
+        Ok(n) => Ok(n as f32),
+        Err(_) => value
+            .as_int()
+            .map(|n| n as f32)
+            .map_err(|type_name| format!("Expected a number, found {type_name}").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Point, Shape};
+
+    #[test]
+    fn test_load_script() {
+        let (blueprint, errors) = load_script_str(
+            r#"
+            color("red");
+            for i in 0..3 {
+                draw_by(10, 0);
+                move_by(0, 5);
+            }
+            tag("end");
+            style("dashed");
+            draw_to(0.5, 0);
+            "#,
+            Path::new("test.rhai"),
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let blueprint = blueprint.unwrap();
+        let edges = blueprint
+            .shapes_iter()
+            .flat_map(Shape::edges_iter)
+            .collect::<Vec<_>>();
+        assert_eq!(edges.len(), 4);
+        assert_eq!(edges[1].from, Point::new(10., 5.));
+        assert_eq!(edges[1].to, Point::new(20., 5.));
+        assert_eq!(edges[1].color, Color::Red);
+        assert_eq!(edges[1].span.line, 4);
+        assert_eq!(edges[3].style, LineStyle::Dashed);
+        assert_eq!(
+            blueprint.tags_iter().collect::<Vec<_>>(),
+            vec![("end", &Point::new(30., 15.))]
+        );
+    }
+
+    #[test]
+    fn test_load_script_error() {
+        let (blueprint, errors) =
+            load_script_str("draw_by(10, 0);\ncolor(\"pink\");", Path::new("test.rhai"));
+
+        assert_eq!(blueprint.unwrap().shapes_iter().count(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(2));
+        assert!(errors[0].message.contains("`pink` is not a known color."));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::domain::{Blueprint, Color, Edge, LineStyle, Point};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Source of a blueprint, loading back into the same blueprint: one block per shape, made of
+/// absolute points.
+///
+/// The syntax only has integer coordinates and widths, other values are rounded. Custom colors,
+/// which have no name, are written black.
+pub struct BlueprintSource<'b> {
+    blueprint: &'b Blueprint,
+}
+
+impl BlueprintSource<'_> {
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<'b> From<&'b Blueprint> for BlueprintSource<'b> {
+    fn from(value: &'b Blueprint) -> Self {
+        Self { blueprint: value }
+    }
+}
+
+impl Display for BlueprintSource<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let layers = self
+            .blueprint
+            .layers_iter()
+            .map(|layer| layer.name.as_str())
+            .collect::<Vec<_>>();
+        // each tag is written after the first point at its position
+        let mut tags = BTreeMap::<(i32, i32), Vec<&str>>::new();
+        for (tag, point) in self.blueprint.tags_iter() {
+            tags.entry(coordinates(*point)).or_default().push(tag);
+        }
+        let mut point = |f: &mut Formatter<'_>, point: Point| {
+            let (x, y) = coordinates(point);
+            write!(f, "@{x},{y}")?;
+            for tag in tags.remove(&(x, y)).unwrap_or_default() {
+                write!(f, " #{tag}")?;
+            }
+            writeln!(f)
+        };
+
+        for shape in self.blueprint.shapes_iter() {
+            if let Some(name) = shape.name() {
+                write!(f, "shape {name} ")?;
+            }
+            writeln!(f, "{{")?;
+            let mut position = None;
+            for edge in shape.edges_iter() {
+                if position != Some(coordinates(edge.from)) {
+                    write!(f, "  move ")?;
+                    point(f, edge.from)?;
+                }
+
+                let attributes = attributes(edge, &layers, self.blueprint);
+                if attributes.is_empty() {
+                    write!(f, "  ")?;
+                } else {
+                    write!(f, "  [{}] ", attributes.join(", "))?;
+                }
+                point(f, edge.to)?;
+                position = Some(coordinates(edge.to));
+            }
+            writeln!(f, "}}")?;
+        }
+
+        // tags of points without edges
+        for ((x, y), tags) in tags {
+            write!(f, "move @{x},{y}")?;
+            for tag in tags {
+                write!(f, " #{tag}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn coordinates(point: Point) -> (i32, i32) {
+    (point.x.round() as i32, point.y.round() as i32)
+}
+
+/// `key:value` attributes of `edge` differing from the defaults, followed by its custom ones.
+fn attributes(edge: &Edge, layers: &[&str], blueprint: &Blueprint) -> Vec<String> {
+    let mut attributes = Vec::new();
+    if let Some(color) = edge.color.name()
+        && edge.color != Color::default()
+    {
+        attributes.push(format!("color:{color}"));
+    }
+    let width = edge.width.round().max(1.) as i32;
+    if width != 1 {
+        attributes.push(format!("width:{width}"));
+    }
+    if edge.style != LineStyle::default() {
+        attributes.push(format!("style:{}", edge.style.name()));
+    }
+    if let Some(layer) = edge.layer {
+        attributes.push(format!("layer:{}", layers[layer]));
+    }
+    for (key, value) in blueprint.attributes(edge).into_iter().flatten() {
+        attributes.push(format!("{key}:{value}"));
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::BlueprintBuilder;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_source() {
+        let blueprint = BlueprintBuilder::new()
+            .tag("origin")
+            .color(Color::Red)
+            .line_to(10., 0.)
+            .tag("corner")
+            .width(2.)
+            .style(LineStyle::Dotted)
+            .layer(Some("walls"))
+            .line_by(0., 5.)
+            .move_to(20., 0.)
+            .color(Color::Black)
+            .width(1.)
+            .style(LineStyle::Solid)
+            .layer(None)
+            .line_by(0., 5.)
+            .move_to(30., 5.)
+            .tag("lonely")
+            .build();
+
+        let source = BlueprintSource::from(&blueprint).to_string();
+        assert_eq!(
+            source,
+            "{
+  move @0,0 #origin
+  [color:red] @10,0 #corner
+  [color:red, width:2, style:dotted, layer:walls] @10,5
+}
+{
+  move @20,0
+  @20,5
+}
+move @30,5 #lonely
+"
+        );
+
+        let (loaded, errors) = load_blueprint_str(&source, Path::new("test.bp"), false);
+        assert!(!errors.iter().any(|e| e.is_error()), "{errors:?}");
+        let geometry = |blueprint: &Blueprint| {
+            blueprint
+                .shapes_iter()
+                .flat_map(|shape| shape.edges_iter())
+                .map(|e| (e.from, e.to, e.color, e.width, e.style, e.layer))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(geometry(&loaded.unwrap()), geometry(&blueprint));
+    }
+}
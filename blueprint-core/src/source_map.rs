@@ -0,0 +1,134 @@
+use crate::domain::SourceSpan;
+use crate::lexer::Span;
+
+/// Start of the lines of a source, to find the line and the column of byte offsets without
+/// scanning it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Byte offsets of the first character of each line, the first one being 0.
+    line_starts: Vec<usize>,
+    /// Byte offsets of the characters taking several bytes, with the bytes taken in excess of one
+    /// by them and the ones before them, to count columns in characters.
+    wide_chars: Vec<(usize, usize)>,
+    /// Length of the source, in bytes.
+    len: usize,
+}
+
+impl SourceMap {
+    pub fn new(src: &str) -> Self {
+        let mut source_map = Self {
+            line_starts: vec![0],
+            wide_chars: Vec::new(),
+            len: 0,
+        };
+        source_map.extend(src);
+        source_map
+    }
+
+    /// Maps `src` as following the source mapped so far, for sources read a chunk at a time.
+    pub fn extend(&mut self, src: &str) {
+        let len = self.len;
+        self.line_starts
+            .extend(src.match_indices('\n').map(|(i, _)| len + i + 1));
+        if !src.is_ascii() {
+            let mut excess = self.excess(len);
+            for (i, c) in src.char_indices().filter(|(_, c)| !c.is_ascii()) {
+                excess += c.len_utf8() - 1;
+                self.wide_chars.push((len + i, excess));
+            }
+        }
+        self.len += src.len();
+    }
+
+    /// Length of the source mapped so far, in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 1-based line and column, in characters, of the byte `offset`. A newline is on the line it
+    /// ends.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let excess = self.excess(offset) - self.excess(line_start);
+        (line, offset - line_start - excess + 1)
+    }
+
+    /// Bytes taken in excess of one by the characters before the byte `offset`.
+    fn excess(&self, offset: usize) -> usize {
+        match self
+            .wide_chars
+            .partition_point(|(start, _)| *start < offset)
+        {
+            0 => 0,
+            i => self.wide_chars[i - 1].1,
+        }
+    }
+
+    /// Byte offset of the start of the 1-based `line`.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    pub fn locate(&self, span: Span) -> SourceSpan {
+        let (line, column) = self.line_col(span.start);
+        SourceSpan {
+            start: span.start,
+            end: span.end,
+            line,
+            column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let source_map = SourceMap::new("abc\n\nabcde\n");
+        let locate = |start, end| source_map.locate(Span::from(start..end));
+
+        assert_eq!(
+            locate(0, 2),
+            SourceSpan {
+                start: 0,
+                end: 2,
+                line: 1,
+                column: 1
+            }
+        );
+        assert_eq!(locate(3, 3).line, 1);
+        assert_eq!(locate(4, 4).line, 2);
+        assert_eq!(
+            locate(7, 9),
+            SourceSpan {
+                start: 7,
+                end: 9,
+                line: 3,
+                column: 3
+            }
+        );
+        assert_eq!(locate(12, 13).line, 4);
+        assert_eq!(source_map.line_col(10), (3, 6));
+        assert_eq!(source_map.line_start(3), Some(5));
+        assert_eq!(source_map.line_start(5), None);
+
+        let mut chunked = SourceMap::new("abc\n");
+        chunked.extend("\nabcde\n");
+        assert_eq!(chunked, source_map);
+
+        // columns are counted in characters
+        let mut source_map = SourceMap::new("é\n\"€\" ");
+        source_map.extend("x\u{1F600}y");
+        assert_eq!(source_map.line_col(2), (1, 2));
+        assert_eq!(source_map.line_col(8), (2, 4));
+        assert_eq!(source_map.line_col(9), (2, 5));
+        assert_eq!(source_map.line_col(14), (2, 7));
+    }
+}
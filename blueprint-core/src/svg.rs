@@ -0,0 +1,242 @@
+use crate::domain::{Blueprint, Bound, Color, Coordinate, Curve, Edge, LineStyle};
+use std::f32::consts::PI;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// SVG drawing of a blueprint, made of one `line` element per visible straight edge, and one
+/// `path` element per visible curve. Like images, the
+/// drawing starts at the origin of the blueprint and is surrounded by a padding.
+pub struct SvgDrawing<'b, T: Coordinate = f32> {
+    blueprint: &'b Blueprint<T>,
+    scale: f32,
+    padding: f32,
+    background: Color,
+}
+
+impl<T: Coordinate> SvgDrawing<'_, T> {
+    /// Pixels per blueprint unit of the size of the drawing.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Space around the blueprint, in blueprint units.
+    pub fn padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        write!(writer, "{self}")
+    }
+}
+
+impl<'b, T: Coordinate> From<&'b Blueprint<T>> for SvgDrawing<'b, T> {
+    fn from(value: &'b Blueprint<T>) -> Self {
+        Self {
+            blueprint: value,
+            scale: 1.,
+            padding: 0.,
+            background: Color::Transparent,
+        }
+    }
+}
+
+impl<T: Coordinate> Display for SvgDrawing<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (_, bottom_right) = self.blueprint.boundaries().unwrap_or_default();
+        let padding = self.padding;
+        let positive = |value: T| {
+            if value > T::default() {
+                value
+            } else {
+                T::default()
+            }
+        };
+        let width = positive(bottom_right.x) + T::from_decimal(2. * padding);
+        let height = positive(bottom_right.y) + T::from_decimal(2. * padding);
+        let scale = T::from_decimal(self.scale);
+
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {width} {height}">"#,
+            width * scale,
+            height * scale,
+            -padding,
+            -padding,
+        )?;
+        if !self.background.is_transparent() {
+            writeln!(
+                f,
+                r#"<rect x="{}" y="{}" width="{width}" height="{height}" fill="{}"/>"#,
+                -padding,
+                -padding,
+                paint(self.background),
+            )?;
+        }
+
+        for edge in self.blueprint.edges_iter() {
+            if edge.color.is_transparent() {
+                continue;
+            }
+
+            match edge.curve {
+                Curve::Line => write!(
+                    f,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}""#,
+                    edge.from.x, edge.from.y, edge.to.x, edge.to.y,
+                )?,
+                _ => write!(f, r#"<path d="{}" fill="none""#, path(edge))?,
+            }
+            write!(
+                f,
+                r#" stroke="{}" stroke-width="{}""#,
+                paint(edge.color),
+                edge.width,
+            )?;
+            if edge.style != LineStyle::Solid {
+                let [dash, gap] = edge.style.pattern();
+                write!(
+                    f,
+                    r#" stroke-dasharray="{} {}" stroke-dashoffset="{}""#,
+                    dash * edge.width,
+                    gap * edge.width,
+                    edge.dash_offset,
+                )?;
+            }
+            writeln!(f, "/>")?;
+        }
+
+        writeln!(f, "</svg>")
+    }
+}
+
+/// SVG path data of the curve of `edge`.
+fn path<T: Coordinate>(edge: &Edge<T>) -> String {
+    let (from, to) = (edge.from, edge.to);
+    match (edge.curve, edge.arc()) {
+        (Curve::Arc { center, .. }, Some((radius, _, sweep))) => {
+            // both y axes point down: arcs going clockwise as drawn have a positive sweep
+            let arc = |x: T, y: T, sweep: f32| {
+                format!(
+                    "A {radius} {radius} 0 {} {} {x} {y}",
+                    (sweep.abs() > PI) as u8,
+                    (sweep > 0.) as u8
+                )
+            };
+            if from == to {
+                // an arc ending where it starts is not drawn, a full circle is two halves
+                let (x, y) = (center.x + center.x - from.x, center.y + center.y - from.y);
+                format!(
+                    "M {} {} {} {}",
+                    from.x,
+                    from.y,
+                    arc(x, y, sweep / 2.),
+                    arc(to.x, to.y, sweep / 2.)
+                )
+            } else {
+                format!("M {} {} {}", from.x, from.y, arc(to.x, to.y, sweep))
+            }
+        }
+        (Curve::Bezier { control1, control2 }, _) => format!(
+            "M {} {} C {} {} {} {} {} {}",
+            from.x, from.y, control1.x, control1.y, control2.x, control2.y, to.x, to.y
+        ),
+        _ => format!("M {} {} L {} {}", from.x, from.y, to.x, to.y),
+    }
+}
+
+/// SVG paint of `color`, with its opacity when it is not opaque.
+fn paint(color: Color) -> String {
+    let (r, g, b, a) = color.as_rgba();
+    match a {
+        255 => format!("#{r:02x}{g:02x}{b:02x}"),
+        a => format!("rgba({r},{g},{b},{})", a as f32 / 255.),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Point, Shape};
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_svg() {
+        let (blueprint, _) = load_blueprint_str(
+            "[color:red] 10,0 [color:transparent] 0,5 [style:dashed, width:2] -10,0",
+            Path::new("test.bp"),
+            false,
+        );
+
+        assert_eq!(
+            SvgDrawing::from(&blueprint.unwrap())
+                .scale(2.)
+                .padding(1.)
+                .background(Color::White)
+                .to_string(),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="14" viewBox="-1 -1 12 7">
+<rect x="-1" y="-1" width="12" height="7" fill="#ffffff"/>
+<line x1="0" y1="0" x2="10" y2="0" stroke="#ff0000" stroke-width="1"/>
+<line x1="10" y1="5" x2="0" y2="5" stroke="#000000" stroke-width="2" stroke-dasharray="12 8" stroke-dashoffset="0"/>
+</svg>
+"##
+        );
+    }
+
+    #[test]
+    fn test_svg_curves() {
+        let edge = |x1, y1, x2, y2, curve| {
+            Edge::new(x1, y1, x2, y2, Color::Black, Default::default()).with_curve(curve)
+        };
+        let mut blueprint = Blueprint::default();
+        blueprint.push(Shape::from(vec![
+            edge(
+                10.,
+                10.,
+                0.,
+                0.,
+                Curve::Arc {
+                    center: Point::new(0., 10.),
+                    counterclockwise: true,
+                },
+            ),
+            edge(
+                0.,
+                0.,
+                10.,
+                0.,
+                Curve::Bezier {
+                    control1: Point::new(0., 10.),
+                    control2: Point::new(10., 10.),
+                },
+            ),
+            edge(
+                10.,
+                0.,
+                10.,
+                0.,
+                Curve::Arc {
+                    center: Point::new(10., 5.),
+                    counterclockwise: false,
+                },
+            ),
+        ]));
+
+        assert_eq!(
+            SvgDrawing::from(&blueprint).padding(1.).to_string(),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="17" height="12" viewBox="-1 -1 17 12">
+<path d="M 10 10 A 10 10 0 0 0 0 0" fill="none" stroke="#000000" stroke-width="1"/>
+<path d="M 0 0 C 0 10 10 10 10 0" fill="none" stroke="#000000" stroke-width="1"/>
+<path d="M 10 0 A 5 5 0 0 1 10 10 A 5 5 0 0 1 10 0" fill="none" stroke="#000000" stroke-width="1"/>
+</svg>
+"##
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::image::{ImageEncoder, Raster, bands};
+use std::collections::BTreeSet;
+use std::io;
+use std::io::{BufWriter, Write};
+
+/// How images are drawn in terminals.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TerminalGraphics {
+    /// Two pixels per character, with the upper half block `▀` colored with 24-bit colors.
+    Blocks,
+    /// Sixel graphics, a pixel per pixel, for the terminals supporting them.
+    Sixel,
+}
+
+/// Image drawn in a terminal, written as the escape sequences of its graphics.
+pub struct TerminalImage<'c> {
+    raster: &'c dyn Raster,
+    graphics: TerminalGraphics,
+}
+
+impl TerminalImage<'_> {
+    pub fn graphics(mut self, graphics: TerminalGraphics) -> Self {
+        self.graphics = graphics;
+        self
+    }
+
+    fn write_blocks(&self, writer: &mut dyn Write) -> Result<(), io::Error> {
+        for rows in bands(self.raster) {
+            let band = self.raster.band(rows.clone());
+            // a character draws two rows, an odd band leaving the last one without lower half
+            for y in rows.clone().step_by(2) {
+                for x in 0..band.width {
+                    let (r, g, b, _) = band.get(x, y).as_rgba();
+                    write!(writer, "\x1b[38;2;{r};{g};{b}m")?;
+                    if y + 1 < rows.end {
+                        let (r, g, b, _) = band.get(x, y + 1).as_rgba();
+                        write!(writer, "\x1b[48;2;{r};{g};{b}m")?;
+                    } else {
+                        write!(writer, "\x1b[49m")?;
+                    }
+                    write!(writer, "▀")?;
+                }
+                writeln!(writer, "\x1b[0m")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sixel(&self, writer: &mut dyn Write) -> Result<(), io::Error> {
+        write!(
+            writer,
+            "\x1bPq\"1;1;{};{}",
+            self.raster.width(),
+            self.raster.height()
+        )?;
+        // colors are reduced to a 6x6x6 cube, holding the named colors exactly
+        for index in 0..216 {
+            let (r, g, b) = (index / 36, index / 6 % 6, index % 6);
+            write!(writer, "#{index};2;{};{};{}", r * 20, g * 20, b * 20)?;
+        }
+
+        for rows in bands(self.raster) {
+            let band = self.raster.band(rows.clone());
+            let pixels = (rows.start..rows.end)
+                .map(|y| {
+                    (0..band.width)
+                        .map(|x| {
+                            let (r, g, b, _) = band.get(x, y).as_rgba();
+                            let level = |c: u8| (c as usize * 5 + 127) / 255;
+                            level(r) * 36 + level(g) * 6 + level(b)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            // each sixel draws a column of six rows
+            for sixel_rows in pixels.chunks(6) {
+                let colors = sixel_rows.iter().flatten().collect::<BTreeSet<_>>();
+                for color in colors {
+                    write!(writer, "#{color}")?;
+                    let sixels = (0..band.width).map(|x| {
+                        let bits = sixel_rows
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, row)| row[x] == *color)
+                            .fold(0, |bits, (i, _)| bits | 1 << i);
+                        (63 + bits) as u8 as char
+                    });
+                    write_run_lengths(writer, sixels)?;
+                    // back to the start of the row, to draw the next color over it
+                    write!(writer, "$")?;
+                }
+                write!(writer, "-")?;
+            }
+        }
+        write!(writer, "\x1b\\")
+    }
+}
+
+/// Writes `sixels`, repetitions being written as `!<count><sixel>`.
+fn write_run_lengths(
+    writer: &mut dyn Write,
+    sixels: impl Iterator<Item = char>,
+) -> Result<(), io::Error> {
+    let mut sixels = sixels.peekable();
+    while let Some(sixel) = sixels.next() {
+        let mut count = 1;
+        while sixels.next_if_eq(&sixel).is_some() {
+            count += 1;
+        }
+        match count {
+            1..=3 => write!(writer, "{}", sixel.to_string().repeat(count))?,
+            count => write!(writer, "!{count}{sixel}")?,
+        }
+    }
+    Ok(())
+}
+
+impl ImageEncoder for TerminalImage<'_> {
+    fn write_to(&self, writer: &mut dyn io::Write) -> Result<(), io::Error> {
+        let mut writer = BufWriter::new(writer);
+        match self.graphics {
+            TerminalGraphics::Blocks => self.write_blocks(&mut writer)?,
+            TerminalGraphics::Sixel => self.write_sixel(&mut writer)?,
+        }
+        writer.flush()
+    }
+}
+
+impl<'c, R: Raster> From<&'c R> for TerminalImage<'c> {
+    fn from(value: &'c R) -> Self {
+        Self {
+            raster: value,
+            graphics: TerminalGraphics::Blocks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::domain::Color;
+
+    #[test]
+    fn test_blocks() {
+        let mut canvas = Canvas::new(2, 3).with_background(Color::White);
+        canvas.set(1, 0, Color::Red);
+        canvas.set(0, 2, Color::Black);
+
+        let mut blocks = Vec::new();
+        TerminalImage::from(&canvas).write_to(&mut blocks).unwrap();
+        assert_eq!(
+            String::from_utf8(blocks).unwrap(),
+            "\x1b[38;2;255;255;255m\x1b[48;2;255;255;255m▀\
+             \x1b[38;2;255;0;0m\x1b[48;2;255;255;255m▀\x1b[0m\n\
+             \x1b[38;2;0;0;0m\x1b[49m▀\
+             \x1b[38;2;255;255;255m\x1b[49m▀\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_sixel() {
+        let mut canvas = Canvas::new(5, 2).with_background(Color::White);
+        canvas.set(0, 1, Color::Red);
+
+        let mut sixel = Vec::new();
+        TerminalImage::from(&canvas)
+            .graphics(TerminalGraphics::Sixel)
+            .write_to(&mut sixel)
+            .unwrap();
+        let sixel = String::from_utf8(sixel).unwrap();
+        assert!(sixel.starts_with("\x1bPq\"1;1;5;2#0;2;0;0;0#1;2;0;0;20"));
+        // white everywhere but the lower pixel of the first column, red there
+        assert!(sixel.contains("#215@!4B$"));
+        assert!(sixel.contains("#180A!4?$"));
+        assert!(sixel.ends_with("-\x1b\\"));
+    }
+}
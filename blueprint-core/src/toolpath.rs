@@ -0,0 +1,109 @@
+use crate::domain::{Blueprint, Color, Coordinate, FLATNESS, Point};
+
+/// Polyline drawn by a plotter without lifting its pen.
+#[derive(Debug, PartialEq)]
+pub struct Toolpath<T: Coordinate = f32> {
+    pub color: Color,
+    pub points: Vec<Point<T>>,
+}
+
+impl<T: Coordinate> Toolpath<T> {
+    fn start(&self) -> Point<T> {
+        self.points[0]
+    }
+
+    fn end(&self) -> Point<T> {
+        self.points[self.points.len() - 1]
+    }
+}
+
+/// Splits the visible edges of `blueprint`, curves being flattened, into toolpaths, chaining the
+/// consecutive edges of a shape that are connected and share a color, and orders them to keep pen-up moves short: from
+/// the origin, the closest toolpath is drawn next, reversed if its end is the closest.
+pub fn toolpaths<T: Coordinate>(blueprint: &Blueprint<T>) -> Vec<Toolpath<T>> {
+    let mut remaining = Vec::<Toolpath<T>>::new();
+    for shape in blueprint.shapes_iter() {
+        let mut current: Option<Toolpath<T>> = None;
+        for edge in shape.edges_iter().flat_map(|edge| edge.flatten(FLATNESS)) {
+            if edge.color.is_transparent() {
+                remaining.extend(current.take());
+                continue;
+            }
+
+            match &mut current {
+                Some(toolpath) if toolpath.color == edge.color && toolpath.end() == edge.from => {
+                    toolpath.points.push(edge.to);
+                }
+                _ => {
+                    remaining.extend(current.replace(Toolpath {
+                        color: edge.color,
+                        points: vec![edge.from, edge.to],
+                    }));
+                }
+            }
+        }
+        remaining.extend(current);
+    }
+
+    let mut ordered = Vec::with_capacity(remaining.len());
+    let mut position = Point::<T>::default();
+    while !remaining.is_empty() {
+        let (index, reversed, _) = remaining
+            .iter()
+            .enumerate()
+            .flat_map(|(index, toolpath)| {
+                [
+                    (index, false, position.distance_to_point(&toolpath.start())),
+                    (index, true, position.distance_to_point(&toolpath.end())),
+                ]
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .expect("there are remaining toolpaths");
+
+        let mut toolpath = remaining.swap_remove(index);
+        if reversed {
+            toolpath.points.reverse();
+        }
+        position = toolpath.end();
+        ordered.push(toolpath);
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_blueprint_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_toolpaths() {
+        let (blueprint, _) = load_blueprint_str(
+            "{ move @20,0 10,0 0,10 [color:red] -10,0 } { 10,0 move 0,10 -10,0 }",
+            Path::new("test.bp"),
+            false,
+        );
+
+        let toolpaths = toolpaths(&blueprint.unwrap())
+            .into_iter()
+            .map(|toolpath| {
+                let points = toolpath
+                    .points
+                    .iter()
+                    .map(|point| (point.x, point.y))
+                    .collect::<Vec<_>>();
+                (toolpath.color, points)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            toolpaths,
+            vec![
+                (Color::Black, vec![(0., 0.), (10., 0.)]),
+                (Color::Black, vec![(20., 0.), (30., 0.), (30., 10.)]),
+                (Color::Red, vec![(30., 10.), (20., 10.)]),
+                (Color::Black, vec![(10., 10.), (0., 10.)]),
+            ]
+        );
+    }
+}
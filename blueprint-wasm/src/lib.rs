@@ -0,0 +1,86 @@
+//! JavaScript API previewing blueprints in the browser, built with
+//! `wasm-pack build blueprint-wasm --target web`:
+//!
+//! ```js
+//! import init, { parse } from "./pkg/blueprint_wasm.js";
+//!
+//! await init();
+//! const preview = parse("10,0 0,10 -10,0 0,-10");
+//! element.innerHTML = preview.svg ?? "";
+//! for (const diagnostic of JSON.parse(preview.diagnostics)) {
+//!     console.warn(diagnostic.message);
+//! }
+//! ```
+
+use blueprint_core::error::to_json;
+use blueprint_core::loader::load_blueprint_str;
+use blueprint_core::svg::SvgDrawing;
+use std::path::Path;
+use wasm_bindgen::prelude::*;
+
+/// Space around the previewed blueprints, in blueprint units.
+const PADDING: f32 = 5.;
+
+/// Blueprint drawn from a source, along with the problems found in it.
+#[wasm_bindgen]
+pub struct Preview {
+    svg: Option<String>,
+    diagnostics: String,
+}
+
+#[wasm_bindgen]
+impl Preview {
+    /// SVG drawing of the blueprint, undefined when it could not be loaded.
+    #[wasm_bindgen(getter)]
+    pub fn svg(&self) -> Option<String> {
+        self.svg.clone()
+    }
+
+    /// JSON array of the problems found, in the format of `blueprint check --json`.
+    #[wasm_bindgen(getter)]
+    pub fn diagnostics(&self) -> String {
+        self.diagnostics.clone()
+    }
+}
+
+/// Loads the blueprint of `src` and draws it. Includes cannot be resolved, as there is no file
+/// system to read them from.
+#[wasm_bindgen]
+pub fn parse(src: &str) -> Preview {
+    let path = Path::new("input.bp");
+    let (blueprint, errors) = load_blueprint_str(src, path, false);
+
+    let diagnostics = errors
+        .iter()
+        .map(|error| to_json(path, src, error))
+        .collect::<Vec<_>>();
+    Preview {
+        svg: blueprint.map(|blueprint| {
+            SvgDrawing::from(&blueprint.composed())
+                .padding(PADDING)
+                .to_string()
+        }),
+        diagnostics: serde_json::Value::from(diagnostics).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let preview = parse("10,0 [colour:red] 0,10");
+
+        assert!(
+            preview
+                .svg()
+                .unwrap()
+                .contains(r#"<line x1="0" y1="0" x2="10" y2="0""#)
+        );
+        let diagnostics =
+            serde_json::from_str::<serde_json::Value>(&preview.diagnostics()).unwrap();
+        assert_eq!(diagnostics[0]["severity"], "warning");
+        assert_eq!(diagnostics[0]["location"]["column"], 7);
+    }
+}
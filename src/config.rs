@@ -0,0 +1,142 @@
+use crate::keymap::Keymap;
+use blueprint_core::error::Error;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// Colors of the UI.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ThemeMode {
+    #[default]
+    Light,
+    /// Dark background, the black and white edges being drawn inverted to keep their contrast.
+    Dark,
+}
+
+impl ThemeMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            ThemeMode::Light => ThemeMode::Dark,
+            ThemeMode::Dark => ThemeMode::Light,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ThemeMode::Light => "light",
+            ThemeMode::Dark => "dark",
+        }
+    }
+}
+
+/// Settings of the UI kept from a run to the next, in `blueprint/config.toml` of the configuration
+/// directory of the user (`$XDG_CONFIG_HOME`, or `~/.config`):
+///
+/// ```toml
+/// theme = "dark"
+/// pan-step = 20
+///
+/// [keys]
+/// pan-up = "k"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub theme: ThemeMode,
+    /// Blueprint units the view moves by when a pan key is pressed.
+    pub pan_step: f32,
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: ThemeMode::default(),
+            pan_step: 10.,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration of the user, the default one when it does not exist or is not
+    /// valid.
+    pub fn load() -> Config {
+        path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|src| parse(&src))
+            .unwrap_or_default()
+    }
+
+    /// Saves the configuration, keeping the settings it does not know about. The keymap is not
+    /// changed from the UI, and kept as it is written.
+    pub fn save(&self) -> Result<(), Error> {
+        let path = path().ok_or_else(|| {
+            Error::InvalidArgument("No configuration directory to save the settings to".to_string())
+        })?;
+        let mut table = fs::read_to_string(&path)
+            .ok()
+            .and_then(|src| src.parse::<Table>().ok())
+            .unwrap_or_default();
+        table.insert(
+            "theme".to_string(),
+            Value::String(self.theme.name().to_string()),
+        );
+
+        let write = |source| Error::Write {
+            path: path.clone(),
+            source,
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(write)?;
+        }
+        fs::write(&path, table.to_string()).map_err(write)
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let directory = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(directory.join("blueprint").join("config.toml"))
+}
+
+/// Reads the configuration `src`, keeping the defaults of the settings missing or not valid.
+fn parse(src: &str) -> Config {
+    let table = src.parse::<Table>().unwrap_or_default();
+    let theme = match table.get("theme").and_then(Value::as_str) {
+        Some("dark") => ThemeMode::Dark,
+        _ => ThemeMode::Light,
+    };
+    let pan_step = match table.get("pan-step") {
+        Some(Value::Integer(step)) if *step > 0 => *step as f32,
+        Some(Value::Float(step)) if *step > 0. => *step as f32,
+        _ => Config::default().pan_step,
+    };
+    let keymap = match table.get("keys") {
+        Some(Value::Table(keys)) => Keymap::from_table(keys),
+        _ => Keymap::default(),
+    };
+    Config {
+        theme,
+        pan_step,
+        keymap,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("theme = \"dark\"").theme, ThemeMode::Dark);
+        assert_eq!(parse("theme = \"light\"").theme, ThemeMode::Light);
+        assert_eq!(parse("theme = 3").theme, ThemeMode::Light);
+        assert_eq!(parse("not toml").theme, ThemeMode::Light);
+        assert_eq!(parse("pan-step = 2.5").pan_step, 2.5);
+        assert_eq!(parse("pan-step = -1").pan_step, 10.);
+        assert_ne!(parse("[keys]\npan-up = \"k\"").keymap, Keymap::default());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{export_args, select};
+use blueprint_core::error::{BlueprintError, Error};
+use blueprint_core::export;
+use blueprint_core::loader::load_blueprint_str_as;
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+
+/// Renders blueprints for the clients connecting to `address`, without the startup cost of a
+/// process per render.
+///
+/// Each request is a JSON object on its own line:
+///
+/// ```json
+/// {"path": "plan.bp", "source": "{ 10,0 }", "options": {"format": "png", "scale": 4}}
+/// ```
+///
+/// `source` is optional, the file at `path` being read when it is missing, and `options` takes the
+/// export options of the command line, as the manifests of projects do. Each response is a JSON
+/// object on its own line, followed by the `length` bytes of the exported image:
+///
+/// ```json
+/// {"length": 1234, "diagnostics": [...], "error": null}
+/// ```
+///
+/// The diagnostics are the objects of `blueprint check --json`. `error` tells why no image was
+/// exported, with a `length` of 0.
+pub fn run(address: &str) -> Result<(), Error> {
+    let listener = TcpListener::bind(address).map_err(|source| Error::Listen {
+        address: address.to_string(),
+        source,
+    })?;
+    eprintln!("Listening on {address}");
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        thread::spawn(move || {
+            if let Err(e) = serve(stream) {
+                eprintln!("Connection failed: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Answers the requests of `stream` until it is closed.
+fn serve(stream: TcpStream) -> Result<(), io::Error> {
+    let mut writer = stream.try_clone()?;
+    for request in BufReader::new(stream).lines() {
+        let request = request?;
+        if request.trim().is_empty() {
+            continue;
+        }
+
+        let (response, image) = respond(&request);
+        writeln!(writer, "{response}")?;
+        writer.write_all(&image)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Response to `request`, and the image following it.
+fn respond(request: &str) -> (Value, Vec<u8>) {
+    let mut diagnostics = Vec::new();
+    let result = render(request, &mut diagnostics);
+    let (image, error) = match result {
+        Ok(image) => (image, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let response = serde_json::json!({
+        "length": image.len(),
+        "diagnostics": diagnostics,
+        "error": error,
+    });
+    (response, image)
+}
+
+/// Exports the blueprint of `request`, pushing the problems found in it to `diagnostics`.
+fn render(request: &str, diagnostics: &mut Vec<Value>) -> Result<Vec<u8>, Error> {
+    let request = serde_json::from_str::<Value>(request)
+        .map_err(|e| Error::InvalidArgument(format!("Invalid request: {e}")))?;
+    let path = request
+        .get("path")
+        .and_then(Value::as_str)
+        .map(Path::new)
+        .ok_or_else(|| Error::InvalidArgument("`path` must be a path".to_string()))?;
+    let src = match request.get("source") {
+        None => fs::read_to_string(path).map_err(|source| Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?,
+        Some(Value::String(src)) => src.clone(),
+        Some(_) => {
+            return Err(Error::InvalidArgument(
+                "`source` must be a string".to_string(),
+            ));
+        }
+    };
+    let export = match request.get("options") {
+        None => export_args(&[])?,
+        Some(Value::Object(options)) => export_args(&self::options(options)?)?,
+        Some(_) => {
+            return Err(Error::InvalidArgument(
+                "`options` must be an object".to_string(),
+            ));
+        }
+    };
+    if export.split_layers || export.terminal.is_some() || export.output.is_some() {
+        return Err(Error::InvalidArgument(
+            "The daemon only writes to its responses, one image each".to_string(),
+        ));
+    }
+
+    let (blueprint, errors) = load_blueprint_str_as::<f64>(&src, path, export.strict);
+    diagnostics.extend(
+        errors
+            .iter()
+            .map(|error| blueprint_core::error::to_json(path, &src, error)),
+    );
+    let blueprint = match blueprint {
+        Some(blueprint) if !export.strict || !errors.iter().any(BlueprintError::is_error) => {
+            blueprint
+        }
+        _ => {
+            return Err(Error::Invalid {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+    };
+
+    let mut image = Vec::new();
+    export::export_to(&select(blueprint, &export), &mut image, &export.options)?;
+    Ok(image)
+}
+
+/// Command-line options of the keys of `options`, flags being given as `true`.
+fn options(options: &Map<String, Value>) -> Result<Vec<String>, Error> {
+    options
+        .iter()
+        .filter(|(_, value)| **value != Value::Bool(false))
+        .map(|(key, value)| match value {
+            Value::Bool(true) => Ok(format!("--{key}")),
+            Value::String(value) => Ok(format!("--{key}={value}")),
+            Value::Number(value) => Ok(format!("--{key}={value}")),
+            _ => Err(Error::InvalidArgument(format!(
+                "`options.{key}` must be a string, a number or a boolean"
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let (response, image) = respond(
+            r#"{"path": "test.bp", "source": "{ 10,0 [color:purple] 0,5 }", "options": {"format": "svg", "tags": true}}"#,
+        );
+        assert_eq!(response["length"], image.len());
+        assert_eq!(response["error"], Value::Null);
+        assert_eq!(response["diagnostics"].as_array().unwrap().len(), 1);
+        assert!(String::from_utf8(image).unwrap().starts_with("<svg"));
+
+        let (response, image) =
+            respond(r#"{"path": "test.bp", "source": "", "options": {"scale": [2]}}"#);
+        assert_eq!(response["length"], 0);
+        assert_eq!(
+            response["error"],
+            "`options.scale` must be a string, a number or a boolean"
+        );
+        assert!(image.is_empty());
+    }
+}
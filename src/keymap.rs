@@ -0,0 +1,462 @@
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// Action of the UI triggered by a keyboard shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    PanUp,
+    PanLeft,
+    PanDown,
+    PanRight,
+    /// Starts a measurement at the mouse position.
+    Measure,
+    ClearMeasures,
+    PinMeasurement,
+    UnpinMeasurement,
+    CopyPosition,
+    CopyMeasurement,
+    ToggleRectangle,
+    ToggleConstruction,
+    ToggleWidths,
+    ToggleTheme,
+    ToggleEditor,
+    ToggleSidebar,
+    ToggleFullscreen,
+    NextTab,
+    PreviousTab,
+    PreviousVersion,
+    NextVersion,
+    Reload,
+    Print,
+    Screenshot,
+    Search,
+    Cheatsheet,
+}
+
+impl Action {
+    pub const ALL: [Action; 29] = [
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+        Action::PanUp,
+        Action::PanLeft,
+        Action::PanDown,
+        Action::PanRight,
+        Action::Measure,
+        Action::ClearMeasures,
+        Action::PinMeasurement,
+        Action::UnpinMeasurement,
+        Action::CopyPosition,
+        Action::CopyMeasurement,
+        Action::ToggleRectangle,
+        Action::ToggleConstruction,
+        Action::ToggleWidths,
+        Action::ToggleTheme,
+        Action::ToggleEditor,
+        Action::ToggleSidebar,
+        Action::ToggleFullscreen,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::PreviousVersion,
+        Action::NextVersion,
+        Action::Reload,
+        Action::Print,
+        Action::Screenshot,
+        Action::Search,
+        Action::Cheatsheet,
+    ];
+
+    /// Name of the action in the `keys` table of the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::ZoomReset => "zoom-reset",
+            Action::PanUp => "pan-up",
+            Action::PanLeft => "pan-left",
+            Action::PanDown => "pan-down",
+            Action::PanRight => "pan-right",
+            Action::Measure => "measure",
+            Action::ClearMeasures => "clear-measures",
+            Action::PinMeasurement => "pin-measurement",
+            Action::UnpinMeasurement => "unpin-measurement",
+            Action::CopyPosition => "copy-position",
+            Action::CopyMeasurement => "copy-measurement",
+            Action::ToggleRectangle => "toggle-rectangle",
+            Action::ToggleConstruction => "toggle-construction",
+            Action::ToggleWidths => "toggle-widths",
+            Action::ToggleTheme => "toggle-theme",
+            Action::ToggleEditor => "toggle-editor",
+            Action::ToggleSidebar => "toggle-sidebar",
+            Action::ToggleFullscreen => "toggle-fullscreen",
+            Action::NextTab => "next-tab",
+            Action::PreviousTab => "previous-tab",
+            Action::PreviousVersion => "previous-version",
+            Action::NextVersion => "next-version",
+            Action::Reload => "reload",
+            Action::Print => "print",
+            Action::Screenshot => "screenshot",
+            Action::Search => "search",
+            Action::Cheatsheet => "cheatsheet",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ZoomIn => "zoom in",
+            Action::ZoomOut => "zoom out",
+            Action::ZoomReset => "reset the zoom and the position",
+            Action::PanUp => "move up",
+            Action::PanLeft => "move left",
+            Action::PanDown => "move down",
+            Action::PanRight => "move right",
+            Action::Measure => "measure from the mouse",
+            Action::ClearMeasures => "clear the measurements",
+            Action::PinMeasurement => "pin the measurement",
+            Action::UnpinMeasurement => "remove the closest pinned measurement",
+            Action::CopyPosition => "copy the position of the mouse",
+            Action::CopyMeasurement => "copy the measurement",
+            Action::ToggleRectangle => "select rectangles rather than measure",
+            Action::ToggleConstruction => "show or hide the transparent edges",
+            Action::ToggleWidths => "scale the widths of the edges with the zoom, or not",
+            Action::ToggleTheme => "switch between the light and dark themes",
+            Action::ToggleEditor => "show or hide the editor",
+            Action::ToggleSidebar => "show or hide the sidebar",
+            Action::ToggleFullscreen => "show only the blueprint, fullscreen, or everything",
+            Action::NextTab => "next tab",
+            Action::PreviousTab => "previous tab",
+            Action::PreviousVersion => "show the blueprint loaded before",
+            Action::NextVersion => "show the blueprint loaded after",
+            Action::Reload => "reload the file",
+            Action::Print => "preview the page printed, or stop previewing it",
+            Action::Screenshot => "save what is drawn to a PNG file",
+            Action::Search => "find a tag",
+            Action::Cheatsheet => "show or hide the shortcuts",
+        }
+    }
+
+    /// Whether the action is triggered when its key is released rather than pressed.
+    fn on_release(self) -> bool {
+        matches!(self, Action::Measure | Action::ClearMeasures)
+    }
+
+    /// Whether the action is also triggered with shift held, to take larger steps.
+    fn shifted(self) -> bool {
+        matches!(
+            self,
+            Action::PanUp | Action::PanLeft | Action::PanDown | Action::PanRight
+        )
+    }
+}
+
+/// Names of the keys that are not characters.
+const NAMED_KEYS: [(&str, Named); 26] = [
+    ("space", Named::Space),
+    ("escape", Named::Escape),
+    ("enter", Named::Enter),
+    ("tab", Named::Tab),
+    ("backspace", Named::Backspace),
+    ("delete", Named::Delete),
+    ("up", Named::ArrowUp),
+    ("down", Named::ArrowDown),
+    ("left", Named::ArrowLeft),
+    ("right", Named::ArrowRight),
+    ("home", Named::Home),
+    ("end", Named::End),
+    ("page-up", Named::PageUp),
+    ("page-down", Named::PageDown),
+    ("f1", Named::F1),
+    ("f2", Named::F2),
+    ("f3", Named::F3),
+    ("f4", Named::F4),
+    ("f5", Named::F5),
+    ("f6", Named::F6),
+    ("f7", Named::F7),
+    ("f8", Named::F8),
+    ("f9", Named::F9),
+    ("f10", Named::F10),
+    ("f11", Named::F11),
+    ("f12", Named::F12),
+];
+
+/// Key with the modifiers held, e.g. `ctrl+shift+tab`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    /// Character of the key, or the name of a key of [`NAMED_KEYS`].
+    key: String,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Shortcut {
+    fn matches(&self, key: &str, modifiers: Modifiers) -> bool {
+        // symbols such as `?` are typed with shift on most layouts
+        let symbol = key.chars().count() == 1 && !key.chars().all(char::is_alphanumeric);
+        self.key == key
+            && self.ctrl == modifiers.control()
+            && self.alt == modifiers.alt()
+            && (self.shift == modifiers.shift() || (symbol && !self.shift))
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let shortcut = s.trim().to_lowercase();
+        let (modifiers, key) = match shortcut.strip_suffix('+') {
+            // the `+` key itself
+            Some(modifiers) if modifiers.is_empty() || modifiers.ends_with('+') => {
+                (modifiers.strip_suffix('+').unwrap_or(modifiers), "+")
+            }
+            _ => shortcut.rsplit_once('+').unwrap_or(("", &shortcut)),
+        };
+
+        if key.chars().count() != 1 && !NAMED_KEYS.iter().any(|(name, _)| *name == key) {
+            return Err(format!("unknown key `{key}` in `{s}`"));
+        }
+        let mut result = Shortcut {
+            key: key.to_string(),
+            ctrl: false,
+            alt: false,
+            shift: false,
+        };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier {
+                "ctrl" | "control" => result.ctrl = true,
+                "alt" => result.alt = true,
+                "shift" => result.shift = true,
+                _ => return Err(format!("unknown modifier `{modifier}` in `{s}`")),
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "alt+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Shortcuts of the actions of the UI. The configuration rebinds them in its `keys` table, by the
+/// name of the action:
+///
+/// ```toml
+/// [keys]
+/// pan-up = "k"
+/// zoom-in = ["+", "ctrl+up"]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(Shortcut, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = [
+            ("i", Action::ZoomIn),
+            ("e", Action::ZoomIn),
+            ("o", Action::ZoomOut),
+            ("q", Action::ZoomOut),
+            ("0", Action::ZoomReset),
+            ("w", Action::PanUp),
+            ("a", Action::PanLeft),
+            ("s", Action::PanDown),
+            ("d", Action::PanRight),
+            ("space", Action::Measure),
+            ("escape", Action::ClearMeasures),
+            ("p", Action::PinMeasurement),
+            ("delete", Action::UnpinMeasurement),
+            ("backspace", Action::UnpinMeasurement),
+            ("c", Action::CopyPosition),
+            ("shift+c", Action::CopyMeasurement),
+            ("r", Action::ToggleRectangle),
+            ("h", Action::ToggleConstruction),
+            ("l", Action::ToggleWidths),
+            ("t", Action::ToggleTheme),
+            ("ctrl+e", Action::ToggleEditor),
+            ("ctrl+b", Action::ToggleSidebar),
+            ("f11", Action::ToggleFullscreen),
+            ("ctrl+tab", Action::NextTab),
+            ("ctrl+shift+tab", Action::PreviousTab),
+            ("[", Action::PreviousVersion),
+            ("]", Action::NextVersion),
+            ("ctrl+r", Action::Reload),
+            ("ctrl+p", Action::Print),
+            ("ctrl+shift+s", Action::Screenshot),
+            ("/", Action::Search),
+            ("?", Action::Cheatsheet),
+        ];
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(shortcut, action)| (shortcut.parse().unwrap(), action))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Default keymap with the shortcuts of `keys`, ignoring the actions and the shortcuts that are
+    /// not valid.
+    pub fn from_table(keys: &Table) -> Keymap {
+        let mut keymap = Keymap::default();
+        for (name, shortcuts) in keys {
+            let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name) else {
+                continue;
+            };
+            let shortcuts = match shortcuts {
+                Value::String(shortcut) => vec![shortcut.as_str()],
+                Value::Array(shortcuts) => shortcuts.iter().filter_map(Value::as_str).collect(),
+                _ => continue,
+            };
+            keymap.bind(
+                action,
+                shortcuts
+                    .into_iter()
+                    .filter_map(|shortcut| shortcut.parse().ok())
+                    .collect(),
+            );
+        }
+        keymap
+    }
+
+    /// Binds `action` to `shortcuts` instead of its previous ones, unbinding them from the other
+    /// actions.
+    pub fn bind(&mut self, action: Action, shortcuts: Vec<Shortcut>) {
+        self.bindings
+            .retain(|(shortcut, bound)| *bound != action && !shortcuts.contains(shortcut));
+        self.bindings
+            .extend(shortcuts.into_iter().map(|shortcut| (shortcut, action)));
+    }
+
+    /// Shortcuts bound to `action`.
+    pub fn shortcuts(&self, action: Action) -> impl Iterator<Item = &Shortcut> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(shortcut, _)| shortcut)
+    }
+
+    /// Action bound to `key` pressed, or `released`, with `modifiers`.
+    pub fn action(&self, key: &Key, modifiers: Modifiers, released: bool) -> Option<Action> {
+        let key = match key {
+            Key::Character(c) => c.to_lowercase(),
+            Key::Named(named) => NAMED_KEYS
+                .iter()
+                .find(|(_, key)| key == named)
+                .map(|(name, _)| name.to_string())?,
+            Key::Unidentified => return None,
+        };
+        let action = |modifiers| {
+            self.bindings
+                .iter()
+                .find(|(shortcut, action)| {
+                    action.on_release() == released && shortcut.matches(&key, modifiers)
+                })
+                .map(|(_, action)| *action)
+        };
+        action(modifiers).or_else(|| {
+            action(modifiers.difference(Modifiers::SHIFT))
+                .filter(|action| modifiers.shift() && action.shifted())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        assert_eq!(
+            "Ctrl+Shift+Tab".parse::<Shortcut>().unwrap().to_string(),
+            "ctrl+shift+tab"
+        );
+        assert_eq!("ctrl++".parse::<Shortcut>().unwrap().to_string(), "ctrl++");
+        assert_eq!(
+            "meta+a".parse::<Shortcut>(),
+            Err("unknown modifier `meta` in `meta+a`".to_string())
+        );
+        assert_eq!(
+            "ctrl+".parse::<Shortcut>(),
+            Err("unknown key `` in `ctrl+`".to_string())
+        );
+
+        let keymap = Keymap::from_table(
+            &r#"
+            pan-up = "k"
+            zoom-in = ["w", "ctrl+up"]
+            unknown = "x"
+            "#
+            .parse()
+            .unwrap(),
+        );
+        let character = |c: &str| Key::Character(c.into());
+        assert_eq!(
+            keymap.action(&character("k"), Modifiers::empty(), false),
+            Some(Action::PanUp)
+        );
+        assert_eq!(
+            keymap.action(&character("w"), Modifiers::empty(), false),
+            Some(Action::ZoomIn)
+        );
+        assert_eq!(
+            keymap.action(&character("i"), Modifiers::empty(), false),
+            None
+        );
+        assert_eq!(
+            keymap.action(&Key::Named(Named::ArrowUp), Modifiers::CTRL, false),
+            Some(Action::ZoomIn)
+        );
+        assert_eq!(
+            keymap.action(&character("K"), Modifiers::SHIFT, false),
+            Some(Action::PanUp)
+        );
+        assert_eq!(
+            keymap.action(&character("W"), Modifiers::SHIFT, false),
+            None
+        );
+        assert_eq!(
+            keymap.action(&character("?"), Modifiers::SHIFT, false),
+            Some(Action::Cheatsheet)
+        );
+        assert_eq!(
+            keymap.action(
+                &Key::Named(Named::Tab),
+                Modifiers::CTRL | Modifiers::SHIFT,
+                false
+            ),
+            Some(Action::PreviousTab)
+        );
+        assert_eq!(
+            keymap.action(&Key::Named(Named::F11), Modifiers::empty(), false),
+            Some(Action::ToggleFullscreen)
+        );
+        assert_eq!(
+            keymap.action(&Key::Named(Named::Space), Modifiers::empty(), false),
+            None
+        );
+        assert_eq!(
+            keymap.action(&Key::Named(Named::Space), Modifiers::empty(), true),
+            Some(Action::Measure)
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use blueprint_core::domain::{Blueprint, Color, Constraint, LineStyle};
+use blueprint_core::error::{BlueprintError, Severity};
+use blueprint_core::lexer::{Span, Token, lexer};
+use blueprint_core::loader::load_blueprint_str;
+use blueprint_core::parser::KNOWN_ATTRIBUTES;
+use blueprint_core::source_map::SourceMap;
+use chumsky::Parser;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, Diagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, Location, MarkedString, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Uri,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Runs a language server over stdin/stdout, until the client shuts it down.
+pub fn run() -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["[".to_string(), ":".to_string(), ",".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })?;
+    connection.initialize(capabilities)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(notification) = server.handle_notification(notification) {
+                    connection
+                        .sender
+                        .send(Message::Notification(notification))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    /// Content of the opened documents.
+    documents: HashMap<Uri, String>,
+}
+
+impl Server {
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => serde_json::from_value(request.params)
+                .map(|params| serde_json::to_value(self.definition(params))),
+            HoverRequest::METHOD => serde_json::from_value(request.params)
+                .map(|params| serde_json::to_value(self.hover(params))),
+            Completion::METHOD => serde_json::from_value(request.params)
+                .map(|params| serde_json::to_value(self.completion(params))),
+            method => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request {method}"),
+                );
+            }
+        };
+
+        match result {
+            Ok(Ok(result)) => Response {
+                id,
+                result: Some(result),
+                error: None,
+            },
+            Ok(Err(e)) | Err(e) => Response::new_err(
+                id,
+                lsp_server::ErrorCode::InvalidParams as i32,
+                e.to_string(),
+            ),
+        }
+    }
+
+    /// Updates the documents, returning the diagnostics to publish if one changed.
+    fn handle_notification(&mut self, notification: Notification) -> Option<Notification> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents
+                    .insert(params.text_document.uri.clone(), params.text_document.text);
+                params.text_document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                // with full synchronization, the last change holds the whole document
+                let text = params.content_changes.into_iter().last()?.text;
+                self.documents
+                    .insert(params.text_document.uri.clone(), text);
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+                return None;
+            }
+            _ => return None,
+        };
+
+        let src = self.documents.get(&uri)?;
+        let (_, errors) = load_blueprint_str(src, &uri_to_path(&uri), false);
+        Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            PublishDiagnosticsParams {
+                diagnostics: diagnostics(src, &errors),
+                uri,
+                version: None,
+            },
+        ))
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let src = self.documents.get(&uri)?;
+        let offset = offset(src, params.text_document_position_params.position)?;
+
+        let span = definition(src, offset)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri.clone(),
+            range(src, span),
+        )))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let src = self.documents.get(&uri)?;
+        let offset = offset(src, params.text_document_position_params.position)?;
+
+        let (blueprint, _) = load_blueprint_str(src, &uri_to_path(&uri), false);
+        let (text, span) = hover(&blueprint?, src, offset)?;
+        Some(Hover {
+            contents: HoverContents::Scalar(MarkedString::String(text)),
+            range: Some(range(src, span)),
+        })
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<Vec<CompletionItem>> {
+        let src = self
+            .documents
+            .get(&params.text_document_position.text_document.uri)?;
+        let offset = offset(src, params.text_document_position.position)?;
+
+        Some(completion(src, offset))
+    }
+}
+
+fn uri_to_path(uri: &Uri) -> PathBuf {
+    PathBuf::from(
+        uri.path()
+            .as_estr()
+            .decode()
+            .into_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Converts the errors located in `src` to LSP diagnostics. Errors in included files are left
+/// out, as they do not belong to this document.
+fn diagnostics(src: &str, errors: &[BlueprintError]) -> Vec<Diagnostic> {
+    errors
+        .iter()
+        .filter(|error| error.path.is_none())
+        .map(|error| Diagnostic {
+            range: error.span.map(|span| range(src, span)).unwrap_or_default(),
+            severity: Some(match error.severity {
+                Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+            }),
+            source: Some("blueprint".to_string()),
+            message: error.message.clone(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// An occurrence of a tag in the source.
+struct TagOccurrence<'s> {
+    tag: &'s str,
+    span: Span,
+    /// Whether the tag is attached to a point, rather than referenced by `@#tag`, `#tag.x` or a
+    /// constraint, e.g. `perpendicular(#tag)`.
+    definition: bool,
+}
+
+fn tag_occurrences(src: &str) -> Vec<TagOccurrence<'_>> {
+    let tokens = lexer().parse(src).into_output().unwrap_or_default();
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| match token.node {
+            Token::Tag(tag) => Some(TagOccurrence {
+                tag,
+                span: token.span,
+                definition: (i == 0 || !matches!(tokens[i - 1].node, Token::At | Token::OpenParen))
+                    && tokens.get(i + 1).is_none_or(|next| next.node != Token::Dot),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tag_at(src: &str, offset: usize) -> Option<TagOccurrence<'_>> {
+    tag_occurrences(src)
+        .into_iter()
+        .find(|occurrence| occurrence.span.start <= offset && offset <= occurrence.span.end)
+}
+
+/// Finds the definition of the tag at `offset`: the last one before it, or the first one after
+/// it when the tag is referenced before being defined.
+fn definition(src: &str, offset: usize) -> Option<Span> {
+    let tag = tag_at(src, offset)?;
+    if tag.definition {
+        return Some(tag.span);
+    }
+
+    let definitions = tag_occurrences(src)
+        .into_iter()
+        .filter(|occurrence| occurrence.definition && occurrence.tag == tag.tag)
+        .collect::<Vec<_>>();
+    definitions
+        .iter()
+        .rev()
+        .find(|definition| definition.span.end <= tag.span.start)
+        .or(definitions.first())
+        .map(|definition| definition.span)
+}
+
+/// Describes the position the tag at `offset` resolves to.
+fn hover(blueprint: &Blueprint, src: &str, offset: usize) -> Option<(String, Span)> {
+    let tag = tag_at(src, offset)?;
+    let (_, point) = blueprint.tags_iter().find(|(name, _)| *name == tag.tag)?;
+
+    Some((
+        format!("#{}: ({}, {})", tag.tag, point.x, point.y),
+        tag.span,
+    ))
+}
+
+/// Completes attribute keys, colors, styles and constraints when `offset` is inside an attributes list.
+fn completion(src: &str, offset: usize) -> Vec<CompletionItem> {
+    let before = &src[..offset];
+    let Some(open) = before.rfind('[') else {
+        return Vec::new();
+    };
+    if before[open..].contains(']') {
+        return Vec::new();
+    }
+
+    let attribute = before[open + 1..].rsplit(',').next().unwrap_or_default();
+    let (labels, kind) = match attribute.split_once(':') {
+        None => (KNOWN_ATTRIBUTES.to_vec(), CompletionItemKind::PROPERTY),
+        Some((key, _)) if key.trim() == "color" => (
+            Color::NAMED.iter().filter_map(Color::name).collect(),
+            CompletionItemKind::COLOR,
+        ),
+        Some((key, _)) if key.trim() == "style" => (
+            LineStyle::ALL.iter().map(LineStyle::name).collect(),
+            CompletionItemKind::ENUM_MEMBER,
+        ),
+        Some((key, _)) if key.trim() == "constraint" => {
+            (Constraint::NAMES.to_vec(), CompletionItemKind::ENUM_MEMBER)
+        }
+        Some(_) => return Vec::new(),
+    };
+
+    labels
+        .into_iter()
+        .map(|label| CompletionItem {
+            label: label.to_string(),
+            kind: Some(kind),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn position(src: &str, offset: usize) -> Position {
+    let source_map = SourceMap::new(src);
+    let (line, _) = source_map.line_col(offset);
+    let line_start = source_map.line_start(line).unwrap_or_default();
+
+    Position::new(
+        line as u32 - 1,
+        src[line_start..offset].encode_utf16().count() as u32,
+    )
+}
+
+fn range(src: &str, span: Span) -> Range {
+    Range::new(position(src, span.start), position(src, span.end))
+}
+
+/// Converts an LSP position, whose character is counted in UTF-16 code units, to a byte offset.
+fn offset(src: &str, position: Position) -> Option<usize> {
+    let line_start = SourceMap::new(src).line_start(position.line as usize + 1)?;
+    let line = src[line_start..].split('\n').next().unwrap_or_default();
+
+    let mut character = 0;
+    for (i, c) in line.char_indices() {
+        if character >= position.character {
+            return Some(line_start + i);
+        }
+        character += c.len_utf16() as u32;
+    }
+    Some(line_start + line.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_definition() {
+        let src = "@#a move @5,5 #a @#a.x,0 #a @#a";
+        //         0         1         2         3
+
+        assert_eq!(definition(src, 2), Some(Span::from(14..16)));
+        assert_eq!(definition(src, 14), Some(Span::from(14..16)));
+        assert_eq!(definition(src, 19), Some(Span::from(14..16)));
+        assert_eq!(definition(src, 30), Some(Span::from(25..27)));
+        assert_eq!(definition(src, 6), None);
+    }
+
+    #[test]
+    fn test_hover() {
+        let src = "move @5,5 @10,20 #a";
+        let (blueprint, _) = load_blueprint_str(src, Path::new("test.bp"), false);
+
+        assert_eq!(
+            hover(&blueprint.unwrap(), src, 18),
+            Some(("#a: (5, 15)".to_string(), Span::from(17..19)))
+        );
+    }
+
+    #[test]
+    fn test_completion() {
+        let labels = |src: &str| {
+            completion(src, src.len())
+                .into_iter()
+                .map(|item| item.label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels("[co"), KNOWN_ATTRIBUTES);
+        assert_eq!(labels("[color:red, "), KNOWN_ATTRIBUTES);
+        assert_eq!(labels("[color: "), Color::NAMED.map(|c| c.name().unwrap()));
+        assert_eq!(labels("[style:"), ["solid", "dashed", "dotted"]);
+        assert_eq!(labels("[constraint:"), Constraint::NAMES);
+        assert_eq!(labels("[color:red] 5,"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_position_offset() {
+        let src = "ab\néc\nd";
+
+        assert_eq!(position(src, 6), Position::new(1, 2));
+        assert_eq!(offset(src, Position::new(1, 2)), Some(6));
+        assert_eq!(offset(src, Position::new(1, 9)), Some(6));
+        assert_eq!(offset(src, Position::new(2, 0)), Some(7));
+        assert_eq!(offset(src, Position::new(3, 0)), None);
+    }
+}
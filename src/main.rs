@@ -1,13 +1,23 @@
-mod domain;
-mod lexer;
-mod parser;
-mod ppm;
+mod config;
+mod daemon;
+mod keymap;
+mod lsp;
+mod project;
+mod serve;
 mod ui;
 
-use crate::domain::{Blueprint, Bound, Color, Draw, Edge, Point, Shape};
-use crate::parser::{CommandKind, Coord};
-use crate::ppm::PpmImage;
-use crate::ui::{AppEvent, Command};
+use crate::ui::{AppEvent, Command, WatchStatus};
+use blueprint_core::codegen::RustCode;
+use blueprint_core::domain::{Blueprint, Bound, Color, Coordinate, Point, Units};
+use blueprint_core::error::{BlueprintError, Error, Severity};
+use blueprint_core::export::ExportOptions;
+use blueprint_core::grammar::GrammarFormat;
+use blueprint_core::image::{BlueprintRaster, ImageEncoder, Monochrome};
+use blueprint_core::loader::{load_blueprint, load_blueprint_as, load_blueprint_str_as};
+use blueprint_core::ps::Paper;
+use blueprint_core::source_map::SourceMap;
+use blueprint_core::term::{TerminalGraphics, TerminalImage};
+use blueprint_core::{dump, error, export, formatter, grammar, info, lexer, lint};
 use futures::SinkExt;
 use futures::Stream;
 use futures::channel::mpsc;
@@ -15,194 +25,812 @@ use futures::channel::mpsc::Receiver;
 use futures::{StreamExt, select};
 use iced_futures::stream;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::exit;
-use std::{env, fs};
-
-fn main() {
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use std::{env, fs, io};
+use terminal_size::{Height, Width, terminal_size};
+
+fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
-    let in_filename = args.get(1).unwrap_or_else(|| {
-        eprintln!("Usage: {} <filename>", args[0]);
-        exit(1);
+    let result = match args.get(1).map(String::as_str) {
+        Some("fmt") => fmt(&args),
+        Some("check") => check(&args),
+        Some("dump") => dump(&args),
+        Some("tokens") => tokens(&args),
+        Some("grammar") => grammar(&args),
+        Some("codegen") => codegen(&args),
+        Some("info") => info(&args),
+        Some("render") => render_all(&args),
+        Some("watch") => watch(&args),
+        Some("serve") => serve(&args),
+        Some("build") => build(&args),
+        Some("daemon") => daemon(&args),
+        Some("lsp") => {
+            if let Err(e) = lsp::run() {
+                eprintln!("Language server failed: {e}");
+                return ExitCode::FAILURE;
+            }
+            Ok(())
+        }
+        _ => show(&args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `blueprint <options> <filename>...`: exports the files, then shows them in the UI, each in its
+/// own tab.
+fn show(args: &[String]) -> Result<(), Error> {
+    let export = export_args(&args[1..])?;
+    let in_filenames = filenames(&args[1..]);
+    if in_filenames.is_empty() {
+        return Err(usage(format!("{} {EXPORT_USAGE} <filename>...", args[0])));
+    }
+    if in_filenames.len() > 1 && export.output.as_deref().is_some_and(|o| !is_directory(o)) {
+        return Err(Error::InvalidArgument(
+            "--output must be a directory when showing several files".to_string(),
+        ));
+    }
+    for in_filename in &in_filenames {
+        render(in_filename, &export)?;
+    }
+
+    // in a pipeline or a terminal, there is no file to show
+    if in_filenames.iter().any(|in_filename| *in_filename == "-")
+        || export.output.as_deref() == Some("-")
+        || export.terminal.is_some()
+    {
+        return Ok(());
+    }
+    ui::show(in_filenames.into_iter().map(PathBuf::from).collect())
+        .map_err(|error| Error::Ui(error.to_string()))
+}
+
+/// Options shared by the commands exporting blueprints.
+const EXPORT_USAGE: &str = "[--strict] [-o <filename> | --output=<filename>] [--format=ppm|png|bmp|pam|svg|dxf|gcode|hpgl|ps] [--background=<color>] [--ascii] [--scale=<factor>] [--supersample=<factor>] [--region=x1,y1,x2,y2] [--layers=<name>,...] [--feed-rate=<mm/min>] [--paper=a4|a3|letter|legal] [--tags] [--dimensions] [--split-layers] [--mono[=dither]] [--term[=blocks|sixel]]";
+
+fn usage(usage: String) -> Error {
+    Error::InvalidArgument(format!("Usage: {usage}"))
+}
+
+/// Export requested on the command line.
+struct ExportArgs {
+    strict: bool,
+    split_layers: bool,
+    output: Option<String>,
+    region: Option<(Point<f64>, Point<f64>)>,
+    /// Names of the layers drawn, all the edges being drawn when `None`.
+    layers: Option<Vec<String>>,
+    /// Graphics the blueprint is drawn with in the terminal, instead of being written to a file.
+    terminal: Option<TerminalGraphics>,
+    options: ExportOptions,
+}
+
+/// Parses the export options among `args`.
+fn export_args(args: &[String]) -> Result<ExportArgs, Error> {
+    let strict = args.iter().any(|arg| arg == "--strict");
+    let ascii = args.iter().any(|arg| arg == "--ascii");
+    let tags = args.iter().any(|arg| arg == "--tags");
+    let dimensions = args.iter().any(|arg| arg == "--dimensions");
+    let split_layers = args.iter().any(|arg| arg == "--split-layers");
+    let monochrome = args.iter().find_map(|arg| match arg.as_str() {
+        "--mono" => Some(Monochrome::Threshold),
+        "--mono=dither" => Some(Monochrome::Dither),
+        _ => None,
     });
-    let out_filename = format!(
-        "{}.ppm",
-        in_filename
-            .rsplit_once(".")
-            .unwrap_or_else(|| {
-                eprintln!("<filename> must end with .bp");
-                exit(1)
+    let terminal = args
+        .iter()
+        .find_map(|arg| match arg.as_str() {
+            "--term" => Some(Ok(terminal_graphics())),
+            "--term=blocks" => Some(Ok(TerminalGraphics::Blocks)),
+            "--term=sixel" => Some(Ok(TerminalGraphics::Sixel)),
+            arg if arg.starts_with("--term=") => Some(Err(Error::InvalidArgument(format!(
+                "--term must be blocks or sixel, got {}",
+                &arg["--term=".len()..]
+            )))),
+            _ => None,
+        })
+        .transpose()?;
+    let output = match args.iter().position(|arg| arg == "-o") {
+        Some(i) => Some(args.get(i + 1).map(String::as_str).ok_or_else(|| {
+            Error::InvalidArgument("-o must be followed by the output file".to_string())
+        })?),
+        None => args.iter().find_map(|arg| arg.strip_prefix("--output=")),
+    };
+    // the format defaults to the extension of the output file
+    let format = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--format="))
+        .or_else(|| {
+            output
+                .filter(|output| !is_directory(output))
+                .and_then(|output| Path::new(output).extension()?.to_str())
+        })
+        .unwrap_or("ppm");
+    let background = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--background="))
+        .map(|background| {
+            Color::try_from(background).map_err(|_| {
+                Error::InvalidArgument(format!("Unknown background color {background}"))
             })
-            .0
-    );
+        })
+        .transpose()?
+        .unwrap_or(Color::White);
+    let scale = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--scale="))
+        .map(|scale| {
+            scale
+                .parse::<f32>()
+                .ok()
+                .filter(|scale| *scale > 0.)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "--scale must be a positive number, got {scale}"
+                    ))
+                })
+        })
+        .transpose()?
+        .unwrap_or(1.);
+    let supersample = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--supersample="))
+        .map(|supersample| {
+            supersample
+                .parse::<usize>()
+                .ok()
+                .filter(|supersample| *supersample > 0)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "--supersample must be a positive integer, got {supersample}"
+                    ))
+                })
+        })
+        .transpose()?
+        .unwrap_or(1);
+    let feed_rate = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--feed-rate="))
+        .map(|feed_rate| {
+            feed_rate
+                .parse::<f32>()
+                .ok()
+                .filter(|feed_rate| *feed_rate > 0.)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "--feed-rate must be a positive number, got {feed_rate}"
+                    ))
+                })
+        })
+        .transpose()?
+        .unwrap_or(1000.);
+    let paper = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--paper="))
+        .map(|paper| {
+            Paper::try_from(paper).map_err(|_| {
+                Error::InvalidArgument(format!(
+                    "--paper must be a4, a3, letter or legal, got {paper}"
+                ))
+            })
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let region = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--region="))
+        .map(|region| {
+            let coordinates = region
+                .split(',')
+                .map(|coordinate| coordinate.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>();
+            match coordinates.as_deref() {
+                Ok(&[x1, y1, x2, y2]) => Ok((Point::from((x1, y1)), Point::from((x2, y2)))),
+                _ => Err(Error::InvalidArgument(format!(
+                    "--region must be of the form x1,y1,x2,y2, got {region}"
+                ))),
+            }
+        })
+        .transpose()?;
+    let layers = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--layers="))
+        .map(|layers| layers.split(',').map(str::to_string).collect());
+
+    let options = ExportOptions {
+        format: format.to_string(),
+        background,
+        scale,
+        supersample,
+        ascii,
+        feed_rate,
+        tag_markers: tags,
+        dimensions,
+        monochrome,
+        paper,
+    };
+    options.validate()?;
+
+    Ok(ExportArgs {
+        strict,
+        split_layers,
+        output: output.map(str::to_string),
+        region,
+        layers,
+        terminal,
+        options,
+    })
+}
 
-    let blueprint = load_blueprint(Path::new(in_filename)).unwrap();
+/// Loads `in_filename` and exports it as requested by `export`, reporting the problems met to
+/// stderr.
+fn render(in_filename: &str, export: &ExportArgs) -> Result<(), Error> {
+    // `-` stands for stdout, where blueprints read from stdin are written by default
+    let out_filename = match export.output.as_deref() {
+        Some(output) if is_directory(output) => {
+            let name = Path::new(in_filename)
+                .file_name()
+                .filter(|_| in_filename != "-")
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "{in_filename} has no file name to write to {output}"
+                    ))
+                })?;
+            fs::create_dir_all(output).map_err(|source| Error::Write {
+                path: PathBuf::from(output),
+                source,
+            })?;
+            Path::new(output)
+                .join(name)
+                .with_extension(&export.options.format)
+        }
+        Some(output) => PathBuf::from(output),
+        None if in_filename == "-" => PathBuf::from("-"),
+        None => Path::new(in_filename).with_extension(&export.options.format),
+    };
+
+    let (path, stdin, blueprint, errors) = load::<f64>(in_filename, export.strict)?;
+    error::report(path, &source(path, stdin, &errors), &errors);
+    let blueprint = match blueprint {
+        Some(blueprint) if !export.strict || !errors.iter().any(BlueprintError::is_error) => {
+            blueprint
+        }
+        _ => {
+            return Err(Error::Invalid {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+    };
+    let blueprint = select(blueprint, export);
 
-    let canvas = Canvas::from(blueprint).pad(50, 50);
+    if let Some(graphics) = export.terminal {
+        return preview(&blueprint.cast(), graphics, &export.options);
+    }
+    if out_filename == Path::new("-") {
+        if export.split_layers {
+            return Err(Error::InvalidArgument(
+                "Layers cannot be split when writing to stdout".to_string(),
+            ));
+        }
+        return export::export_to(&blueprint, &mut io::stdout().lock(), &export.options);
+    }
 
-    PpmImage::from(&canvas)
-        .write_to_file(&out_filename)
-        .unwrap();
+    if export.split_layers {
+        export::export_layers(&blueprint, &out_filename, &export.options).map(|_| ())
+    } else {
+        export::export(&blueprint, &out_filename, &export.options)
+    }
+}
 
-    ui::show(PathBuf::from(in_filename), Blueprint::default()).expect("can launch UI");
+/// Part of `blueprint` exported as requested by `export`: the edges of its layers, within its
+/// region.
+fn select(blueprint: Blueprint<f64>, export: &ExportArgs) -> Blueprint<f64> {
+    let blueprint = match &export.layers {
+        Some(layers) => blueprint.layers_only(layers),
+        None => blueprint,
+    };
+    match export.region {
+        Some((corner, opposite)) => blueprint.crop(corner, opposite),
+        None => blueprint,
+    }
 }
 
-struct BlueprintLoader<'s> {
-    points: HashMap<&'s str, Point>,
-    last_point: Option<Point>,
-    stack: Vec<Point>,
-    blueprint: Blueprint,
+/// Draws `blueprint` in the terminal with `graphics`, composed according to its layers and
+/// scaled to fit in it.
+fn preview(
+    blueprint: &Blueprint,
+    graphics: TerminalGraphics,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    let blueprint = blueprint.composed();
+    let (columns, rows) = terminal_size()
+        .map(|(Width(columns), Height(rows))| (columns as usize, rows as usize))
+        .unwrap_or((80, 24));
+    // a row is kept for the prompt; sixels are sized for the usual 8x16 pixels characters
+    let (width, height, padding) = match graphics {
+        TerminalGraphics::Blocks => (columns, rows.saturating_sub(1) * 2, 1),
+        TerminalGraphics::Sixel => (columns * 8, rows.saturating_sub(1) * 16, 8),
+    };
+
+    let (_, bottom_right) = blueprint.boundaries().unwrap_or_default();
+    let available = |pixels: usize| pixels.saturating_sub(2 * padding + 1).max(1) as f32;
+    let scale =
+        (available(width) / bottom_right.x.max(1.)).min(available(height) / bottom_right.y.max(1.));
+    let raster = BlueprintRaster::new(blueprint.scale(scale), options.background, 1, padding)
+        .monochrome(options.monochrome);
+
+    TerminalImage::from(&raster)
+        .graphics(graphics)
+        .write_to(&mut io::stdout().lock())?;
+    Ok(())
 }
 
-impl<'s> BlueprintLoader<'s> {
-    pub fn new() -> Self {
-        Self {
-            last_point: Default::default(),
-            points: Default::default(),
-            stack: Default::default(),
-            blueprint: Default::default(),
+/// Sixel graphics when the terminal is known to support them, half blocks otherwise.
+fn terminal_graphics() -> TerminalGraphics {
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    if term.contains("sixel")
+        || ["mlterm", "foot", "yaft-256color"].contains(&term.as_str())
+        || ["WezTerm", "mintty"].contains(&program.as_str())
+    {
+        TerminalGraphics::Sixel
+    } else {
+        TerminalGraphics::Blocks
+    }
+}
+
+/// Arguments of export commands that are not options, `-o` being followed by its value.
+fn filenames(args: &[String]) -> Vec<&String> {
+    let mut filenames = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-o" {
+            args.next();
+        } else if !arg.starts_with("--") {
+            filenames.push(arg);
         }
     }
+    filenames
+}
 
-    pub fn exec(
-        mut self,
-        commands: &'s [parser::Command],
-        lines: &[usize],
-    ) -> Result<Blueprint, ()> {
-        // self.nodes.reserve(commands.len());
+/// Whether `output` is a directory the exports are written to, rather than a file: an existing
+/// directory, or a path ending with a separator to create it.
+fn is_directory(output: &str) -> bool {
+    output.ends_with('/')
+        || output.ends_with(std::path::MAIN_SEPARATOR)
+        || Path::new(output).is_dir()
+}
 
-        self.exec_block(commands, lines)?;
+/// Reads the source of `in_filename`, `-` standing for stdin. Returns the path the source is
+/// reported at.
+fn read(in_filename: &str) -> Result<(&Path, String), Error> {
+    let (path, src) = if in_filename == "-" {
+        let mut src = String::new();
+        (
+            Path::new("<stdin>"),
+            io::stdin().read_to_string(&mut src).map(|_| src),
+        )
+    } else {
+        let path = Path::new(in_filename);
+        (path, fs::read_to_string(path))
+    };
+
+    let src = src.map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok((path, src))
+}
 
-        self.blueprint.translate_to_origin();
-        Ok(self.blueprint)
+/// Blueprint loaded by [`load`]: the path its problems are reported at, the source read from
+/// stdin, the blueprint if it could be loaded, and its problems.
+type Loaded<'a, T> = (
+    &'a Path,
+    Option<String>,
+    Option<Blueprint<T>>,
+    Vec<BlueprintError>,
+);
+
+/// Loads the blueprint of `in_filename`, `-` standing for stdin, with coordinates of type `T`.
+/// Files are loaded by [`load_blueprint_as`], which streams the large ones rather than holding
+/// their source in memory.
+fn load<T: Coordinate>(in_filename: &str, strict: bool) -> Result<Loaded<'_, T>, Error> {
+    if in_filename == "-" {
+        let (path, src) = read(in_filename)?;
+        let (blueprint, errors) = load_blueprint_str_as(&src, path, strict);
+        return Ok((path, Some(src), blueprint, errors));
     }
 
-    fn exec_block(
-        &mut self,
-        commands: &'s [parser::Command],
-        newline_offsets: &[usize],
-    ) -> Result<(), ()> {
-        if commands.is_empty() {
-            return Ok(());
-        }
-        let mut edges = Vec::with_capacity(commands.len() - 1);
+    // files that cannot be opened fail the command, as when they are read whole
+    let path = Path::new(in_filename);
+    File::open(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let (blueprint, errors) = load_blueprint_as(path, strict);
+    Ok((path, None, blueprint, errors))
+}
 
-        for command in commands {
-            let (draw, to, tag) = match &command.kind {
-                CommandKind::Move(Coord::Absolute(x, y, tag)) => {
-                    let to = Point::new(*x as f32, *y as f32);
-                    (None, to, *tag)
-                }
-                CommandKind::Move(Coord::Relative(dx, dy, tag)) => {
-                    let from = self.last_point.unwrap_or_default();
-                    let to = from.add(*dx as f32, *dy as f32);
-                    (None, to, *tag)
-                }
-                CommandKind::Move(Coord::Reference(tag)) => {
-                    let to = match self.points.get(*tag) {
-                        None => {
-                            eprintln!("#{tag} not found",);
-                            return Err(());
-                        }
-                        Some(p) => *p,
-                    };
-                    (None, to, None)
-                }
-                CommandKind::Draw(Coord::Absolute(x, y, tag), color) => {
-                    let from = self.last_point.unwrap_or_default();
-                    let to = Point::new(*x as f32, *y as f32);
-                    (Some((from, color)), to, *tag)
-                }
-                CommandKind::Draw(Coord::Relative(dx, dy, tag), color) => {
-                    let from = self.last_point.unwrap_or_default();
-                    let to = from.add(*dx as f32, *dy as f32);
-                    (Some((from, color)), to, *tag)
-                }
-                CommandKind::Draw(Coord::Reference(tag), color) => {
-                    let from = self.last_point.unwrap_or_default();
-                    let to = match self.points.get(tag) {
-                        None => {
-                            eprintln!("#{tag} not found",);
-                            return Err(());
-                        }
-                        Some(p) => *p,
-                    };
-                    (Some((from, color)), to, None)
-                }
-                CommandKind::Nested(commands) => {
-                    if let Some(last_point) = self.last_point {
-                        self.stack.push(last_point)
-                    }
+/// Source the problems met loading the blueprint of `path` point into: the one read from stdin,
+/// or else the file, only read again when there are problems to report.
+fn source(path: &Path, stdin: Option<String>, errors: &[BlueprintError]) -> String {
+    match stdin {
+        Some(src) => src,
+        None if errors.is_empty() => String::new(),
+        None => fs::read_to_string(path).unwrap_or_default(),
+    }
+}
 
-                    self.exec_block(commands, newline_offsets)?;
+/// `blueprint render [--jobs=<n>] <options> <filename>...`: exports each file next to it, `n` at
+/// a time, without launching the UI. Fails if any of them could not be exported.
+fn render_all(args: &[String]) -> Result<(), Error> {
+    let export = export_args(&args[2..])?;
+    let jobs = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--jobs="))
+        .map(|jobs| {
+            jobs.parse::<usize>()
+                .ok()
+                .filter(|jobs| *jobs > 0)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!("--jobs must be a positive integer, got {jobs}"))
+                })
+        })
+        .transpose()?
+        .unwrap_or(1);
+    let in_filenames = filenames(&args[2..]);
+    if in_filenames.is_empty() {
+        return Err(usage(format!(
+            "{} render [--jobs=<n>] {EXPORT_USAGE} <filename>...",
+            args[0]
+        )));
+    }
+    if in_filenames.len() > 1 && export.output.as_deref().is_some_and(|o| !is_directory(o)) {
+        return Err(Error::InvalidArgument(
+            "--output must be a directory when rendering several files".to_string(),
+        ));
+    }
 
-                    if let Some(last_point) = self.stack.pop() {
-                        self.last_point.replace(last_point);
+    // each worker takes the next file not rendered yet
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(in_filenames.len()) {
+            scope.spawn(|| {
+                while let Some(in_filename) = in_filenames.get(next.fetch_add(1, Ordering::Relaxed))
+                {
+                    if let Err(e) = render(in_filename, &export) {
+                        eprintln!("{e}");
+                        failed.fetch_add(1, Ordering::Relaxed);
                     }
-
-                    continue;
                 }
-            };
+            });
+        }
+    });
 
-            if let Some((from, color)) = draw {
-                let line = newline_offsets
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, offset)| {
-                        if *offset > command.src_index {
-                            Some(i)
-                        } else {
-                            None
-                        }
-                    })
-                    .next()
-                    .unwrap_or_default()
-                    + 1;
+    match failed.into_inner() {
+        0 => Ok(()),
+        failed => Err(Error::Exports {
+            failed,
+            total: in_filenames.len(),
+        }),
+    }
+}
 
-                let edge = Edge::new_from_points(from, to, *color, line);
-                edges.push(edge);
+/// `blueprint watch <options> <filename>`: exports the file, then exports it again every time it
+/// is saved, without launching the UI.
+fn watch(args: &[String]) -> Result<(), Error> {
+    let export = export_args(&args[2..])?;
+    let in_filename = *filenames(&args[2..])
+        .first()
+        .ok_or_else(|| usage(format!("{} watch {EXPORT_USAGE} <filename>", args[0])))?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher =
+        FileWatcher::from(RecommendedWatcher::new(tx, Config::default()).map_err(|e| {
+            Error::Watch {
+                path: PathBuf::from(in_filename),
+                source: io::Error::other(e),
             }
+        })?);
+    watcher.watch(PathBuf::from(in_filename))?;
+
+    let render = || match render(in_filename, &export) {
+        Ok(()) => eprintln!("Exported {in_filename}"),
+        Err(e) => eprintln!("{e}"),
+    };
+    render();
+    while let Ok(event) = rx.recv() {
+        use notify::EventKind;
+
+        // editors saving to a temporary file renamed over the blueprint create it anew
+        let Ok(event) = event else { continue };
+        if !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+            || !event.paths.iter().any(|path| watcher.is_watched(path))
+        {
+            continue;
+        }
 
-            if let Some(tag) = tag {
-                self.points.insert(tag, to);
-            }
+        // a save often comes as several events, the file is exported once they are over
+        while rx.recv_timeout(Duration::from_millis(100)).is_ok() {}
+        render();
+    }
+    Ok(())
+}
 
-            self.last_point.replace(to);
+/// `blueprint serve [--address=<host:port>] <filename>`: serves a page showing the file, updated
+/// every time it is saved.
+fn serve(args: &[String]) -> Result<(), Error> {
+    let address = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--address="))
+        .unwrap_or("127.0.0.1:8080");
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| {
+            usage(format!(
+                "{} serve [--address=<host:port>] <filename>",
+                args[0]
+            ))
+        })?;
+
+    serve::run(Path::new(in_filename), address)
+}
+
+/// `blueprint build [<manifest>]`: exports the members of the project to its targets, the
+/// manifest being `blueprint.toml` in the current directory by default.
+fn build(args: &[String]) -> Result<(), Error> {
+    let manifest = args.get(2).map_or(project::MANIFEST, String::as_str);
+    project::Project::load(Path::new(manifest))?.build()
+}
+
+/// `blueprint daemon [--address=<host:port>]`: renders the blueprints requested by the clients
+/// connecting to the address.
+fn daemon(args: &[String]) -> Result<(), Error> {
+    let address = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--address="))
+        .unwrap_or("127.0.0.1:8081");
+    daemon::run(address)
+}
+
+/// `blueprint fmt [--stdout] <filename>`: formats the file in place, or prints it to stdout.
+fn fmt(args: &[String]) -> Result<(), Error> {
+    let stdout = args.iter().skip(2).any(|arg| arg == "--stdout");
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| usage(format!("{} fmt [--stdout] <filename>", args[0])))?;
+
+    let (path, src) = read(in_filename)?;
+    match formatter::format(&src) {
+        Ok(formatted) if stdout => print!("{formatted}"),
+        Ok(formatted) => fs::write(path, formatted).map_err(|source| Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })?,
+        Err(errors) => {
+            error::report(path, &src, &errors);
+            return Err(Error::Invalid {
+                path: path.to_path_buf(),
+                errors,
+            });
         }
+    }
+    Ok(())
+}
 
-        self.blueprint.push(Shape::from(edges));
+/// `blueprint check [--strict] [--json] <filename>`: reports the problems of the file and the
+/// suspicious geometry it describes, failing if any of them is an error.
+fn check(args: &[String]) -> Result<(), Error> {
+    let strict = args.iter().skip(2).any(|arg| arg == "--strict");
+    let json = args.iter().skip(2).any(|arg| arg == "--json");
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| usage(format!("{} check [--strict] [--json] <filename>", args[0])))?;
+
+    let (path, stdin, blueprint, mut errors) = load(in_filename, strict)?;
+    if let Some(blueprint) = blueprint {
+        errors.extend(lint::lint(&blueprint).into_iter().map(|mut warning| {
+            if strict {
+                warning.severity = Severity::Error;
+            }
+            warning
+        }));
+    }
 
-        Ok(())
+    let src = source(path, stdin, &errors);
+    if json {
+        error::report_json(path, &src, &errors);
+    } else {
+        error::report(path, &src, &errors);
     }
+    if errors.iter().any(BlueprintError::is_error) {
+        return Err(Error::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        });
+    }
+    Ok(())
+}
+
+/// `blueprint dump <filename>`: prints the parsed commands and the resolved blueprint.
+fn dump(args: &[String]) -> Result<(), Error> {
+    let in_filename = args
+        .get(2)
+        .ok_or_else(|| usage(format!("{} dump <filename>", args[0])))?;
+
+    let (path, stdin, blueprint, errors) = load(in_filename, false)?;
+    // the commands are dumped from the whole source
+    let src = match stdin {
+        Some(src) => src,
+        None => fs::read_to_string(path).map_err(|source| Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?,
+    };
+    error::report(path, &src, &errors);
+    print!("{}", dump::dump(&src, blueprint.as_ref()));
+    Ok(())
 }
 
-// todo return a String as error and display it on the UI
-fn load_blueprint(path: &Path) -> Result<Blueprint, ()> {
-    let src = fs::read_to_string(path).map_err(|e| {
-        eprintln!("Could not open {}: {}", path.display(), e);
+/// `blueprint tokens [--json] <filename>`: prints the tokens and comments of the file, with their
+/// kind and location, for syntax highlighters and editor plugins.
+fn tokens(args: &[String]) -> Result<(), Error> {
+    let json = args.iter().skip(2).any(|arg| arg == "--json");
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| usage(format!("{} tokens [--json] <filename>", args[0])))?;
+
+    let (path, src) = read(in_filename)?;
+    let (lexemes, errors) = lexer::tokenize(&src);
+    error::report(path, &src, &errors);
+    let source_map = SourceMap::new(&src);
+    for lexeme in &lexemes {
+        let token = lexer::to_json(&src, &source_map, lexeme);
+        if json {
+            println!("{token}");
+        } else {
+            println!(
+                "{}:{} {} {}",
+                token["line"],
+                token["column"],
+                lexeme.node.kind(),
+                &src[lexeme.span.into_range()]
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `blueprint grammar --format=textmate|vim`: prints the syntax-highlighting definition of
+/// blueprint sources for the editor, generated from the tokens of the lexer.
+fn grammar(args: &[String]) -> Result<(), Error> {
+    let format = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--format="))
+        .ok_or_else(|| usage(format!("{} grammar --format=textmate|vim", args[0])))?;
+    let format = GrammarFormat::try_from(format).map_err(|_| {
+        Error::InvalidArgument(format!("--format must be textmate or vim, got {format}"))
     })?;
 
-    let newline_offsets = src
-        .chars()
-        .enumerate()
-        .filter_map(|(i, c)| if c == '\n' { Some(i) } else { None })
-        .collect::<Vec<usize>>();
+    print!("{}", grammar::grammar(format));
+    Ok(())
+}
 
-    let commands = parser::parse(src.as_str(), path);
+/// `blueprint codegen [--function=<name>] <filename>`: prints Rust code building the blueprint of
+/// the file with the builder of blueprint-core.
+fn codegen(args: &[String]) -> Result<(), Error> {
+    let function = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--function="))
+        .unwrap_or("blueprint");
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| {
+            usage(format!(
+                "{} codegen [--function=<name>] <filename>",
+                args[0]
+            ))
+        })?;
+
+    let (path, stdin, blueprint, errors) = load(in_filename, false)?;
+    error::report(path, &source(path, stdin, &errors), &errors);
+    match blueprint {
+        Some(blueprint) if !errors.iter().any(BlueprintError::is_error) => {
+            print!("{}", RustCode::from(&blueprint).function(function));
+            Ok(())
+        }
+        _ => Err(Error::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        }),
+    }
+}
 
-    BlueprintLoader::new().exec(&commands, &newline_offsets)
+/// `blueprint info [--units=<length>] <filename>`: prints the size of the blueprint of the file,
+/// its number of edges and their length, and the area of each of its rooms, in real-world units
+/// when the length of a blueprint unit is given.
+fn info(args: &[String]) -> Result<(), Error> {
+    let units = args
+        .iter()
+        .skip(2)
+        .find_map(|arg| arg.strip_prefix("--units="))
+        .map(|units| {
+            Units::try_from(units).map_err(|_| {
+                Error::InvalidArgument(format!(
+                    "--units must be a length in mm, cm, m, in or ft, e.g. 5cm, got {units}"
+                ))
+            })
+        })
+        .transpose()?;
+    let in_filename = args
+        .iter()
+        .skip(2)
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| usage(format!("{} info [--units=<length>] <filename>", args[0])))?;
+
+    let (path, stdin, blueprint, errors) = load(in_filename, false)?;
+    error::report(path, &source(path, stdin, &errors), &errors);
+    match blueprint {
+        Some(mut blueprint) if !errors.iter().any(BlueprintError::is_error) => {
+            blueprint.set_units(units);
+            print!("{}", info::info(&blueprint));
+            Ok(())
+        }
+        _ => Err(Error::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        }),
+    }
 }
 
 pub fn open_and_watch_file() -> impl Stream<Item = AppEvent> {
     // https://docs.rs/iced/latest/iced/struct.Subscription.html
     // https://github.com/notify-rs/notify/blob/main/examples/async_monitor.rs
     stream::channel(100, |mut output| async move {
-        let (watcher, mut fs_events_rx) = async_watcher().unwrap();
+        let (watcher, mut fs_events_rx) = match async_watcher() {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                let error = BlueprintError::error(format!("Could not watch files: {e}"));
+                let _ = output.send(AppEvent::Failed(error)).await;
+                return;
+            }
+        };
         let mut watcher = FileWatcher::from(watcher);
 
+        // sending fails once the UI is closed
         let (ui_commands_tx, mut ui_commands_rx) = mpsc::channel(100);
-        output.send(AppEvent::Ready(ui_commands_tx)).await.unwrap();
+        if output.send(AppEvent::Ready(ui_commands_tx)).await.is_err() {
+            return;
+        }
 
         loop {
             let mut next_ui_command = ui_commands_rx.next();
@@ -210,14 +838,18 @@ pub fn open_and_watch_file() -> impl Stream<Item = AppEvent> {
             select! {
                 fs_event = next_fs_event => {
                     if let Some(Ok(fs_event)) = fs_event
-                        && let Some(event) = handle_fs_event(fs_event, &watcher) {
-                            output.send(event).await.unwrap();
+                        && let Some(event) = handle_fs_event(fs_event, &watcher)
+                        && output.send(event).await.is_err() {
+                            return;
                     }
                 },
                 ui_command = next_ui_command => {
-                    if let Some(ui_command) = ui_command
-                        && let Some(event) = handle_ui_command(ui_command, &mut watcher) {
-                            output.send(event).await.unwrap();
+                    if let Some(ui_command) = ui_command {
+                        for event in handle_ui_command(ui_command, &mut watcher) {
+                            if output.send(event).await.is_err() {
+                                return;
+                            }
+                        }
                     }
                 },
             }
@@ -232,8 +864,9 @@ fn async_watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Resul
 
     let watcher = RecommendedWatcher::new(
         move |res| {
+            // the events are dropped once the UI is closed
             futures::executor::block_on(async {
-                tx.send(res).await.unwrap();
+                let _ = tx.send(res).await;
             })
         },
         Config::default(),
@@ -247,110 +880,140 @@ fn handle_fs_event(event: notify::Event, watcher: &FileWatcher) -> Option<AppEve
 
     let path = event
         .paths
-        .into_iter()
-        .find(|path| watcher.is_watched(path))?;
+        .iter()
+        .find_map(|path| watcher.opened_path(path))?;
 
     match &event.kind {
-        EventKind::Modify(ModifyKind::Data(data)) => {
-            load_blueprint(&path).ok().map(AppEvent::BlueprintUpdated)
+        EventKind::Modify(ModifyKind::Data(_)) => {
+            let (blueprint, errors) = load_blueprint(path, false);
+            Some(AppEvent::BlueprintUpdated(
+                path.to_path_buf(),
+                blueprint,
+                errors,
+            ))
         }
         _ => None,
     }
 }
 
-fn handle_ui_command(cmd: Command, watcher: &mut FileWatcher) -> Option<AppEvent> {
+fn handle_ui_command(cmd: Command, watcher: &mut FileWatcher) -> Vec<AppEvent> {
     match cmd {
         Command::OpenFile(path) => {
-            let blueprint = load_blueprint(&path).unwrap();
-            watcher.watch(path);
-            Some(AppEvent::BlueprintUpdated(blueprint))
+            let (blueprint, mut errors) = load_blueprint(&path, false);
+            let status = match watcher.watch(path.clone()) {
+                Ok(()) => WatchStatus::Watching,
+                Err(e) => {
+                    // a file that could not be read is already reported
+                    if blueprint.is_some() {
+                        errors.push(BlueprintError::warning(e.to_string()));
+                    }
+                    WatchStatus::Failed(e.to_string())
+                }
+            };
+            vec![
+                AppEvent::BlueprintUpdated(path.clone(), blueprint, errors),
+                AppEvent::Watched(path, status),
+            ]
+        }
+        // a file that could not be watched, e.g. missing when opened, is watched again
+        Command::Reload(path) if !watcher.is_opened(&path) => {
+            handle_ui_command(Command::OpenFile(path), watcher)
+        }
+        Command::Reload(path) => {
+            let (blueprint, errors) = load_blueprint(&path, false);
+            vec![AppEvent::BlueprintUpdated(path, blueprint, errors)]
+        }
+        Command::CloseFile(path) => {
+            if let Err(e) = watcher.unwatch(&path) {
+                eprintln!("{e}");
+            }
+            Vec::new()
         }
     }
 }
 
 struct FileWatcher {
     inner: RecommendedWatcher,
-    path: Option<PathBuf>,
+    /// Canonical paths of the files watched, with the paths they were opened with.
+    paths: Vec<(PathBuf, PathBuf)>,
 }
 
 impl FileWatcher {
-    fn watch(&mut self, path: PathBuf) {
-        let path = path.canonicalize().unwrap();
-        let parent_path = path.parent().unwrap().to_path_buf();
-
-        if self.path.is_some() {
-            self.inner.unwatch(&parent_path).unwrap();
+    /// Watches `path`, in addition to the files watched so far.
+    fn watch(&mut self, path: PathBuf) -> Result<(), Error> {
+        let canonical_path = path.canonicalize().map_err(|source| Error::Read {
+            path: path.clone(),
+            source,
+        })?;
+
+        // the directory is watched, as editors often replace the file rather than writing to it
+        let directory = watched_directory(&canonical_path);
+        if !self
+            .paths
+            .iter()
+            .any(|(watched, _)| watched_directory(watched) == directory)
+        {
+            self.inner
+                .watch(directory, RecursiveMode::NonRecursive)
+                .map_err(|e| Error::Watch {
+                    path: canonical_path.clone(),
+                    source: io::Error::other(e),
+                })?;
         }
 
-        self.inner
-            .watch(&parent_path, RecursiveMode::NonRecursive)
-            .unwrap();
-
-        self.path = Some(path);
+        self.paths.push((canonical_path, path));
+        Ok(())
     }
 
-    fn is_watched(&self, path: &Path) -> bool {
-        match &self.path {
-            None => false,
-            Some(watched_path) => watched_path == path,
+    /// Stops watching the file opened as `path`.
+    fn unwatch(&mut self, path: &Path) -> Result<(), Error> {
+        let Some(index) = self.paths.iter().position(|(_, opened)| opened == path) else {
+            return Ok(());
+        };
+        let (canonical_path, _) = self.paths.remove(index);
+
+        let directory = watched_directory(&canonical_path);
+        if !self
+            .paths
+            .iter()
+            .any(|(watched, _)| watched_directory(watched) == directory)
+        {
+            self.inner.unwatch(directory).map_err(|e| Error::Watch {
+                path: canonical_path.clone(),
+                source: io::Error::other(e),
+            })?;
         }
+        Ok(())
     }
-}
-
-impl From<RecommendedWatcher> for FileWatcher {
-    fn from(inner: RecommendedWatcher) -> Self {
-        Self { inner, path: None }
-    }
-}
-
-struct Canvas {
-    width: usize,
-    height: usize,
-    pixels: Vec<Color>,
-}
-
-impl From<Blueprint> for Canvas {
-    fn from(blueprint: Blueprint) -> Self {
-        let boundaries = blueprint.boundaries();
-        let (width, height) = (boundaries.1.x, boundaries.1.y);
-        let mut canvas = Canvas::new((width + 1.).ceil() as usize, (height + 1.).ceil() as usize);
-        blueprint.draw(&mut canvas);
-
-        canvas
-    }
-}
 
-impl Canvas {
-    fn new(width: usize, height: usize) -> Self {
-        Self {
-            width,
-            height,
-            pixels: vec![Color::White; width * height],
-        }
+    /// Whether the file opened as `path` is watched.
+    fn is_opened(&self, path: &Path) -> bool {
+        self.paths.iter().any(|(_, opened)| opened == path)
     }
 
-    fn set(&mut self, x: usize, y: usize, color: Color) {
-        debug_assert!(x < self.width, "set width: {} >= {}", x, self.width);
-        debug_assert!(y < self.height, "set height: {} >= {}", y, self.height);
-
-        self.pixels[x + y * self.width] = color;
+    fn is_watched(&self, path: &Path) -> bool {
+        self.opened_path(path).is_some()
     }
 
-    fn get(&self, x: usize, y: usize) -> Color {
-        debug_assert!(x < self.width, "get width: {} >= {}", x, self.width);
-        debug_assert!(y < self.height, "get height: {} >= {}", y, self.height);
-        self.pixels[x + y * self.width]
+    /// Path the file at the canonical `path` was opened with, if it is watched.
+    fn opened_path(&self, path: &Path) -> Option<&Path> {
+        self.paths
+            .iter()
+            .find(|(watched, _)| watched == path)
+            .map(|(_, opened)| opened.as_path())
     }
+}
 
-    fn pad(&self, horizontal: usize, vertical: usize) -> Self {
-        let mut canvas = Canvas::new(self.width + 2 * horizontal, self.height + 2 * vertical);
+/// Directory watched for changes of the file at `path`.
+fn watched_directory(path: &Path) -> &Path {
+    path.parent().unwrap_or(path)
+}
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                canvas.set(x + horizontal, y + vertical, self.get(x, y));
-            }
+impl From<RecommendedWatcher> for FileWatcher {
+    fn from(inner: RecommendedWatcher) -> Self {
+        Self {
+            inner,
+            paths: Vec::new(),
         }
-
-        canvas
     }
 }
@@ -0,0 +1,246 @@
+use crate::{export_args, render};
+use blueprint_core::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Name of the manifest of projects, looked up in the current directory by `blueprint build`.
+pub const MANIFEST: &str = "blueprint.toml";
+
+/// Drawing made of several blueprints, exported together to the same targets, as described by a
+/// manifest:
+///
+/// ```toml
+/// members = ["kitchen.bp", "rooms/bedroom.bp"]
+/// output = "build"
+/// layers = ["walls", "furniture"]
+///
+/// [parameters]
+/// scale = 4
+/// strict = true
+///
+/// [[targets]]
+/// format = "png"
+/// supersample = 4
+///
+/// [[targets]]
+/// format = "svg"
+/// split-layers = true
+/// ```
+///
+/// Parameters and targets take the export options of the command line, without their leading
+/// `--`; the options of a target take precedence over the parameters. Paths are relative to the
+/// manifest, exports being written to the `output` directory, or next to the members by default.
+#[derive(Debug, PartialEq)]
+pub struct Project {
+    members: Vec<PathBuf>,
+    /// Export options of each target, as given on the command line.
+    targets: Vec<Vec<String>>,
+}
+
+impl Project {
+    /// Loads the manifest at `path`, or the one in `path` when it is a directory.
+    pub fn load(path: &Path) -> Result<Project, Error> {
+        let path = if path.is_dir() {
+            path.join(MANIFEST)
+        } else {
+            path.to_path_buf()
+        };
+        let manifest = fs::read_to_string(&path).map_err(|source| Error::Read {
+            path: path.clone(),
+            source,
+        })?;
+        let root = path.parent().unwrap_or(Path::new(""));
+        parse(&manifest, root).map_err(|message| Error::Manifest { path, message })
+    }
+
+    /// Exports each member to each target, reporting the problems met to stderr. Fails if any of
+    /// them could not be exported.
+    pub fn build(&self) -> Result<(), Error> {
+        let mut failed = 0;
+        for target in &self.targets {
+            let export = export_args(target)?;
+            for member in &self.members {
+                let result = render(&member.to_string_lossy(), &export);
+                match result {
+                    Ok(()) => {
+                        eprintln!("Exported {} as {}", member.display(), export.options.format)
+                    }
+                    Err(e) => {
+                        eprintln!("{e}");
+                        failed += 1;
+                    }
+                }
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            failed => Err(Error::Exports {
+                failed,
+                total: self.members.len() * self.targets.len(),
+            }),
+        }
+    }
+}
+
+/// Reads the manifest `src`, of a project in the directory `root`.
+fn parse(src: &str, root: &Path) -> Result<Project, String> {
+    let manifest = src.parse::<Table>().map_err(|e| e.to_string())?;
+    if let Some(key) = manifest.keys().find(|key| {
+        !["members", "output", "layers", "parameters", "targets"].contains(&key.as_str())
+    }) {
+        return Err(format!("unknown key `{key}`"));
+    }
+
+    let members = match manifest.get("members") {
+        Some(Value::Array(members)) if !members.is_empty() => members
+            .iter()
+            .map(|member| member.as_str().map(|member| root.join(member)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("`members` must be an array of paths")?,
+        _ => return Err("`members` must be a non-empty array of paths".to_string()),
+    };
+
+    // shared options, the first ones given on the command line taking precedence
+    let mut shared = match manifest.get("parameters") {
+        None => Vec::new(),
+        Some(Value::Table(parameters)) => options(parameters, "parameters")?,
+        Some(_) => return Err("`parameters` must be a table".to_string()),
+    };
+    match manifest.get("output") {
+        None => {}
+        Some(Value::String(output)) => shared.push(format!(
+            "--output={}{}",
+            root.join(output).display(),
+            std::path::MAIN_SEPARATOR
+        )),
+        Some(_) => return Err("`output` must be a path".to_string()),
+    }
+    if let Some(layers) = manifest.get("layers") {
+        shared.push(option("layers", layers, "layers")?.unwrap_or_default());
+    }
+
+    let targets = match manifest.get("targets") {
+        None => vec![shared],
+        Some(Value::Array(targets)) => targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| match target {
+                Value::Table(target) => {
+                    let mut options = options(target, &format!("targets[{i}]"))?;
+                    options.extend(shared.iter().cloned());
+                    Ok(options)
+                }
+                _ => Err(format!("`targets[{i}]` must be a table")),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err("`targets` must be an array of tables".to_string()),
+    };
+
+    Ok(Project { members, targets })
+}
+
+/// Command-line options of the keys of `table`.
+fn options(table: &Table, location: &str) -> Result<Vec<String>, String> {
+    let mut options = Vec::new();
+    for (key, value) in table {
+        if key == "output" {
+            return Err(format!(
+                "`{location}.output` is not supported, exports are written to the `output` of the project"
+            ));
+        }
+        options.extend(option(key, value, &format!("{location}.{key}"))?);
+    }
+    Ok(options)
+}
+
+/// Command-line option of `key`, `None` for a disabled flag. Arrays are given as comma-separated
+/// lists.
+fn option(key: &str, value: &Value, location: &str) -> Result<Option<String>, String> {
+    let value = match value {
+        Value::Boolean(true) => return Ok(Some(format!("--{key}"))),
+        Value::Boolean(false) => return Ok(None),
+        Value::String(value) => value.clone(),
+        Value::Integer(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("`{location}` must be an array of strings"))?
+            .join(","),
+        _ => {
+            return Err(format!(
+                "`{location}` must be a string, a number or a boolean"
+            ));
+        }
+    };
+    Ok(Some(format!("--{key}={value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let project = parse(
+            r#"
+            members = ["kitchen.bp", "rooms/bedroom.bp"]
+            output = "build"
+            layers = ["walls", "furniture"]
+
+            [parameters]
+            scale = 4
+            strict = true
+            tags = false
+
+            [[targets]]
+            format = "png"
+            supersample = 4
+
+            [[targets]]
+            format = "svg"
+            "#,
+            Path::new("plans"),
+        )
+        .unwrap();
+
+        let output = format!("--output=plans/build{}", std::path::MAIN_SEPARATOR);
+        let shared = ["--scale=4", "--strict", &output, "--layers=walls,furniture"];
+        assert_eq!(
+            project,
+            Project {
+                members: vec![
+                    PathBuf::from("plans/kitchen.bp"),
+                    PathBuf::from("plans/rooms/bedroom.bp")
+                ],
+                targets: vec![
+                    ["--format=png", "--supersample=4"]
+                        .iter()
+                        .chain(&shared)
+                        .map(|option| option.to_string())
+                        .collect(),
+                    ["--format=svg"]
+                        .iter()
+                        .chain(&shared)
+                        .map(|option| option.to_string())
+                        .collect(),
+                ],
+            }
+        );
+
+        assert_eq!(
+            parse("members = []", Path::new("")),
+            Err("`members` must be a non-empty array of paths".to_string())
+        );
+        assert_eq!(
+            parse(
+                "members = [\"a.bp\"]\n[[targets]]\nscale = {}",
+                Path::new("")
+            ),
+            Err("`targets[0].scale` must be a string, a number or a boolean".to_string())
+        );
+    }
+}
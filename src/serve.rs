@@ -0,0 +1,179 @@
+use crate::FileWatcher;
+use blueprint_core::error::Error;
+use blueprint_core::loader::load_blueprint;
+use blueprint_core::svg::SvgDrawing;
+use notify::{Config, EventKind, RecommendedWatcher, Watcher};
+use std::io;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// Space around the served blueprints, in blueprint units.
+const PADDING: f32 = 5.;
+
+/// Page showing the blueprint, replaced by each update received from the server.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Blueprint</title>
+</head>
+<body style="margin: 0; background: white">
+<pre id="diagnostics" style="color: red"></pre>
+<div id="blueprint"></div>
+<script>
+const socket = new WebSocket(`ws://${location.host}/updates`);
+socket.onmessage = (event) => {
+    const update = JSON.parse(event.data);
+    if (update.svg !== null) {
+        document.getElementById("blueprint").innerHTML = update.svg;
+    }
+    document.getElementById("diagnostics").textContent = update.diagnostics.join("\n");
+};
+socket.onclose = () => {
+    document.getElementById("diagnostics").textContent = "Disconnected from the server";
+};
+</script>
+</body>
+</html>
+"#;
+
+/// Latest update sent to the pages, and the sockets of the pages.
+struct Clients {
+    update: String,
+    sockets: Vec<WebSocket<TcpStream>>,
+}
+
+impl Clients {
+    /// Sends `update` to all the pages, forgetting the ones that are closed.
+    fn broadcast(&mut self, update: String) {
+        self.sockets
+            .retain_mut(|socket| socket.send(Message::text(update.clone())).is_ok());
+        self.update = update;
+    }
+}
+
+/// Serves a page showing the blueprint of `path` on `address`, updated every time the file is
+/// saved, until the process is stopped.
+pub fn run(path: &Path, address: &str) -> Result<(), Error> {
+    let listener = TcpListener::bind(address).map_err(|source| Error::Listen {
+        address: address.to_string(),
+        source,
+    })?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher =
+        FileWatcher::from(RecommendedWatcher::new(tx, Config::default()).map_err(|e| {
+            Error::Watch {
+                path: path.to_path_buf(),
+                source: io::Error::other(e),
+            }
+        })?);
+    watcher.watch(path.to_path_buf())?;
+
+    let clients = Arc::new(Mutex::new(Clients {
+        update: update(path),
+        sockets: Vec::new(),
+    }));
+    {
+        let clients = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let clients = clients.clone();
+                thread::spawn(move || serve(stream, &clients));
+            }
+        });
+    }
+    eprintln!("Serving {} on http://{address}", path.display());
+
+    while let Ok(event) = rx.recv() {
+        // editors saving to a temporary file renamed over the blueprint create it anew
+        let Ok(event) = event else { continue };
+        if !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+            || !event.paths.iter().any(|path| watcher.is_watched(path))
+        {
+            continue;
+        }
+
+        // a save often comes as several events, the file is loaded once they are over
+        while rx.recv_timeout(Duration::from_millis(100)).is_ok() {}
+        let update = update(path);
+        clients
+            .lock()
+            .expect("clients are not poisoned")
+            .broadcast(update);
+    }
+    Ok(())
+}
+
+/// JSON message holding the SVG drawing of the blueprint of `path`, `null` if it could not be
+/// loaded, and its diagnostics.
+fn update(path: &Path) -> String {
+    let (blueprint, errors) = load_blueprint(path, false);
+    serde_json::json!({
+        "svg": blueprint.map(|blueprint| SvgDrawing::from(&blueprint.composed()).padding(PADDING).to_string()),
+        "diagnostics": errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+/// Answers the request of `stream`: the page, or the socket it receives the updates from.
+fn serve(mut stream: TcpStream, clients: &Mutex<Clients>) {
+    // the request is left in the stream, for the socket handshake to read it
+    let mut request = [0; 1024];
+    let Ok(read) = stream.peek(&mut request) else {
+        return;
+    };
+    let request = String::from_utf8_lossy(&request[..read]);
+    let Some(target) = request.split(' ').nth(1) else {
+        return;
+    };
+
+    match target {
+        "/" => {
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+                PAGE.len()
+            );
+        }
+        "/updates" => {
+            let Ok(mut socket) = tungstenite::accept(stream) else {
+                return;
+            };
+            let mut clients = clients.lock().expect("clients are not poisoned");
+            if socket.send(Message::text(clients.update.clone())).is_ok() {
+                clients.sockets.push(socket);
+            }
+        }
+        _ => {
+            let _ = write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let json =
+            serde_json::from_str::<serde_json::Value>(&update(Path::new("examples/example.bp")))
+                .unwrap();
+        assert!(json["svg"].as_str().unwrap().starts_with("<svg"));
+        assert!(json["diagnostics"].is_array());
+
+        let json =
+            serde_json::from_str::<serde_json::Value>(&update(Path::new("missing.bp"))).unwrap();
+        assert!(json["svg"].is_null());
+        assert_eq!(json["diagnostics"].as_array().unwrap().len(), 1);
+    }
+}
@@ -1,50 +1,200 @@
-use crate::domain::Edge;
+use crate::config::{Config, ThemeMode};
+use crate::keymap::Action;
 use crate::open_and_watch_file;
+use blueprint_core::domain::{Bound, Curve, Edge, FLATNESS, LineStyle, ShapeId, polygon_area};
+use blueprint_core::error::{BlueprintError, Severity};
+use blueprint_core::index::IndexedBlueprint;
+use blueprint_core::loader::load_blueprint_str;
+use blueprint_core::ps::{Paper, PostScriptDocument};
 use futures::channel::mpsc::Sender;
+use iced::advanced::text::Highlighter;
+use iced::advanced::text::highlighter::Format;
 use iced::alignment::{Horizontal, Vertical};
 use iced::keyboard::key::Named;
 use iced::mouse::{Cursor, ScrollDelta};
-use iced::widget::canvas::{Fill, Geometry, Path, Stroke, Style, Text};
-use iced::widget::{MouseArea, canvas, column, container, row, text};
+use iced::widget::canvas::{Fill, Geometry, LineDash, Path, Stroke, Style, Text};
+use iced::widget::{
+    MouseArea, button, canvas, center, column, container, opaque, pick_list, row, scrollable,
+    stack, text, text_editor, text_input,
+};
 use iced::{
-    Color, Element, Event, Font, Length, Point, Rectangle, Renderer, Subscription, Task, Theme,
-    Vector, border, event, keyboard, mouse, padding,
+    Color, Element, Event, Font, Length, Point, Rectangle, Renderer, Size, Subscription, Task,
+    Theme, Vector, border, event, font, keyboard, mouse, padding, window,
 };
+use std::collections::VecDeque;
 use std::fmt::{Debug, Display, Formatter};
-use std::ops::{Add, Sub};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::ops::{Add, Range, Sub};
 use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Time without edits after which the source being edited is loaded and saved.
+const EDIT_DELAY: Duration = Duration::from_millis(300);
+
+/// Time the edges added and removed by a reload stay highlighted.
+const DIFF_DURATION: Duration = Duration::from_secs(2);
+
+/// Blueprints kept in the history of each tab.
+const HISTORY_SIZE: usize = 20;
+
+/// Time the tag found by the search flashes for.
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+
+/// Time the path of the last screenshot saved stays shown.
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
 
-pub fn show(path: PathBuf, blueprint: crate::Blueprint) -> iced::Result {
+/// Pixels around the view within which edges are drawn, their strokes reaching into it.
+const VIEW_MARGIN: f32 = 50.;
+
+/// Number of tags completing the search.
+const COMPLETIONS: usize = 8;
+
+/// Time a pan key is held before the view keeps moving until it is released.
+const PAN_DELAY: Duration = Duration::from_millis(250);
+
+/// Steps per second the view moves by while a pan key is held, before it accelerates.
+const PAN_RATE: f32 = 10.;
+
+/// Shows the files of `paths`, each in its own tab.
+pub fn show(paths: Vec<PathBuf>) -> iced::Result {
     iced::application(Blueprint::title, Blueprint::update, Blueprint::view)
         .subscription(Blueprint::subscription)
-        .theme(|_| Theme::Light)
+        .theme(Blueprint::theme)
         .default_font(Font::MONOSPACE)
-        .run_with(|| (Blueprint::new(path, blueprint), Task::none()))
+        .run_with(|| (Blueprint::new(paths), Task::none()))
 }
 
 /// events received by the UI
 pub enum AppEvent {
     Ready(Sender<Command>),
-    BlueprintUpdated(crate::Blueprint),
+    BlueprintUpdated(PathBuf, Option<crate::Blueprint>, Vec<BlueprintError>),
+    /// Whether the file opened at the path is watched.
+    Watched(PathBuf, WatchStatus),
+    /// A problem affecting all the files, e.g. when they cannot be watched.
+    Failed(BlueprintError),
+}
+
+/// Whether the file of a tab is reloaded when it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchStatus {
+    /// The file is being opened.
+    Pending,
+    Watching,
+    Failed(String),
 }
 
 /// commands sent from the UI
 #[derive(Debug)]
 pub enum Command {
     OpenFile(PathBuf),
+    /// Loads the file again, even though it was not reported changed.
+    Reload(PathBuf),
+    CloseFile(PathBuf),
 }
 
 #[derive(Debug)]
 struct Blueprint {
-    path: PathBuf,
+    tabs: Vec<Tab>,
+    /// Index of the tab shown.
+    active: usize,
     sender: Option<Sender<Command>>,
+    mouse_position: Point,
+    mouse_mode: MouseMode,
+    /// Modifiers held, telling how clicks are handled.
+    modifiers: keyboard::Modifiers,
+    config: Config,
+    /// Bounds of the canvas in the window the last time it received an event, to center the view
+    /// in it and take screenshots of it.
+    canvas_bounds: Rectangle,
+    /// Whether the tree of shapes and tags is shown.
+    sidebar: bool,
+    shapes_expanded: bool,
+    tags_expanded: bool,
+    /// Whether the keyboard shortcuts are shown.
+    cheatsheet: bool,
+    /// Whether the window is fullscreen, showing only the blueprint, e.g. on a projector.
+    fullscreen: bool,
+    /// Whether dragging the mouse selects the edges within a rectangle rather than measuring.
+    rectangle: bool,
+    /// Whether the transparent edges, used as construction geometry, are drawn.
+    construction: bool,
+    /// Whether the widths of the edges are in blueprint units, scaled with the zoom, rather than
+    /// in pixels.
+    scaled_widths: bool,
+    /// Paper and scale, in millimeters per blueprint unit as typed, of the page previewed before
+    /// printing.
+    print: Option<(Paper, String)>,
+    /// Name of the tag being searched for, as typed.
+    search: Option<String>,
+    /// Pan keys held, with the direction they move the view in.
+    held: Vec<(keyboard::Key, Vector)>,
+    /// Time the pan keys were first pressed, and the view last moved by them.
+    held_since: Option<(Instant, Instant)>,
+}
+
+/// An open file, and how it is viewed.
+#[derive(Debug)]
+struct Tab {
+    path: PathBuf,
     zoom_level: ZoomLevel,
     translation: Vector,
     fixed_translation: Option<Vector>,
-    mouse_position: Point,
-    mouse_mode: MouseMode,
     fixed_position: Option<Point>,
+    /// Translation and mouse position when the view started being dragged with the middle or
+    /// right button.
+    pan: Option<(Vector, Point)>,
     raw_blueprint: crate::Blueprint,
+    /// Blueprint as drawn, composed according to its layers, indexed to draw the edges in view.
+    composed: IndexedBlueprint,
+    errors: Vec<BlueprintError>,
+    /// Source shown next to the blueprint, when it is being edited.
+    editor: Option<text_editor::Content>,
+    /// Time of the last edit of the source not loaded yet.
+    edited: Option<Instant>,
+    /// Entry of the sidebar clicked last, highlighted in the blueprint.
+    selection: Option<Selection>,
+    /// Region whose area is measured.
+    area: Option<Region>,
+    /// Measurements kept drawn, from a point to another, in blueprint coordinates.
+    pinned: Vec<(crate::Point, crate::Point)>,
+    /// Time the blueprint was last loaded.
+    reloaded: Option<Instant>,
+    /// Time the file was last reported changed, even if it could not be loaded.
+    changed: Option<Instant>,
+    watch_status: WatchStatus,
+    /// Corners of the rectangle last selected, in blueprint coordinates.
+    rectangle: Option<(crate::Point, crate::Point)>,
+    /// Time of the last reload changing the blueprint, and the edges it added and removed.
+    diff: Option<(Instant, Vec<Edge>, Vec<Edge>)>,
+    /// Blueprints last loaded, the latest last, to step back to.
+    history: VecDeque<crate::Blueprint>,
+    /// Index in `history` of the blueprint shown.
+    version: usize,
+    /// Time the selection was found with the search, flashing it.
+    flashed: Option<Instant>,
+    /// Time the last screenshot was saved, and the file it was saved to.
+    screenshot: Option<(Instant, PathBuf)>,
+}
+
+/// Region clicked with Shift held.
+#[derive(Debug, Clone)]
+enum Region {
+    /// Index of the closed shape clicked.
+    Shape(usize),
+    /// Corners clicked outside of closed shapes, in blueprint coordinates.
+    Polygon(Vec<crate::Point>),
+}
+
+/// Element of a blueprint listed in the sidebar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// Identifier of a shape, kept selected across reloads.
+    Shape(ShapeId),
+    Tag(String),
+    /// Indices of a shape, and of an edge of it, clicked in the blueprint.
+    Edge(usize, usize),
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -55,85 +205,684 @@ pub enum MouseMode {
 }
 
 impl Blueprint {
-    fn new(path: PathBuf, blueprint: crate::Blueprint) -> Self {
+    fn new(paths: Vec<PathBuf>) -> Self {
         Self {
-            path,
+            tabs: paths.into_iter().map(Tab::new).collect(),
+            active: 0,
             sender: None,
+            mouse_position: Default::default(),
+            mouse_mode: Default::default(),
+            modifiers: Default::default(),
+            config: Config::load(),
+            canvas_bounds: Rectangle::new(Point::ORIGIN, Size::new(800., 600.)),
+            sidebar: false,
+            shapes_expanded: true,
+            tags_expanded: true,
+            cheatsheet: false,
+            fullscreen: false,
+            rectangle: false,
+            construction: false,
+            scaled_widths: false,
+            print: None,
+            search: None,
+            held: Vec::new(),
+            held_since: None,
+        }
+    }
+}
+
+impl Tab {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
             zoom_level: ZoomLevel::default(),
             translation: Vector::new(50.0, 50.0),
             fixed_translation: None,
-            mouse_position: Default::default(),
-            mouse_mode: Default::default(),
             fixed_position: None,
-            raw_blueprint: blueprint,
+            pan: None,
+            raw_blueprint: crate::Blueprint::default(),
+            composed: IndexedBlueprint::default(),
+            errors: Vec::new(),
+            editor: None,
+            edited: None,
+            selection: None,
+            area: None,
+            pinned: Vec::new(),
+            reloaded: None,
+            changed: None,
+            watch_status: WatchStatus::Pending,
+            rectangle: None,
+            diff: None,
+            history: VecDeque::new(),
+            version: 0,
+            flashed: None,
+            screenshot: None,
+        }
+    }
+
+    /// Corners of the region whose area is measured, in blueprint coordinates, and its area.
+    fn area(&self) -> Option<(Vec<crate::Point>, f32)> {
+        match self.area.as_ref()? {
+            Region::Shape(index) => {
+                let shape = self.raw_blueprint.shapes_iter().nth(*index)?;
+                Some((shape.points(), shape.area()?))
+            }
+            Region::Polygon(points) if points.len() >= 3 => {
+                Some((points.clone(), polygon_area(points)))
+            }
+            Region::Polygon(_) => None,
         }
     }
+
+    /// Measures the area of the closed shape at `point`, or adds `point` to the corners of the
+    /// polygon measured when one is being clicked or there is no shape there.
+    fn measure_area(&mut self, point: crate::Point) {
+        self.area = match self.area.take() {
+            Some(Region::Polygon(mut points)) => {
+                points.push(point);
+                Some(Region::Polygon(points))
+            }
+            _ => match self.raw_blueprint.find_enclosing_shape(point) {
+                Some((index, _)) => Some(Region::Shape(index)),
+                None => Some(Region::Polygon(vec![point])),
+            },
+        };
+    }
+
+    /// Point of the blueprint, in its unscaled coordinates, the selection is centered on.
+    fn selection_center(&self) -> Option<crate::Point> {
+        match self.selection.as_ref()? {
+            Selection::Shape(id) => {
+                let (top_left, bottom_right) = self
+                    .raw_blueprint
+                    .shapes_iter()
+                    .find(|shape| shape.id() == *id)?
+                    .boundaries()?;
+                Some(crate::Point::new(
+                    (top_left.x + bottom_right.x) / 2.,
+                    (top_left.y + bottom_right.y) / 2.,
+                ))
+            }
+            Selection::Tag(name) => self
+                .raw_blueprint
+                .tags_iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, point)| *point),
+            Selection::Edge(..) => self.selected_edge().map(Edge::midpoint),
+        }
+    }
+
+    fn selected_edge(&self) -> Option<&Edge> {
+        match self.selection {
+            Some(Selection::Edge(shape, edge)) => self
+                .raw_blueprint
+                .shapes_iter()
+                .nth(shape)?
+                .edges_iter()
+                .nth(edge),
+            _ => None,
+        }
+    }
+
+    /// Indices of the shape and of the edge closest to `point`, in blueprint coordinates, if
+    /// within `distance`.
+    fn edge_at(&self, point: crate::Point, distance: f32) -> Option<(usize, usize)> {
+        let (closest, _, _) = self
+            .raw_blueprint
+            .find_closest_edge(point)
+            .filter(|(_, _, d)| *d < distance)?;
+        self.raw_blueprint
+            .shapes_iter()
+            .enumerate()
+            .find_map(|(index, shape)| {
+                let edge = shape
+                    .edges_iter()
+                    .position(|edge| std::ptr::eq(edge, closest))?;
+                Some((index, edge))
+            })
+    }
+
+    /// Loads the source being edited, and saves it to the file.
+    fn load_edits(&mut self) {
+        let Some(editor) = &self.editor else {
+            return;
+        };
+        self.edited = None;
+
+        let src = editor.text();
+        let (blueprint, errors) = load_blueprint_str(&src, &self.path, false);
+        // keep showing the last valid blueprint when the new one could not be loaded
+        if let Some(blueprint) = blueprint {
+            self.reload(blueprint);
+        }
+        self.errors = errors;
+        if let Err(e) = fs::write(&self.path, src) {
+            self.errors.push(BlueprintError::error(format!(
+                "Could not save {}: {e}",
+                self.path.display()
+            )));
+        }
+    }
+
+    /// Replaces the blueprint, highlighting the edges changed since the previous one.
+    fn reload(&mut self, blueprint: crate::Blueprint) {
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(blueprint.clone());
+        self.version = self.history.len() - 1;
+
+        let now = Instant::now();
+        // the first blueprint loaded is not a change
+        if self.reloaded.is_some() {
+            self.show(blueprint);
+        } else {
+            self.composed = blueprint.composed().into();
+            self.raw_blueprint = blueprint;
+        }
+        self.reloaded = Some(now);
+    }
+
+    /// Shows the blueprint at `version` in the history, if any.
+    fn show_version(&mut self, version: usize) {
+        if let Some(blueprint) = self.history.get(version) {
+            self.version = version;
+            self.show(blueprint.clone());
+        }
+    }
+
+    /// Shows `blueprint`, highlighting the edges changed from the one shown.
+    fn show(&mut self, blueprint: crate::Blueprint) {
+        let (added, removed) = blueprint.diff(&self.raw_blueprint);
+        if !added.is_empty() || !removed.is_empty() {
+            self.diff = Some((Instant::now(), added, removed));
+        }
+        self.composed = blueprint.composed().into();
+        self.raw_blueprint = blueprint;
+    }
+
+    fn name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
 }
 
 impl Blueprint {
-    fn update(&mut self, message: Message) {
+    fn update(&mut self, message: Message) -> Task<Message> {
+        let tab = &mut self.tabs[self.active];
         match message {
             Message::ZoomIn => {
-                self.zoom_level = self.zoom_level.zoom_in();
+                tab.zoom_level = tab.zoom_level.zoom_in();
             }
             Message::ZoomOut => {
-                self.zoom_level = self.zoom_level.zoom_out();
+                tab.zoom_level = tab.zoom_level.zoom_out();
             }
             Message::ZoomReset => {
-                self.zoom_level = ZoomLevel::default();
-                self.translation = Vector::new(50.0, 50.0);
+                tab.zoom_level = ZoomLevel::default();
+                tab.translation = Vector::new(50.0, 50.0);
+            }
+            Message::Translate(direction) => {
+                let step = pan_step(&self.config, self.modifiers, tab.zoom_level);
+                tab.translation = tab.translation + direction * step;
             }
-            Message::TranslateUp => self.translation.y -= 1.0,
-            Message::TranslateLeft => self.translation.x -= 1.0,
-            Message::TranslateDown => self.translation.y += 1.0,
-            Message::TranslateRight => self.translation.x += 1.0,
             Message::CursorMoved(point) => {
                 self.mouse_position = point;
 
                 if matches!(self.mouse_mode, MouseMode::Move)
-                    && let Some(fixed_translation) = self.fixed_translation
+                    && let Some(fixed_translation) = tab.fixed_translation
                 {
-                    self.translation = fixed_translation.add(Vector::new(
-                        self.mouse_position.x
-                            - self.fixed_position.unwrap_or(self.mouse_position).x,
-                        self.mouse_position.y
-                            - self.fixed_position.unwrap_or(self.mouse_position).y,
+                    tab.translation = fixed_translation.add(Vector::new(
+                        self.mouse_position.x - tab.fixed_position.unwrap_or(self.mouse_position).x,
+                        self.mouse_position.y - tab.fixed_position.unwrap_or(self.mouse_position).y,
                     ));
                 }
+                if let Some((translation, position)) = tab.pan {
+                    tab.translation = translation.add(self.mouse_position.sub(position));
+                }
             }
             Message::ChangeMouseMode(mode) => {
                 self.mouse_mode = mode;
             }
+            Message::StorePosition if self.modifiers.shift() => {
+                let position = self.mouse_position.sub(tab.translation);
+                let scale = tab.zoom_level.scale_factor();
+                tab.measure_area(crate::Point::new(position.x / scale, position.y / scale));
+            }
             Message::StorePosition => {
-                self.fixed_translation = Some(self.translation);
-                self.fixed_position = Some(self.mouse_position);
+                tab.fixed_translation = Some(tab.translation);
+                tab.fixed_position = Some(self.mouse_position);
+            }
+            Message::ClearMeasures => {
+                // the search is closed first
+                if self.search.take().is_some() {
+                    return Task::none();
+                }
+                tab.fixed_translation = None;
+                tab.fixed_position = None;
+                tab.rectangle = None;
+                tab.area = None;
+                tab.pinned.clear();
+            }
+            Message::PinMeasurement => {
+                if let Some(fixed_position) = tab.fixed_position
+                    && matches!(self.mouse_mode, MouseMode::Select)
+                {
+                    let scale = tab.zoom_level.scale_factor();
+                    let point = |position: Point| {
+                        let position = position.sub(tab.translation);
+                        crate::Point::new(position.x / scale, position.y / scale)
+                    };
+                    tab.pinned
+                        .push((point(fixed_position), point(self.mouse_position)));
+                }
+            }
+            Message::CopyPosition => {
+                let scale = tab.zoom_level.scale_factor();
+                let position = self.mouse_position.sub(tab.translation);
+                return iced::clipboard::write(format!(
+                    "@{},{}",
+                    coordinate(position.x / scale),
+                    coordinate(position.y / scale)
+                ));
             }
+            Message::CopyMeasurement => {
+                // the measurement in progress, or the last one pinned
+                let delta = match tab.fixed_position {
+                    Some(fixed_position) if matches!(self.mouse_mode, MouseMode::Select) => {
+                        let distances =
+                            Distances::from(self.mouse_position, fixed_position, tab.zoom_level);
+                        Some((distances.horizontal, distances.vertical))
+                    }
+                    _ => tab
+                        .pinned
+                        .last()
+                        .map(|(from, to)| (to.x - from.x, to.y - from.y)),
+                };
+                if let Some((dx, dy)) = delta {
+                    return iced::clipboard::write(format!(
+                        "{},{}",
+                        coordinate(dx),
+                        coordinate(dy)
+                    ));
+                }
+            }
+            Message::CopyEdge => {
+                if let Some(edge) = tab.selected_edge() {
+                    return iced::clipboard::write(describe(edge, &tab.raw_blueprint).join("\n"));
+                }
+            }
+            Message::UnpinMeasurement => {
+                // the measurement whose middle is the closest to the mouse
+                let scale = tab.zoom_level.scale_factor();
+                let position = self.mouse_position.sub(tab.translation);
+                let mouse = crate::Point::new(position.x / scale, position.y / scale);
+                if let Some(index) = (0..tab.pinned.len()).min_by(|&a, &b| {
+                    let distance = |index: usize| {
+                        let (from, to) = tab.pinned[index];
+                        let middle = crate::Point::new((from.x + to.x) / 2., (from.y + to.y) / 2.);
+                        middle.distance_to_point(&mouse)
+                    };
+                    distance(a).total_cmp(&distance(b))
+                }) {
+                    tab.pinned.remove(index);
+                }
+            }
+            Message::ModifiersChanged(modifiers) => self.modifiers = modifiers,
             Message::DropPosition => {
-                self.fixed_translation = None;
-                self.fixed_position = None;
+                if self.rectangle
+                    && matches!(self.mouse_mode, MouseMode::Select)
+                    && let Some(fixed_position) = tab.fixed_position
+                {
+                    let scale = tab.zoom_level.scale_factor();
+                    let point = |position: Point| {
+                        let position = position.sub(tab.translation);
+                        crate::Point::new(position.x / scale, position.y / scale)
+                    };
+                    tab.rectangle = Some((point(fixed_position), point(self.mouse_position)));
+                } else if matches!(self.mouse_mode, MouseMode::Select)
+                    && tab.fixed_position == Some(self.mouse_position)
+                {
+                    // clicking rather than dragging selects the closest edge
+                    let scale = tab.zoom_level.scale_factor();
+                    let position = self.mouse_position.sub(tab.translation);
+                    let position = crate::Point::new(position.x / scale, position.y / scale);
+                    match tab.edge_at(position, 20. / scale) {
+                        Some((shape, edge)) => tab.selection = Some(Selection::Edge(shape, edge)),
+                        None if matches!(tab.selection, Some(Selection::Edge(..))) => {
+                            tab.selection = None
+                        }
+                        None => {}
+                    }
+                }
+                tab.fixed_translation = None;
+                tab.fixed_position = None;
+            }
+            Message::StartPan => {
+                tab.pan = Some((tab.translation, self.mouse_position));
+            }
+            Message::EndPan => {
+                tab.pan = None;
+            }
+            Message::ToggleTheme => {
+                self.config.theme = self.config.theme.toggle();
+                if let Err(e) = self.config.save() {
+                    eprintln!("{e}");
+                }
+            }
+            Message::ToggleSidebar => self.sidebar = !self.sidebar,
+            Message::ToggleCheatsheet => self.cheatsheet = !self.cheatsheet,
+            Message::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                let mode = match self.fullscreen {
+                    true => window::Mode::Fullscreen,
+                    false => window::Mode::Windowed,
+                };
+                return window::get_latest()
+                    .and_then(move |window| window::change_mode(window, mode));
+            }
+            Message::ToggleRectangle => self.rectangle = !self.rectangle,
+            Message::ToggleConstruction => self.construction = !self.construction,
+            Message::ToggleWidths => self.scaled_widths = !self.scaled_widths,
+            // keys held are repeated by the system, the view rather moves on ticks
+            Message::KeyPressed(key, _, _) if self.held.iter().any(|(held, _)| *held == key) => {}
+            Message::KeyPressed(key, modified_key, modifiers) => {
+                let Some(action) = self.config.keymap.action(&modified_key, modifiers, false)
+                else {
+                    return Task::none();
+                };
+                return match Message::from(action) {
+                    Message::Translate(direction) => {
+                        self.held.push((key, direction));
+                        let now = Instant::now();
+                        self.held_since.get_or_insert((now, now));
+                        self.update(Message::Translate(direction))
+                    }
+                    message => self.update(message),
+                };
+            }
+            Message::KeyReleased(key, modifiers) => {
+                self.held.retain(|(held, _)| *held != key);
+                if self.held.is_empty() {
+                    self.held_since = None;
+                }
+                if let Some(action) = self.config.keymap.action(&key, modifiers, true) {
+                    return self.update(action.into());
+                }
+            }
+            Message::PreviousVersion => {
+                if let Some(version) = tab.version.checked_sub(1) {
+                    tab.show_version(version);
+                }
+            }
+            Message::NextVersion => tab.show_version(tab.version + 1),
+            Message::Reload => {
+                let sent = match &mut self.sender {
+                    Some(sender) => sender
+                        .try_send(Command::Reload(tab.path.clone()))
+                        .map_err(|e| e.to_string()),
+                    None => Err("the files are not opened yet".to_string()),
+                };
+                if let Err(e) = sent {
+                    tab.errors.push(BlueprintError::error(format!(
+                        "Could not reload {}: {e}",
+                        tab.path.display()
+                    )));
+                }
             }
-            Message::BlueprintUpdated(blueprint) => {
+            Message::TogglePrint => {
+                self.print = match self.print {
+                    Some(_) => None,
+                    None => Some((Paper::default(), "1".to_string())),
+                }
+            }
+            Message::ToggleSearch => {
+                self.search = match self.search {
+                    Some(_) => None,
+                    None => Some(String::new()),
+                };
+                if self.search.is_some() {
+                    return text_input::focus(text_input::Id::new("search"));
+                }
+            }
+            Message::Search(name) => {
+                if let Some(search) = &mut self.search {
+                    *search = name;
+                }
+            }
+            Message::FindTag(name) => {
+                self.search = None;
+                tab.flashed = Some(Instant::now());
+                return self.update(Message::Select(Selection::Tag(name)));
+            }
+            Message::PrintPaper(paper) => {
+                if let Some((print_paper, _)) = &mut self.print {
+                    *print_paper = paper;
+                }
+            }
+            Message::PrintScale(scale) => {
+                if let Some((_, print_scale)) = &mut self.print {
+                    *print_scale = scale;
+                }
+            }
+            Message::Print => {
+                if let Some((paper, scale)) = &self.print {
+                    match print_scale(scale)
+                        .and_then(|scale| print(tab.composed.blueprint(), *paper, scale))
+                    {
+                        Ok(()) => self.print = None,
+                        Err(e) => tab
+                            .errors
+                            .push(BlueprintError::error(format!("Could not print: {e}"))),
+                    }
+                }
+            }
+            Message::Screenshot => {
+                return window::get_latest()
+                    .and_then(window::screenshot)
+                    .map(Message::Screenshotted);
+            }
+            Message::Screenshotted(screenshot) => {
+                match save_screenshot(&screenshot, self.canvas_bounds) {
+                    Ok(path) => tab.screenshot = Some((Instant::now(), path)),
+                    Err(e) => tab.errors.push(BlueprintError::error(format!(
+                        "Could not save the screenshot: {e}"
+                    ))),
+                }
+            }
+            Message::ToggleShapes => self.shapes_expanded = !self.shapes_expanded,
+            Message::ToggleTags => self.tags_expanded = !self.tags_expanded,
+            Message::Select(selection) => {
+                tab.selection = Some(selection);
+                if let Some(center) = tab.selection_center() {
+                    let scale = tab.zoom_level.scale_factor();
+                    tab.translation = Vector::new(
+                        self.canvas_bounds.width / 2. - center.x * scale,
+                        self.canvas_bounds.height / 2. - center.y * scale,
+                    );
+                }
+            }
+            Message::CanvasResized(bounds) => self.canvas_bounds = bounds,
+            Message::SelectTab(index) => {
+                self.active = index.min(self.tabs.len() - 1);
+            }
+            Message::NextTab => {
+                self.active = (self.active + 1) % self.tabs.len();
+            }
+            Message::PreviousTab => {
+                self.active = (self.active + self.tabs.len() - 1) % self.tabs.len();
+            }
+            Message::CloseTab(index) => {
+                // the last file stays open
+                if self.tabs.len() > 1 && index < self.tabs.len() {
+                    let tab = self.tabs.remove(index);
+                    if let Some(sender) = &mut self.sender {
+                        let _ = sender.try_send(Command::CloseFile(tab.path));
+                    }
+                    if self.active > index || self.active == self.tabs.len() {
+                        self.active -= 1;
+                    }
+                }
+            }
+            Message::ToggleEditor => {
+                tab.editor = match tab.editor {
+                    Some(_) => {
+                        tab.load_edits();
+                        None
+                    }
+                    None => match fs::read_to_string(&tab.path) {
+                        Ok(src) => Some(text_editor::Content::with_text(&src)),
+                        Err(e) => {
+                            tab.errors = vec![BlueprintError::error(format!(
+                                "Could not open {}: {e}",
+                                tab.path.display()
+                            ))];
+                            None
+                        }
+                    },
+                };
+            }
+            Message::Edit(action) => {
+                if let Some(editor) = &mut tab.editor {
+                    if action.is_edit() {
+                        tab.edited = Some(Instant::now());
+                    }
+                    editor.perform(action);
+                }
+            }
+            Message::Tick(now) => {
+                for tab in &mut self.tabs {
+                    if tab.edited.is_some_and(|edited| now - edited >= EDIT_DELAY) {
+                        tab.load_edits();
+                    }
+                    if tab
+                        .diff
+                        .as_ref()
+                        .is_some_and(|(reloaded, _, _)| now - *reloaded >= DIFF_DURATION)
+                    {
+                        tab.diff = None;
+                    }
+                    if tab
+                        .flashed
+                        .is_some_and(|flashed| now - flashed >= FLASH_DURATION)
+                    {
+                        tab.flashed = None;
+                    }
+                    if tab
+                        .screenshot
+                        .as_ref()
+                        .is_some_and(|(saved, _)| now - *saved >= NOTICE_DURATION)
+                    {
+                        tab.screenshot = None;
+                    }
+                }
+                if let Some((since, moved)) = &mut self.held_since {
+                    let moving_since = *since + PAN_DELAY;
+                    if now > moving_since {
+                        // twice as fast after each second, up to four times
+                        let held = (now - moving_since).as_secs_f32();
+                        let elapsed = (now - (*moved).max(moving_since)).as_secs_f32();
+                        let steps = PAN_RATE * 2_f32.powf(held).min(4.) * elapsed;
+                        let direction = self
+                            .held
+                            .iter()
+                            .fold(Vector::new(0., 0.), |sum, (_, direction)| sum + *direction);
+                        let tab = &mut self.tabs[self.active];
+                        let step = pan_step(&self.config, self.modifiers, tab.zoom_level);
+                        tab.translation = tab.translation + direction * (steps * step);
+                    }
+                    *moved = now;
+                }
+            }
+            Message::ReleaseKeys => {
+                self.held.clear();
+                self.held_since = None;
+            }
+            Message::BlueprintUpdated(path, blueprint, errors) => {
                 println!("Blueprint reloaded");
-                self.raw_blueprint = blueprint;
+                let Some(tab) = self.tabs.iter_mut().find(|tab| tab.path == path) else {
+                    return Task::none();
+                };
+                // the source edited elsewhere replaces the one of the editor, unless being edited
+                if let Some(editor) = &mut tab.editor
+                    && tab.edited.is_none()
+                    && let Ok(src) = fs::read_to_string(&tab.path)
+                    && src != editor.text()
+                {
+                    *editor = text_editor::Content::with_text(&src);
+                }
+                tab.changed = Some(Instant::now());
+                // keep showing the last valid blueprint when the new one could not be loaded
+                if let Some(blueprint) = blueprint {
+                    tab.reload(blueprint);
+                }
+                tab.errors = errors;
+            }
+            Message::Watched(path, status) => {
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.path == path) {
+                    tab.watch_status = status;
+                }
+            }
+            Message::Failed(error) => {
+                for tab in &mut self.tabs {
+                    tab.errors.push(error.clone());
+                    tab.watch_status = WatchStatus::Failed(error.message.clone());
+                }
             }
-            Message::SetSender(sender) => {
+            Message::SetSender(mut sender) => {
+                for tab in &mut self.tabs {
+                    if let Err(e) = sender.try_send(Command::OpenFile(tab.path.clone())) {
+                        tab.errors = vec![BlueprintError::error(format!(
+                            "Could not open {}: {e}",
+                            tab.path.display()
+                        ))];
+                    }
+                }
                 self.sender = Some(sender);
-                self.sender
-                    .as_mut()
-                    .unwrap()
-                    .try_send(Command::OpenFile(self.path.clone()))
-                    .unwrap();
             }
         }
+        Task::none()
     }
 
     fn subscription(&self) -> Subscription<Message> {
+        // the edits are loaded once they have stopped for a while
+        let edits = match self.tabs.iter().any(|tab| tab.edited.is_some()) {
+            true => iced::time::every(EDIT_DELAY / 3).map(Message::Tick),
+            false => Subscription::none(),
+        };
+
+        // the changes of the last reload fade out, and the tag found flashes
+        let diffs = match self
+            .tabs
+            .iter()
+            .any(|tab| tab.diff.is_some() || tab.flashed.is_some())
+        {
+            true => iced::time::every(Duration::from_millis(50)).map(Message::Tick),
+            false => Subscription::none(),
+        };
+        let panning = match self.held.is_empty() {
+            true => Subscription::none(),
+            false => iced::time::every(Duration::from_millis(16)).map(Message::Tick),
+        };
+
         Subscription::batch(vec![
+            edits,
+            diffs,
+            panning,
+            // the time elapsed since the last reload is shown to the second
+            iced::time::every(Duration::from_secs(1)).map(Message::Tick),
             Subscription::run(open_and_watch_file).map(|e| match e {
-                AppEvent::BlueprintUpdated(blueprint) => Message::BlueprintUpdated(blueprint),
+                AppEvent::BlueprintUpdated(path, blueprint, errors) => {
+                    Message::BlueprintUpdated(path, blueprint, errors)
+                }
+                AppEvent::Watched(path, status) => Message::Watched(path, status),
+                AppEvent::Failed(error) => Message::Failed(error),
                 AppEvent::Ready(sender) => Message::SetSender(sender),
             }),
-            event::listen_with(|e, _, _| match e {
+            // the events captured by a widget, e.g. typed in the editor, are not shortcuts
+            event::listen_with(|e, status, _| match e {
+                _ if status == event::Status::Captured => None,
                 Event::Mouse(mouse::Event::WheelScrolled {
                     delta: ScrollDelta::Pixels { x: _, y },
                 })
@@ -146,30 +895,10 @@ impl Blueprint {
                         Some(Message::ZoomOut)
                     }
                 }
-                Event::Keyboard(keyboard::Event::KeyPressed {
-                    key: keyboard::Key::Character(c),
-                    modifiers,
-                    ..
-                }) if modifiers.is_empty() => match c.as_str() {
-                    "i" | "e" => Some(Message::ZoomIn),
-                    "o" | "q" => Some(Message::ZoomOut),
-                    "w" => Some(Message::TranslateUp),
-                    "a" => Some(Message::TranslateLeft),
-                    "s" => Some(Message::TranslateDown),
-                    "d" => Some(Message::TranslateRight),
-                    "0" => Some(Message::ZoomReset),
-                    _ => None,
-                },
-                Event::Keyboard(keyboard::Event::KeyReleased {
-                    key: keyboard::Key::Named(Named::Space),
-                    modifiers,
-                    ..
-                }) if modifiers.is_empty() => Some(Message::StorePosition),
-                Event::Keyboard(keyboard::Event::KeyReleased {
-                    key: keyboard::Key::Named(Named::Escape),
-                    modifiers,
-                    ..
-                }) if modifiers.is_empty() => Some(Message::DropPosition),
+                Event::Window(window::Event::Unfocused) => Some(Message::ReleaseKeys),
+                Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
                 Event::Keyboard(keyboard::Event::KeyPressed {
                     key: keyboard::Key::Named(Named::Control),
                     ..
@@ -178,55 +907,199 @@ impl Blueprint {
                     key: keyboard::Key::Named(Named::Control),
                     ..
                 }) => Some(Message::ChangeMouseMode(Default::default())),
+                // the other keys trigger the actions of the keymap of the configuration
+                // with shift applied, for symbols such as `?`
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key,
+                    modified_key,
+                    modifiers,
+                    ..
+                }) => Some(Message::KeyPressed(key, modified_key, modifiers)),
+                Event::Keyboard(keyboard::Event::KeyReleased { key, modifiers, .. }) => {
+                    Some(Message::KeyReleased(key, modifiers))
+                }
                 _ => None,
             }),
         ])
     }
 
     fn view(&self) -> Element<'_, Message> {
-        let zoom_level = text(format!("zoom: {}", self.zoom_level));
+        let tab = &self.tabs[self.active];
+        let zoom_level = text(format!("zoom: {}", tab.zoom_level));
         let mouse_position = text(format!(
             "mouse: {}, {}",
             self.mouse_position.x.floor(),
             self.mouse_position.y.floor()
         ));
 
-        let distances = self
+        let distances = tab
             .fixed_position
-            .filter(|_| matches!(self.mouse_mode, MouseMode::Select))
-            .map(|position| Distances::from(self.mouse_position, position, self.zoom_level));
+            .filter(|_| matches!(self.mouse_mode, MouseMode::Select) && !self.rectangle)
+            .map(|position| Distances::from(self.mouse_position, position, tab.zoom_level));
 
         let delta = distances.map(|d| {
             text(format!(
-                "dx: {}, dy: {}; area: {}",
+                "dx: {}, dy: {}; distance: {}",
                 d.horizontal.floor(),
                 d.vertical.floor(),
                 d.diagonal.floor()
             ))
         });
 
-        let blueprint = self.raw_blueprint.scale(self.zoom_level.scale_factor());
-        let closest = blueprint
-            .find_closest_edge(crate::Point::from(
-                self.mouse_position.sub(self.translation),
-            ))
-            .filter(|(_, _, distance)| *distance < 20.);
+        // the blueprint is not scaled, the zoom being applied as it is drawn
+        let zoom = tab.zoom_level.scale_factor();
+        let closest = tab
+            .raw_blueprint
+            .find_closest_edge({
+                let position = self.mouse_position.sub(tab.translation);
+                crate::Point::new(position.x / zoom, position.y / zoom)
+            })
+            .filter(|(_, _, distance)| *distance * zoom < 20.);
 
-        let highlighted = closest.map(|(edge, _, _)| text(format!("line: {}", edge.line)));
-        let header = row![zoom_level, mouse_position]
+        let highlighted = closest
+            .map(|(edge, _, _)| text(format!("source: {}:{}", edge.span.line, edge.span.column)));
+        let area = tab.area();
+        let path = text(tab.path.display().to_string());
+        let reloaded = text(match tab.reloaded {
+            Some(reloaded) => format!("reloaded {}s ago", reloaded.elapsed().as_secs()),
+            None => "not loaded".to_string(),
+        });
+        // older blueprints of the history are not reloaded
+        let version = (tab.version + 1 < tab.history.len()).then(|| {
+            text(format!("version {}/{}", tab.version + 1, tab.history.len()))
+                .color(iced_color(crate::Color::Magenta))
+        });
+        let counts = text(format!(
+            "{} shapes, {} edges",
+            tab.raw_blueprint.shapes_iter().count(),
+            tab.raw_blueprint
+                .shapes_iter()
+                .map(|shape| shape.edges_iter().count())
+                .sum::<usize>()
+        ));
+        let mode = text(match self.mouse_mode {
+            MouseMode::Select if self.rectangle => "mode: rectangle",
+            MouseMode::Select => "mode: select",
+            MouseMode::Move => "mode: move",
+        });
+        let rectangle = tab.rectangle.map(|(corner, opposite)| {
+            let (width, height) = ((opposite.x - corner.x).abs(), (opposite.y - corner.y).abs());
+            text(format!(
+                "box: {} x {}, area: {}, {} edges",
+                coordinate(width),
+                coordinate(height),
+                coordinate(width * height),
+                tab.raw_blueprint.edges_within(corner, opposite).count()
+            ))
+        });
+        let watch_status = match &tab.watch_status {
+            WatchStatus::Pending => text("opening"),
+            WatchStatus::Watching => text(match tab.changed {
+                Some(changed) => format!("watching, changed {}s ago", changed.elapsed().as_secs()),
+                None => "watching".to_string(),
+            }),
+            WatchStatus::Failed(e) => {
+                text(format!("not watched: {e}")).color(iced_color(crate::Color::Red))
+            }
+        };
+        let screenshot = tab
+            .screenshot
+            .as_ref()
+            .map(|(_, path)| text(format!("screenshot saved to {}", path.display())));
+        let header = row![path, reloaded]
+            .push_maybe(version)
+            .push_maybe(screenshot)
+            .push(counts)
+            .push(watch_status)
+            .push(button(text("reload")).padding(0).on_press(Message::Reload))
+            .push(mode)
+            .push(zoom_level)
+            .push(mouse_position)
             .push_maybe(delta)
+            .push_maybe(area.as_ref().map(|(_, area)| text(format!("area: {area}"))))
+            .push_maybe(rectangle)
             .push_maybe(highlighted)
             .spacing(20);
 
         let highlighted = closest.map(|(edge, point, _)| (*edge, point));
 
         let image = canvas(DrawableBlueprint {
-            blueprint,
+            blueprint: &tab.raw_blueprint,
+            composed: &tab.composed,
             highlighted,
-            translation: self.translation,
-            zoom_level: self.zoom_level,
-            mouse_position: self.mouse_position,
-            distances: self.fixed_position.zip(distances),
+            construction: self.construction,
+            diff: tab.diff.as_ref().map(|(reloaded, added, removed)| {
+                let opacity = 1. - reloaded.elapsed().as_secs_f32() / DIFF_DURATION.as_secs_f32();
+                (added.as_slice(), removed.as_slice(), opacity.max(0.))
+            }),
+            scaled_widths: self.scaled_widths,
+            flash: tab
+                .flashed
+                .map(|flashed| flashed.elapsed().as_secs_f32() / FLASH_DURATION.as_secs_f32()),
+            page: self.print.as_ref().and_then(|(paper, scale)| {
+                let scale = print_scale(scale).ok()?;
+                let (min, max) = tab.raw_blueprint.boundaries().unwrap_or_default();
+                let (width, height) = paper.size();
+                let size = Size::new(width / scale, height / scale);
+                // the blueprint is printed at the center of the page
+                let top_left = Point::new(
+                    (min.x + max.x - size.width) / 2.,
+                    (min.y + max.y - size.height) / 2.,
+                );
+                let zoom = tab.zoom_level.scale_factor();
+                Some(Rectangle::new(
+                    Point::new(top_left.x * zoom, top_left.y * zoom),
+                    size * zoom,
+                ))
+            }),
+            translation: tab.translation,
+            zoom_level: tab.zoom_level,
+            measurements: {
+                let scale = tab.zoom_level.scale_factor();
+                let point = |point: &crate::Point| Point::new(point.x * scale, point.y * scale);
+                tab.fixed_position
+                    .zip(distances)
+                    .map(|(fixed_position, distances)| {
+                        (
+                            fixed_position.sub(tab.translation),
+                            self.mouse_position.sub(tab.translation),
+                            distances,
+                        )
+                    })
+                    .into_iter()
+                    .chain(tab.pinned.iter().map(|(from, to)| {
+                        let (from, to) = (point(from), point(to));
+                        (from, to, Distances::from(to, from, tab.zoom_level))
+                    }))
+                    .collect()
+            },
+            selection: tab.selection.clone(),
+            rectangle: {
+                let scale = tab.zoom_level.scale_factor();
+                let point = |point: crate::Point| Point::new(point.x * scale, point.y * scale);
+                match tab.fixed_position {
+                    // the rectangle being dragged
+                    Some(fixed_position)
+                        if self.rectangle && matches!(self.mouse_mode, MouseMode::Select) =>
+                    {
+                        Some((
+                            fixed_position.sub(tab.translation),
+                            self.mouse_position.sub(tab.translation),
+                        ))
+                    }
+                    _ => tab
+                        .rectangle
+                        .map(|(corner, opposite)| (point(corner), point(opposite))),
+                }
+            },
+            area: area.map(|(points, area)| {
+                let scale = tab.zoom_level.scale_factor();
+                let points = points
+                    .iter()
+                    .map(|point| Point::new(point.x * scale, point.y * scale))
+                    .collect();
+                (points, area)
+            }),
         })
         .width(Length::Fill)
         .height(Length::Fill);
@@ -234,29 +1107,285 @@ impl Blueprint {
         let image = MouseArea::new(image)
             .on_move(Message::CursorMoved)
             .on_release(Message::DropPosition)
-            .on_press(Message::StorePosition);
-
-        let rows = column![
-            container(header)
-                .style(|_| container::Style::default()
-                    .border(border::width(1).color(Color::from(crate::Color::Cyan))))
-                .padding(padding::bottom(5).top(5)),
-            container(image).style(|_| container::Style::default()
-                // .background(Background::Color(Color::from(crate::Color::Magenta)))
-                .border(border::width(1).color(Color::from(crate::Color::Cyan))))
-        ];
-
-        container(rows)
+            .on_press(Message::StorePosition)
+            .on_middle_press(Message::StartPan)
+            .on_middle_release(Message::EndPan)
+            .on_right_press(Message::StartPan)
+            .on_right_release(Message::EndPan);
+        // only the blueprint, without the header, the panels and the borders
+        if self.fullscreen {
+            let image = container(image).width(Length::Fill).height(Length::Fill);
+            return match self.cheatsheet {
+                true => stack![image, self.cheatsheet()].into(),
+                false => image.into(),
+            };
+        }
+        let image = match &tab.editor {
+            Some(editor) => Element::from(
+                row![
+                    container(
+                        text_editor(editor)
+                            .on_action(Message::Edit)
+                            .height(Length::Fill)
+                            .highlight_with::<SourceHighlighter>(
+                                closest.map(|(edge, _, _)| edge.span.start..edge.span.end),
+                                |_, theme: &Theme| Format {
+                                    color: Some(theme.palette().primary),
+                                    font: Some(Font {
+                                        weight: font::Weight::Bold,
+                                        ..Font::MONOSPACE
+                                    }),
+                                }
+                            )
+                    )
+                    .width(Length::FillPortion(1)),
+                    container(image).width(Length::FillPortion(2)),
+                ]
+                .spacing(5),
+            ),
+            None => Element::from(image),
+        };
+        let image = match self.sidebar {
+            true => Element::from(row![self.sidebar(tab), image].spacing(5)),
+            false => image,
+        };
+        let image = match tab.selected_edge() {
+            Some(edge) => {
+                Element::from(row![image, inspector(edge, &tab.raw_blueprint)].spacing(5))
+            }
+            None => image,
+        };
+
+        // a single file needs no tabs
+        let tabs = (self.tabs.len() > 1).then(|| {
+            row(self.tabs.iter().enumerate().map(|(index, tab)| {
+                let style = if index == self.active {
+                    button::primary
+                } else {
+                    button::secondary
+                };
+                row![
+                    button(text(tab.name()))
+                        .style(style)
+                        .on_press(Message::SelectTab(index)),
+                    button(text("×"))
+                        .style(style)
+                        .on_press(Message::CloseTab(index)),
+                ]
+                .into()
+            }))
+            .spacing(5)
+            .padding(padding::bottom(5))
+        });
+
+        let errors = (!tab.errors.is_empty()).then(|| {
+            container(column(tab.errors.iter().map(|error| {
+                let color = match error.severity {
+                    Severity::Error => crate::Color::Red,
+                    Severity::Warning => crate::Color::Magenta,
+                };
+                text(error.to_string()).color(iced_color(color)).into()
+            })))
+            .style(|_| {
+                container::Style::default()
+                    .border(border::width(1).color(iced_color(crate::Color::Cyan)))
+            })
+            .width(Length::Fill)
+            .padding(padding::bottom(5).top(5))
+        });
+
+        let print = self.print.as_ref().map(|(paper, scale)| {
+            row![
+                text("print on"),
+                pick_list(Paper::ALL, Some(*paper), Message::PrintPaper),
+                text("at"),
+                text_input("1", scale)
+                    .on_input(Message::PrintScale)
+                    .on_submit(Message::Print)
+                    .width(80),
+                text("mm per unit"),
+                button(text("print")).on_press(Message::Print),
+                button(text("cancel"))
+                    .style(button::secondary)
+                    .on_press(Message::TogglePrint),
+            ]
+            .spacing(10)
+            .align_y(Vertical::Center)
+            .padding(padding::bottom(5).top(5))
+        });
+
+        let search = self.search.as_ref().map(|search| {
+            let mut tags = tab
+                .raw_blueprint
+                .tags_iter()
+                .filter_map(|(tag, _)| fuzzy_match(search, tag).map(|distance| (distance, tag)))
+                .collect::<Vec<_>>();
+            tags.sort();
+            tags.truncate(COMPLETIONS);
+
+            row![
+                text("find tag"),
+                text_input("name", search)
+                    .id(text_input::Id::new("search"))
+                    .on_input(Message::Search)
+                    .on_submit_maybe(
+                        tags.first()
+                            .map(|(_, tag)| Message::FindTag(tag.to_string()))
+                    )
+                    .width(200),
+            ]
+            .extend(tags.iter().map(|(_, tag)| {
+                button(text(format!("#{tag}")))
+                    .style(button::text)
+                    .on_press(Message::FindTag(tag.to_string()))
+                    .into()
+            }))
+            .push_maybe(tags.is_empty().then(|| text("no tag matches")))
+            .push(
+                button(text("cancel"))
+                    .style(button::secondary)
+                    .on_press(Message::ToggleSearch),
+            )
+            .spacing(10)
+            .align_y(Vertical::Center)
+            .padding(padding::bottom(5).top(5))
+        });
+
+        let rows = column![]
+            .push_maybe(tabs)
+            .push(
+                container(header)
+                    .style(|_| {
+                        container::Style::default()
+                            .border(border::width(1).color(iced_color(crate::Color::Cyan)))
+                    })
+                    .padding(padding::bottom(5).top(5)),
+            )
+            .push_maybe(print)
+            .push_maybe(search)
+            .push_maybe(errors)
+            .push(container(image).style(|_| {
+                container::Style::default()
+                    // .background(Background::Color(iced_color(crate::Color::Magenta)))
+                    .border(border::width(1).color(iced_color(crate::Color::Cyan)))
+            }));
+
+        let content = container(rows)
             .padding(10)
             .width(Length::Fill)
+            .height(Length::Fill);
+        // .style(|_| {
+        //     container::Style::default()
+        //         .background(Background::Color(iced_color(crate::Color::Yellow)))
+        // })
+        match self.cheatsheet {
+            true => stack![content, self.cheatsheet()].into(),
+            false => content.into(),
+        }
+    }
+
+    /// Keyboard shortcuts of the actions, shown over the blueprint.
+    fn cheatsheet(&self) -> Element<'_, Message> {
+        let shortcuts = column(Action::ALL.into_iter().map(|action| {
+            let shortcuts = self
+                .config
+                .keymap
+                .shortcuts(action)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            text(format!("{shortcuts:<20} {}", action.description())).into()
+        }));
+        let cheatsheet = column![
+            text("shortcuts"),
+            shortcuts,
+            text("ctrl (held)           move the blueprint with the mouse"),
+            button(text("close")).on_press(Message::ToggleCheatsheet),
+        ]
+        .spacing(10);
+        opaque(center(
+            container(cheatsheet)
+                .padding(20)
+                .style(container::rounded_box),
+        ))
+    }
+
+    /// Tree of the shapes and tags of the blueprint of `tab`, centering the view on the entry
+    /// clicked.
+    fn sidebar<'a>(&self, tab: &'a Tab) -> Element<'a, Message> {
+        let entry = |label: String, selection: Selection| {
+            let style = if tab.selection.as_ref() == Some(&selection) {
+                button::primary
+            } else {
+                button::text
+            };
+            button(text(label))
+                .style(style)
+                .on_press(Message::Select(selection))
+                .into()
+        };
+        let heading = |label: &str, count: usize, expanded: bool, message: Message| {
+            let arrow = if expanded { "▾" } else { "▸" };
+            button(text(format!("{arrow} {label} ({count})")))
+                .style(button::text)
+                .on_press(message)
+        };
+
+        let shapes = tab.raw_blueprint.shapes_iter().map(|shape| {
+            let label = match shape.name() {
+                Some(name) => format!("shape {name}"),
+                None => {
+                    let line = shape.edges_iter().next().map_or(0, |edge| edge.span.line);
+                    format!("shape at line {line}")
+                }
+            };
+            entry(label, Selection::Shape(shape.id()))
+        });
+        let tags = tab.raw_blueprint.tags_iter().map(|(tag, point)| {
+            entry(
+                format!("#{tag} @{},{}", point.x, point.y),
+                Selection::Tag(tag.to_string()),
+            )
+        });
+
+        let tree = column![heading(
+            "shapes",
+            tab.raw_blueprint.shapes_iter().count(),
+            self.shapes_expanded,
+            Message::ToggleShapes
+        )]
+        .push_maybe(
+            self.shapes_expanded
+                .then(|| column(shapes).padding(padding::left(10))),
+        )
+        .push(heading(
+            "tags",
+            tab.raw_blueprint.tags_iter().count(),
+            self.tags_expanded,
+            Message::ToggleTags,
+        ))
+        .push_maybe(
+            self.tags_expanded
+                .then(|| column(tags).padding(padding::left(10))),
+        );
+
+        container(scrollable(tree))
+            .width(Length::Fixed(220.))
             .height(Length::Fill)
-            // .style(|_| {
-            //     container::Style::default()
-            //         .background(Background::Color(Color::from(crate::Color::Yellow)))
-            // })
+            .style(|_| {
+                container::Style::default()
+                    .border(border::width(1).color(iced_color(crate::Color::Cyan)))
+            })
             .into()
     }
 
+    fn theme(&self) -> Theme {
+        match self.config.theme {
+            ThemeMode::Light => Theme::Light,
+            ThemeMode::Dark => Theme::Dark,
+        }
+    }
+
     fn title(&self) -> String {
         "Blueprint".into()
     }
@@ -271,71 +1400,451 @@ pub enum Message {
     ChangeMouseMode(MouseMode),
     StorePosition,
     DropPosition,
-    TranslateUp,
-    TranslateLeft,
-    TranslateDown,
-    TranslateRight,
-    BlueprintUpdated(crate::Blueprint),
+    ClearMeasures,
+    PinMeasurement,
+    UnpinMeasurement,
+    /// Copies the blueprint coordinates of the mouse, as an absolute position of the blueprint
+    /// syntax.
+    CopyPosition,
+    /// Copies the measurement, as an edge of the blueprint syntax.
+    CopyMeasurement,
+    /// Copies the description of the edge selected.
+    CopyEdge,
+    ModifiersChanged(keyboard::Modifiers),
+    StartPan,
+    EndPan,
+    /// Moves the view by a step in the direction.
+    Translate(Vector),
+    ToggleTheme,
+    ToggleEditor,
+    ToggleSidebar,
+    ToggleFullscreen,
+    ToggleCheatsheet,
+    ToggleRectangle,
+    ToggleConstruction,
+    ToggleWidths,
+    ToggleShapes,
+    ToggleTags,
+    Select(Selection),
+    CanvasResized(Rectangle),
+    Edit(text_editor::Action),
+    Tick(Instant),
+    SelectTab(usize),
+    NextTab,
+    PreviousTab,
+    CloseTab(usize),
+    /// Shows the blueprint loaded before the one shown.
+    PreviousVersion,
+    NextVersion,
+    Reload,
+    TogglePrint,
+    PrintPaper(Paper),
+    PrintScale(String),
+    /// Prints the page previewed.
+    Print,
+    /// Takes a screenshot of the window, to save what the canvas shows.
+    Screenshot,
+    Screenshotted(window::Screenshot),
+    ToggleSearch,
+    /// Name of the tag searched for, as typed.
+    Search(String),
+    /// Centers the view on the tag, and flashes it.
+    FindTag(String),
+    BlueprintUpdated(PathBuf, Option<crate::Blueprint>, Vec<BlueprintError>),
+    Watched(PathBuf, WatchStatus),
+    Failed(BlueprintError),
     SetSender(Sender<Command>),
+    /// Key pressed, and the key typed with the modifiers, triggering the action it is bound to.
+    KeyPressed(keyboard::Key, keyboard::Key, keyboard::Modifiers),
+    KeyReleased(keyboard::Key, keyboard::Modifiers),
+    /// Forgets the keys held, whose release is not received once the window lost the focus.
+    ReleaseKeys,
+}
+
+impl From<Action> for Message {
+    fn from(value: Action) -> Self {
+        match value {
+            Action::ZoomIn => Message::ZoomIn,
+            Action::ZoomOut => Message::ZoomOut,
+            Action::ZoomReset => Message::ZoomReset,
+            Action::PanUp => Message::Translate(Vector::new(0., -1.)),
+            Action::PanLeft => Message::Translate(Vector::new(-1., 0.)),
+            Action::PanDown => Message::Translate(Vector::new(0., 1.)),
+            Action::PanRight => Message::Translate(Vector::new(1., 0.)),
+            Action::Measure => Message::StorePosition,
+            Action::ClearMeasures => Message::ClearMeasures,
+            Action::PinMeasurement => Message::PinMeasurement,
+            Action::UnpinMeasurement => Message::UnpinMeasurement,
+            Action::CopyPosition => Message::CopyPosition,
+            Action::CopyMeasurement => Message::CopyMeasurement,
+            Action::ToggleRectangle => Message::ToggleRectangle,
+            Action::ToggleConstruction => Message::ToggleConstruction,
+            Action::ToggleWidths => Message::ToggleWidths,
+            Action::ToggleTheme => Message::ToggleTheme,
+            Action::ToggleEditor => Message::ToggleEditor,
+            Action::ToggleSidebar => Message::ToggleSidebar,
+            Action::ToggleFullscreen => Message::ToggleFullscreen,
+            Action::NextTab => Message::NextTab,
+            Action::PreviousTab => Message::PreviousTab,
+            Action::PreviousVersion => Message::PreviousVersion,
+            Action::NextVersion => Message::NextVersion,
+            Action::Reload => Message::Reload,
+            Action::Print => Message::TogglePrint,
+            Action::Screenshot => Message::Screenshot,
+            Action::Search => Message::ToggleSearch,
+            Action::Cheatsheet => Message::ToggleCheatsheet,
+        }
+    }
+}
+
+/// Highlighter of the editor, highlighting a range of bytes of the source: the command the edge
+/// hovered was drawn by.
+struct SourceHighlighter {
+    range: Option<Range<usize>>,
+    /// Byte offsets of the start of the lines highlighted so far, the last one being the start of
+    /// the line highlighted next.
+    line_starts: Vec<usize>,
+}
+
+impl Highlighter for SourceHighlighter {
+    type Settings = Option<Range<usize>>;
+    type Highlight = ();
+    type Iterator<'a> = std::option::IntoIter<(Range<usize>, ())>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            range: settings.clone(),
+            line_starts: vec![0],
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.range = new_settings.clone();
+        self.line_starts.truncate(1);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.line_starts.truncate(line + 1);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let start = *self.line_starts.last().expect("the first line starts at 0");
+        let end = start + line.len();
+        self.line_starts.push(end + 1);
+        self.range
+            .as_ref()
+            .filter(|range| range.start < end && range.end > start)
+            .map(|range| {
+                (
+                    range.start.max(start) - start..range.end.min(end) - start,
+                    (),
+                )
+            })
+            .into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.line_starts.len() - 1
+    }
 }
 
+/// Canvas drawing a blueprint at the zoom level of its tab. The blueprint and its edges are kept
+/// in blueprint coordinates, their points being scaled as they are drawn.
 #[derive(Debug)]
-struct DrawableBlueprint {
-    blueprint: crate::Blueprint,
-    highlighted: Option<(Edge, crate::domain::Point)>,
+struct DrawableBlueprint<'a> {
+    blueprint: &'a crate::Blueprint,
+    /// The blueprint composed according to its layers, whose edges are the ones drawn.
+    composed: &'a IndexedBlueprint,
+    highlighted: Option<(Edge, blueprint_core::domain::Point)>,
+    /// Whether the transparent edges are drawn, faint and dashed.
+    construction: bool,
+    /// Edges added and removed by the last reload, and the opacity they are highlighted with.
+    diff: Option<(&'a [Edge], &'a [Edge], f32)>,
+    /// Whether the widths of the edges are scaled with the zoom, as on printed plans.
+    scaled_widths: bool,
+    /// How far the flash of the selection is, from 0 to 1.
+    flash: Option<f32>,
+    /// Page the blueprint is printed on.
+    page: Option<Rectangle>,
     translation: Vector,
     zoom_level: ZoomLevel,
-    mouse_position: Point,
-    distances: Option<(Point, Distances)>,
+    /// Measurements drawn, from a point to another, the one in progress first.
+    measurements: Vec<(Point, Point, Distances)>,
+    selection: Option<Selection>,
+    /// Corners of the region whose area is measured, and its area.
+    area: Option<(Vec<Point>, f32)>,
+    /// Corners of the rectangle selected, whose edges are highlighted.
+    rectangle: Option<(Point, Point)>,
 }
 
-impl<Message> canvas::Program<Message> for DrawableBlueprint {
-    type State = ();
+impl DrawableBlueprint<'_> {
+    /// Point of the canvas where `point` of the blueprint is drawn.
+    fn point(&self, point: &crate::Point) -> Point {
+        let zoom = self.zoom_level.scale_factor();
+        Point::new(point.x * zoom, point.y * zoom)
+    }
+
+    /// Path drawing `edge`, its curve followed by straight edges within [`FLATNESS`].
+    fn path(&self, edge: &Edge) -> Path {
+        match edge.curve {
+            Curve::Line => Path::line(self.point(&edge.from), self.point(&edge.to)),
+            _ => Path::new(|builder| {
+                builder.move_to(self.point(&edge.from));
+                for segment in edge.flatten(FLATNESS) {
+                    builder.line_to(self.point(&segment.to));
+                }
+            }),
+        }
+    }
+}
+
+impl canvas::Program<Message> for DrawableBlueprint<'_> {
+    /// Bounds of the canvas, the UI being told when they change.
+    type State = Option<Rectangle>;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        _event: canvas::Event,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if *state == Some(bounds) {
+            return (event::Status::Ignored, None);
+        }
+        *state = Some(bounds);
+        (event::Status::Ignored, Some(Message::CanvasResized(bounds)))
+    }
 
     fn draw(
         &self,
         _state: &Self::State,
         renderer: &Renderer,
-        _theme: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
         _cursor: Cursor,
     ) -> Vec<Geometry> {
         let mut frame = canvas::Frame::new(renderer, bounds.size());
         frame.translate(self.translation);
 
-        for shape in self.blueprint.shapes_iter() {
-            for edge in shape.edges_iter() {
-                if edge.color.is_transparent() {
-                    continue;
+        if let Some(page) = self.page {
+            frame.fill_rectangle(page.position(), page.size(), Color::new(0.5, 0.5, 0.5, 0.1));
+            frame.stroke(
+                &Path::rectangle(page.position(), page.size()),
+                Stroke::default().with_color(Color::new(0.5, 0.5, 0.5, 1.)),
+            );
+        }
+
+        // only the edges in view are drawn, along with the ones a few pixels away whose stroke
+        // may reach into it
+        let zoom = self.zoom_level.scale_factor();
+        let margin = VIEW_MARGIN / zoom;
+        let visible = self.composed.edges_in_rect(
+            crate::Point::new(
+                -self.translation.x / zoom - margin,
+                -self.translation.y / zoom - margin,
+            ),
+            crate::Point::new(
+                (bounds.width - self.translation.x) / zoom + margin,
+                (bounds.height - self.translation.y) / zoom + margin,
+            ),
+        );
+        for edge in visible {
+            let line = self.path(edge);
+            if edge.color.is_transparent() {
+                if self.construction {
+                    frame.stroke(
+                        &line,
+                        Stroke {
+                            line_dash: LineDash {
+                                segments: &[2., 4.],
+                                offset: 0,
+                            },
+                            ..Stroke::default().with_color(Color::new(0.5, 0.5, 0.5, 0.5))
+                        },
+                    );
                 }
+                continue;
+            }
+
+            let width = match self.scaled_widths {
+                true => edge.width * self.zoom_level.scale_factor(),
+                false => edge.width,
+            };
+            let segments = edge.style.pattern().map(|segment| segment * width.max(1.));
+
+            // black and white are swapped on dark backgrounds, where black edges would vanish
+            let color = match edge.color {
+                crate::Color::Black if theme.extended_palette().is_dark => crate::Color::White,
+                crate::Color::White if theme.extended_palette().is_dark => crate::Color::Black,
+                color => color,
+            };
+            let mut stroke = Stroke::default()
+                .with_color(iced_color(color))
+                .with_width(width);
+            if edge.style != LineStyle::Solid {
+                stroke.line_dash = LineDash {
+                    segments: &segments,
+                    offset: (edge.dash_offset * self.zoom_level.scale_factor()) as usize,
+                };
+            }
+            frame.stroke(&line, stroke);
+        }
 
-                let line = Path::line(edge.from.into(), edge.to.into());
+        if let Some((added, removed, opacity)) = self.diff {
+            // removed edges are ghosted, behind the added ones
+            for (edges, color, opacity) in [
+                (removed, Color::new(1., 0., 0., 1.), opacity * 0.5),
+                (added, Color::new(0., 0.8, 0., 1.), opacity),
+            ] {
+                for edge in edges {
+                    let line = self.path(edge);
+                    frame.stroke(
+                        &line,
+                        Stroke::default()
+                            .with_color(Color {
+                                a: opacity,
+                                ..color
+                            })
+                            .with_width(edge.width + 2.),
+                    );
+                }
+            }
+        }
+
+        match &self.selection {
+            Some(Selection::Shape(id)) => {
+                for edge in self
+                    .blueprint
+                    .shapes_iter()
+                    .filter(|shape| shape.id() == *id)
+                    .flat_map(|shape| shape.edges_iter())
+                {
+                    let line = self.path(edge);
+                    frame.stroke(
+                        &line,
+                        Stroke::default()
+                            .with_color(iced_color(crate::Color::Cyan))
+                            .with_width(edge.width + 2.),
+                    );
+                }
+            }
+            Some(Selection::Edge(shape, edge)) => {
+                if let Some(edge) = self
+                    .blueprint
+                    .shapes_iter()
+                    .nth(*shape)
+                    .and_then(|shape| shape.edges_iter().nth(*edge))
+                {
+                    frame.stroke(
+                        &self.path(edge),
+                        Stroke::default()
+                            .with_color(iced_color(crate::Color::Cyan))
+                            .with_width(edge.width + 2.),
+                    );
+                }
+            }
+            Some(Selection::Tag(name)) => {
+                if let Some((_, point)) = self.blueprint.tags_iter().find(|(tag, _)| tag == name) {
+                    frame.stroke(
+                        &Path::circle(self.point(point), 5.),
+                        Stroke::default()
+                            .with_color(iced_color(crate::Color::Cyan))
+                            .with_width(2.),
+                    );
+                    // a ring spreading from the tag found, fading out
+                    if let Some(flash) = self.flash.filter(|flash| *flash < 1.) {
+                        frame.stroke(
+                            &Path::circle(self.point(point), 5. + 30. * flash),
+                            Stroke::default()
+                                .with_color(iced_color(crate::Color::Cyan).scale_alpha(1. - flash))
+                                .with_width(3.),
+                        );
+                    }
+                }
+            }
+            None => {}
+        }
 
-                frame.stroke(&line, Stroke::default().with_color(edge.color.into()));
+        if let Some((corner, opposite)) = self.rectangle {
+            let zoom = self.zoom_level.scale_factor();
+            let within = self.blueprint.edges_within(
+                crate::Point::new(corner.x / zoom, corner.y / zoom),
+                crate::Point::new(opposite.x / zoom, opposite.y / zoom),
+            );
+            for edge in within {
+                let line = self.path(edge);
+                frame.stroke(
+                    &line,
+                    Stroke::default()
+                        .with_color(iced_color(crate::Color::Cyan))
+                        .with_width(edge.width + 2.),
+                );
             }
+
+            let top_left = Point::new(corner.x.min(opposite.x), corner.y.min(opposite.y));
+            let size = Size::new((opposite.x - corner.x).abs(), (opposite.y - corner.y).abs());
+            frame.stroke(
+                &Path::rectangle(top_left, size),
+                Stroke {
+                    line_dash: LineDash {
+                        segments: &[4., 4.],
+                        offset: 0,
+                    },
+                    ..Stroke::default().with_color(iced_color(crate::Color::Cyan))
+                },
+            );
+        }
+
+        if let Some((points, area)) = &self.area {
+            let region = Path::new(|path| {
+                for (i, point) in points.iter().enumerate() {
+                    if i == 0 {
+                        path.move_to(*point);
+                    } else {
+                        path.line_to(*point);
+                    }
+                }
+                path.close();
+            });
+            frame.fill(&region, Color::new(1., 0., 1., 0.2));
+            frame.stroke(
+                &region,
+                Stroke::default().with_color(Color::new(1., 0., 1., 1.0)),
+            );
+
+            let center = points.iter().fold(Point::ORIGIN, |center, point| {
+                Point::new(
+                    center.x + point.x / points.len() as f32,
+                    center.y + point.y / points.len() as f32,
+                )
+            });
+            let mut label = Text::from(format!("{area}"));
+            label.horizontal_alignment = Horizontal::Center;
+            label.vertical_alignment = Vertical::Center;
+            label.position = center;
+            frame.fill_text(label);
         }
 
         if let Some((edge, point)) = &self.highlighted {
-            let line = Path::line(edge.from.into(), edge.to.into());
+            let line = self.path(edge);
 
             frame.stroke(
                 &line,
-                Stroke::default().with_color(crate::Color::Red.into()),
+                Stroke::default().with_color(iced_color(crate::Color::Red)),
             );
 
-            let point = Path::circle(point.into(), 2.);
+            let point = Path::circle(self.point(point), 2.);
             frame.fill(
                 &point,
                 Fill {
-                    style: Style::Solid(crate::Color::Red.into()),
+                    style: Style::Solid(iced_color(crate::Color::Red)),
                     ..Default::default()
                 },
             );
         }
 
-        if let Some((fixed_position, distances)) = self.distances {
-            let top_left = fixed_position.sub(self.translation);
-            let bottom_right = self.mouse_position.sub(self.translation);
+        for &(top_left, bottom_right, distances) in &self.measurements {
             let top_right = Point::new(bottom_right.x, top_left.y);
             let bottom_left = Point::new(top_left.x, bottom_right.y);
 
@@ -392,33 +1901,198 @@ impl<Message> canvas::Program<Message> for DrawableBlueprint {
     }
 }
 
-impl From<crate::Point> for Point {
-    fn from(value: crate::domain::Point) -> Self {
-        Self {
-            x: value.x,
-            y: value.y,
-        }
+fn iced_color(color: crate::Color) -> Color {
+    let (r, g, b, a) = color.as_rgba();
+    Color {
+        r: r as f32 / 255.,
+        g: g as f32 / 255.,
+        b: b as f32 / 255.,
+        a: a as f32 / 255.,
     }
 }
 
-impl From<&crate::Point> for Point {
-    fn from(value: &crate::domain::Point) -> Self {
-        Self {
-            x: value.x,
-            y: value.y,
+/// Millimeters per blueprint unit of `scale`, as typed.
+fn print_scale(scale: &str) -> Result<f32, String> {
+    scale
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|scale| *scale > 0.)
+        .ok_or_else(|| format!("the scale must be a positive number, got {scale}"))
+}
+
+/// Panel describing the edge selected, of `blueprint`.
+fn inspector<'a>(edge: &Edge, blueprint: &crate::Blueprint) -> Element<'a, Message> {
+    let description = column(
+        describe(edge, blueprint)
+            .into_iter()
+            .map(|line| text(line).into()),
+    )
+    .push(button(text("copy")).on_press(Message::CopyEdge))
+    .spacing(5)
+    .padding(5);
+    container(scrollable(description))
+        .width(Length::Fixed(220.))
+        .height(Length::Fill)
+        .style(|_| {
+            container::Style::default()
+                .border(border::width(1).color(iced_color(crate::Color::Cyan)))
+        })
+        .into()
+}
+
+/// Ends, length, angle, attributes and source of `edge`, of `blueprint`, a line each.
+fn describe(edge: &Edge, blueprint: &crate::Blueprint) -> Vec<String> {
+    let point = |point: crate::Point| format!("@{},{}", coordinate(point.x), coordinate(point.y));
+    let color = match edge.color.name() {
+        Some(name) => name.to_string(),
+        None => {
+            let (r, g, b, a) = edge.color.as_rgba();
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
         }
+    };
+    let style = match edge.style {
+        LineStyle::Solid => edge.style.name().to_string(),
+        _ => format!(
+            "{}, offset {}",
+            edge.style.name(),
+            coordinate(edge.dash_offset)
+        ),
+    };
+
+    let mut lines = vec![
+        format!("from: {}", point(edge.from)),
+        format!("to: {}", point(edge.to)),
+        format!("length: {}", coordinate(edge.length())),
+        format!("angle: {}°", coordinate(edge.angle())),
+        format!("color: {color}"),
+        format!("width: {}", coordinate(edge.width)),
+        format!("style: {style}"),
+    ];
+    if let Some(layer) = edge
+        .layer
+        .and_then(|layer| blueprint.layers_iter().nth(layer))
+    {
+        lines.push(format!("layer: {}", layer.name));
     }
+    for (key, value) in blueprint.attributes(edge).into_iter().flatten() {
+        lines.push(format!("{key}: {value}"));
+    }
+    lines.push(format!(
+        "source: line {}, column {}",
+        edge.span.line, edge.span.column
+    ));
+    lines
 }
 
-impl From<crate::Color> for Color {
-    fn from(value: crate::Color) -> Self {
-        let (r, g, b, a) = value.as_rgba();
-        Self {
-            r: r as f32 / 255.,
-            g: g as f32 / 255.,
-            b: b as f32 / 255.,
-            a: a as f32 / 255.,
-        }
+/// How far `name` is from the `query` typed, whose characters it contains in order, ignoring the
+/// case: the number of characters skipped to find them, or `None` when it does not contain them.
+fn fuzzy_match(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    let mut characters = name.chars().enumerate();
+    let mut distance = 0;
+    let mut next = 0;
+    for character in query.to_lowercase().chars() {
+        let (index, _) = characters.find(|(_, c)| *c == character)?;
+        distance += index - next;
+        next = index + 1;
+    }
+    Some(distance)
+}
+
+/// Prints `blueprint` on `paper`, at `scale` millimeters per blueprint unit, with `lp`.
+fn print(blueprint: &crate::Blueprint, paper: Paper, scale: f32) -> Result<(), String> {
+    let mut document = Vec::new();
+    PostScriptDocument::from(blueprint)
+        .scale(scale)
+        .paper(paper)
+        .write_to(&mut document)
+        .map_err(|e| e.to_string())?;
+
+    let mut lp = std::process::Command::new("lp")
+        .arg("-o")
+        .arg(format!("media={paper}"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("lp: {e}"))?;
+    if let Some(mut stdin) = lp.stdin.take() {
+        stdin.write_all(&document).map_err(|e| format!("lp: {e}"))?;
+    }
+    match lp.wait().map_err(|e| format!("lp: {e}"))? {
+        status if status.success() => Ok(()),
+        status => Err(format!("lp: {status}")),
+    }
+}
+
+/// Saves the part of `screenshot` within `bounds`, in logical pixels, to a PNG file of the working
+/// directory named after the time it is taken.
+fn save_screenshot(screenshot: &window::Screenshot, bounds: Rectangle) -> Result<PathBuf, String> {
+    let scale = screenshot.scale_factor as f32;
+    let (x, y) = ((bounds.x * scale) as u32, (bounds.y * scale) as u32);
+    let region = Rectangle {
+        x,
+        y,
+        width: ((bounds.width * scale) as u32).min(screenshot.size.width.saturating_sub(x)),
+        height: ((bounds.height * scale) as u32).min(screenshot.size.height.saturating_sub(y)),
+    };
+    let screenshot = screenshot.crop(region).map_err(|e| e.to_string())?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+    let path = PathBuf::from(format!("blueprint-{}.png", timestamp(now.as_secs())));
+    let file = fs::File::create(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        screenshot.size.width,
+        screenshot.size.height,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&screenshot.bytes))
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// UTC date and time of `seconds` since the Unix epoch, e.g. `20261016-141320`.
+fn timestamp(seconds: u64) -> String {
+    // days to civil dates, from http://howardhinnant.github.io/date_algorithms.html
+    let days = seconds / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    let time = seconds % 86400;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// `value` rounded to the hundredth, as written in blueprints.
+fn coordinate(value: f32) -> String {
+    // adding zero turns -0 into 0
+    ((value * 100.).round() / 100. + 0.).to_string()
+}
+
+/// Pixels the view moves by for a step of the pan keys at `zoom_level`: at least one, and ten times
+/// more with shift held.
+fn pan_step(config: &Config, modifiers: keyboard::Modifiers, zoom_level: ZoomLevel) -> f32 {
+    let step = (config.pan_step * zoom_level.scale_factor()).max(1.);
+    match modifiers.shift() {
+        true => step * 10.,
+        false => step,
     }
 }
 
@@ -497,12 +2171,88 @@ impl Distances {
 
 #[cfg(test)]
 mod tests {
-    use crate::ui::ZoomLevel;
+    use crate::ui::{
+        SourceHighlighter, ZoomLevel, coordinate, describe, fuzzy_match, iced_color, timestamp,
+    };
+    use blueprint_core::loader::load_blueprint_str;
     use iced::Color;
+    use iced::advanced::text::Highlighter;
+
+    #[test]
+    fn test_coordinate() {
+        assert_eq!(coordinate(12.), "12");
+        assert_eq!(coordinate(-0.001), "0");
+        assert_eq!(coordinate(12.345), "12.35");
+    }
+
+    #[test]
+    fn test_describe() {
+        let (blueprint, _) = load_blueprint_str(
+            "10,0 [color:red, style:dashed] 0,-10",
+            std::path::Path::new("test.bp"),
+            false,
+        );
+        let blueprint = blueprint.unwrap();
+        let edge = blueprint
+            .shapes_iter()
+            .next()
+            .unwrap()
+            .edges_iter()
+            .nth(1)
+            .unwrap();
+        assert_eq!(
+            describe(edge, &blueprint),
+            vec![
+                "from: @10,10",
+                "to: @10,0",
+                "length: 10",
+                "angle: 90°",
+                "color: red",
+                "width: 1",
+                "style: dashed, offset 0",
+                "source: line 1, column 32",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_highlighter() {
+        let mut highlighter = SourceHighlighter::new(&Some(4..12));
+        let mut highlight = |line| highlighter.highlight_line(line).collect::<Vec<_>>();
+        assert_eq!(highlight("abc"), vec![]);
+        assert_eq!(highlight("defgh"), vec![(0..5, ())]);
+        assert_eq!(highlight("ijk"), vec![(0..2, ())]);
+
+        highlighter.change_line(1);
+        assert_eq!(highlighter.current_line(), 1);
+        assert_eq!(
+            highlighter.highlight_line("de").collect::<Vec<_>>(),
+            vec![(0..2, ())]
+        );
+        highlighter.update(&None);
+        assert_eq!(highlighter.current_line(), 0);
+        assert_eq!(highlighter.highlight_line("abc").count(), 0);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "19700101-000000");
+        assert_eq!(timestamp(951_782_400), "20000229-000000");
+        assert_eq!(timestamp(1_792_160_000), "20261016-141320");
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("kit", "kitchen"), Some(0));
+        assert_eq!(fuzzy_match("KTN", "kitchen"), Some(4));
+        assert_eq!(fuzzy_match("hen", "kitchen"), Some(4));
+        assert_eq!(fuzzy_match("tk", "kitchen"), None);
+        assert_eq!(fuzzy_match("", "kitchen"), Some(0));
+    }
 
     #[test]
     fn test_color() {
-        let color = Color::from(crate::Color::Red);
+        let color = iced_color(crate::Color::Red);
         assert_eq!(color, Color::from_rgba(1., 0., 0., 1.));
     }
 